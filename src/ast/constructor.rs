@@ -5,12 +5,111 @@ use std::{
 
 use crate::{
     ast::operations::Operator,
-    errors::associate::CodeAssociate,
+    errors::{
+        associate::CodeAssociate,
+        error::{std_error, StdErrors},
+    },
     lexer::{lexer::empty_associate, tokens::Tokens},
 };
 
 use super::operations::{ASTOperation, NodeStatement};
 
+/// Splits a name with an optional type annotation, such as `amount: int`.
+fn annotated(name: &str) -> (String, Option<String>) {
+    match name.split_once(':') {
+        Some((name, annotation)) => (name.trim().to_string(), Some(annotation.trim().to_string())),
+        None => (name.trim().to_string(), None),
+    }
+}
+
+/// Splits a list such as `check, x as counter` into each name and the name it is bound to.
+fn name_list(list: &str) -> Vec<(String, String)> {
+    list.split(',')
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty())
+        .map(|name| match name.split_once(" as ") {
+            Some((name, alias)) => (name.trim().to_string(), alias.trim().to_string()),
+            None => (name.to_string(), name.to_string()),
+        })
+        .collect()
+}
+
+/// Splits the module a list is imported or exported from, such as `from "utils/math"`.
+fn module_path(from: &str, associate: &CodeAssociate) -> String {
+    let path = from.trim().strip_prefix("from ").map(|path| path.trim());
+    let Some(path) = path.and_then(|path| path.strip_prefix('"')?.strip_suffix('"')) else {
+        std_error(StdErrors::Syntax(
+            "Expected: from \"<module>\"".to_string(),
+            associate.clone(),
+        ));
+        exit(1);
+    };
+    path.to_string()
+}
+
+/// Splits a braced list such as `{ check, x as counter }` from what follows it.
+fn braced_list<'a>(list: &'a str, associate: &CodeAssociate) -> (Vec<(String, String)>, &'a str) {
+    let Some((list, rest)) = list.trim_start_matches('{').split_once('}') else {
+        std_error(StdErrors::Syntax(
+            "Expected: }".to_string(),
+            associate.clone(),
+        ));
+        exit(1);
+    };
+    (name_list(list), rest)
+}
+
+/// Splits an import such as `{ check, x as counter } from "utils/math"` into the module path
+/// and the imported names with the names they are bound to. A bare `math` imports everything.
+fn import_list(import: &str, associate: &CodeAssociate) -> (String, Option<Vec<(String, String)>>) {
+    if !import.starts_with('{') {
+        return (import.trim_matches('"').to_string(), None);
+    }
+    let (names, from) = braced_list(import, associate);
+    (module_path(from, associate), Some(names))
+}
+
+/// The operation of `export { a, b as c }`, `export { a } from "x"` or `export * from "x"`.
+fn export_list(export: &str, associate: CodeAssociate) -> ASTOperation {
+    if let Some(from) = export.strip_prefix('*') {
+        return ASTOperation::ReExport(module_path(from, &associate), None, associate);
+    }
+    let (names, from) = braced_list(export, &associate);
+    if from.trim().is_empty() {
+        return ASTOperation::ExportList(names, associate);
+    }
+    ASTOperation::ReExport(module_path(from, &associate), Some(names), associate)
+}
+
+/// How tightly an operator holds its operands: comparisons compare the results of arithmetic,
+/// and sums add up the results of products.
+fn precedence(operator: &Operator) -> u8 {
+    match operator {
+        Operator::Multiply | Operator::Divide | Operator::Modulus => 3,
+        Operator::Add | Operator::Subtract => 2,
+        Operator::And | Operator::Or => 0,
+        _ => 1,
+    }
+}
+
+/// `left <operator> right`, for a `left` parsed before. An operator holding its operands
+/// tighter than the last one of `left` takes the last operand of `left` instead.
+fn binary(left: Box<ASTOperation>, operator: Operator, right: Box<ASTOperation>) -> ASTOperation {
+    match *left {
+        ASTOperation::Operation(first, inner, second, associate)
+            if precedence(&inner) < precedence(&operator) =>
+        {
+            ASTOperation::Operation(
+                first,
+                inner,
+                Box::new(binary(second, operator, right)),
+                associate,
+            )
+        }
+        _ => ASTOperation::Operation(left, operator, right, empty_associate()),
+    }
+}
+
 pub struct AST {
     statements: Vec<ASTOperation>,
     tokens: Vec<Tokens>,
@@ -57,6 +156,36 @@ impl AST {
         (tokens, forwardness)
     }
 
+    /// Collects the tokens of the block opened at the current index, skipping over nested
+    /// blocks so that the matching right brace ends it.
+    pub fn get_block_tokens(&self) -> (Vec<Tokens>, usize) {
+        let mut tokens = vec![];
+        let mut forwardness = 0;
+        let mut depth = 0;
+        while self.tokens.len() != self.index + forwardness {
+            let current_token = self.tokens[self.index + forwardness].clone();
+            match current_token {
+                Tokens::LBrace(_) => {
+                    depth += 1;
+                    if depth == 1 {
+                        forwardness += 1;
+                        continue;
+                    }
+                }
+                Tokens::RBrace(_) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            tokens.push(current_token);
+            forwardness += 1;
+        }
+        (tokens, forwardness)
+    }
+
     pub fn get_tokens_until_mult(
         &self,
         original_tokens: Vec<Tokens>,
@@ -82,6 +211,25 @@ impl AST {
         (sent_tokens, forwardness, ending_token)
     }
 
+    /// Attaches a method call such as `.count(1)` that directly follows a call, so that
+    /// `new Item("stick").count(1).name("Wand")` runs each method on the previous result.
+    pub fn chain(&mut self, operation: ASTOperation) -> ASTOperation {
+        if let Tokens::Period(tokens, associate) = self.peek(1) {
+            let statements = self.get_statements_from_tokens(&tokens, associate.clone());
+            if statements.len() != 1 {
+                eprintln!("Expected single statement.");
+                exit(1);
+            }
+            self.index += 1;
+            return ASTOperation::Chain(
+                Box::new(operation),
+                Box::new(statements[0].clone()),
+                associate,
+            );
+        }
+        operation
+    }
+
     pub fn get_statements_from_tokens(
         &self,
         tokens: &Vec<Tokens>,
@@ -119,13 +267,21 @@ impl AST {
                     self.index += forwardness;
 
                     let operation = if name.starts_with("*") {
+                        let (name, annotation) = annotated(&name.replacen("*", "", 1));
                         ASTOperation::StaticVariable(
-                            name.replacen("*", "", 1),
+                            name,
+                            annotation,
                             statements.to_vec(),
                             associate.clone(),
                         )
                     } else {
-                        ASTOperation::AssignVariable(name, statements.to_vec(), associate.clone())
+                        let (name, annotation) = annotated(&name);
+                        ASTOperation::AssignVariable(
+                            name,
+                            annotation,
+                            statements.to_vec(),
+                            associate.clone(),
+                        )
                     };
 
                     if export_next {
@@ -137,18 +293,41 @@ impl AST {
                     }
                 }
                 Tokens::Bracket(tokens, associate) => {
-                    let statements = self.get_statements_from_tokens(&tokens, associate.clone());
+                    // the elements are kept flat so a list is never mistaken for arguments
+                    let mut ast = AST::new(tokens.to_vec());
+                    ast.generate();
                     self.statements
-                        .push(ASTOperation::Set(statements, associate));
+                        .push(ASTOperation::List(ast.flush().to_vec(), associate));
                 }
                 Tokens::Number(str, associate) => {
+                    if str.contains('.') {
+                        self.statements.push(ASTOperation::LiteralDecimal(
+                            str.parse().unwrap(),
+                            associate,
+                        ));
+                    } else {
+                        self.statements
+                            .push(ASTOperation::LiteralNumber(str.parse().unwrap(), associate));
+                    }
+                }
+                Tokens::Coordinate(str, associate) => {
                     self.statements
-                        .push(ASTOperation::LiteralNumber(str.parse().unwrap(), associate));
+                        .push(ASTOperation::LiteralCoordinate(str, associate));
                 }
                 Tokens::DblQuote(str, associate) => {
                     self.statements
                         .push(ASTOperation::LiteralString(str, associate));
                 }
+                Tokens::Block(position_tokens, associate) => {
+                    let position =
+                        self.get_statements_from_tokens(&position_tokens, associate.clone());
+                    self.statements
+                        .push(ASTOperation::BlockPredicate(position, associate));
+                }
+                Tokens::Selector(selector, associate) => {
+                    self.statements
+                        .push(ASTOperation::LiteralSelector(selector, associate));
+                }
                 Tokens::Bool(bool, associate) => {
                     self.statements
                         .push(ASTOperation::LiteralBool(bool, associate));
@@ -156,8 +335,17 @@ impl AST {
                 Tokens::Export(associate) => {
                     export_next = true;
                 }
-                Tokens::Import(name, associate) => {
-                    self.statements.push(ASTOperation::Import(name, associate));
+                Tokens::ExportList(export, associate) => {
+                    self.statements.push(export_list(&export, associate));
+                }
+                Tokens::Import(import, associate) => {
+                    let (path, names) = import_list(&import, &associate);
+                    self.statements
+                        .push(ASTOperation::Import(path, names, associate));
+                }
+                Tokens::Command(command, associate) => {
+                    self.statements
+                        .push(ASTOperation::Command(command, associate));
                 }
                 Tokens::Symbol(reference, associate) => {
                     let next_token = self.peek(1);
@@ -170,7 +358,8 @@ impl AST {
                         let statements =
                             self.get_statements_from_tokens(&statements, associate.clone());
                         self.index += forwardness;
-                        self.statements.push(ASTOperation::AssignVariable(
+                        // only `let` declares, `name = value` changes the variable in scope
+                        self.statements.push(ASTOperation::MutateVariable(
                             reference,
                             statements.to_vec(),
                             associate,
@@ -179,9 +368,16 @@ impl AST {
                         == discriminant(&next_token)
                     {
                         self.index += 1;
+                        // the access ends at the operator of an expression such as `p.health - 1`
                         let (tokens, forwardness, _) = self.get_tokens_until_mult(
                             [
                                 Tokens::SemiColon(empty_associate()),
+                                Tokens::Comma(empty_associate()),
+                                Tokens::Add(empty_associate()),
+                                Tokens::Subtract(empty_associate()),
+                                Tokens::Multiply(empty_associate()),
+                                Tokens::Divide(empty_associate()),
+                                Tokens::Modulus(empty_associate()),
                                 Tokens::And(empty_associate()),
                                 Tokens::Or(empty_associate()),
                                 Tokens::LesserThan(empty_associate()),
@@ -217,11 +413,22 @@ impl AST {
                             exit(1);
                         }
                         self.index += 1;
-                        self.statements.push(ASTOperation::Function(
-                            reference,
-                            statements.to_vec(),
-                            associate,
-                        ));
+                        // several arguments arrive as a set nested inside the parenthesized set
+                        let arguments = match statements.as_slice() {
+                            [ASTOperation::Set(operations, set_associate)] => {
+                                match operations.as_slice() {
+                                    [ASTOperation::Set(arguments, _)] => vec![ASTOperation::Set(
+                                        arguments.to_vec(),
+                                        set_associate.clone(),
+                                    )],
+                                    _ => statements.to_vec(),
+                                }
+                            }
+                            _ => statements.to_vec(),
+                        };
+                        let function = ASTOperation::Function(reference, arguments, associate);
+                        let function = self.chain(function);
+                        self.statements.push(function);
                     } else {
                         self.statements
                             .push(ASTOperation::Access(reference, associate));
@@ -238,8 +445,7 @@ impl AST {
                         exit(1);
                     }
                     self.index += 1;
-                    let (tokens, forwardness) =
-                        self.get_tokens_until(Tokens::RBrace(empty_associate()));
+                    let (tokens, forwardness) = self.get_block_tokens();
                     let statements = self.get_statements_from_tokens(&tokens, associate.clone());
                     self.index += forwardness;
 
@@ -261,17 +467,18 @@ impl AST {
                         exit(1);
                     }
                     self.index += 1;
-                    let (tokens, forwardness) =
-                        self.get_tokens_until(Tokens::RBrace(empty_associate()));
+                    let (tokens, forwardness) = self.get_block_tokens();
                     let statements = self.get_statements_from_tokens(&tokens, associate.clone());
                     self.index += forwardness;
-                    let mut assigned_variables: Vec<String> = vec![];
+                    let mut assigned_variables: Vec<(String, Option<String>)> = vec![];
                     for variable in variables {
-                        if let Tokens::Symbol(str, _) = variable {
-                            assigned_variables.push(str);
-                        } else {
-                            eprintln!("Expected variable name.");
-                            exit(1);
+                        match variable {
+                            Tokens::Symbol(str, _) => assigned_variables.push(annotated(&str)),
+                            Tokens::Comma(_) => {}
+                            _ => {
+                                eprintln!("Expected variable name.");
+                                exit(1);
+                            }
                         }
                     }
 
@@ -295,6 +502,49 @@ impl AST {
                         ));
                     }
                 }
+                Tokens::Struct(name, associate) => {
+                    if discriminant(&self.peek(1))
+                        != discriminant(&Tokens::LBrace(empty_associate()))
+                    {
+                        eprintln!("Expected Left curly brace.");
+                        exit(1);
+                    }
+                    self.index += 1;
+                    let (tokens, forwardness) = self.get_block_tokens();
+                    self.index += forwardness;
+                    // fields are `name: type` entries, everything else are the methods
+                    let mut fields = vec![];
+                    let mut method_tokens = vec![];
+                    let mut depth = 0;
+                    for token in tokens {
+                        match &token {
+                            Tokens::LBrace(_) => depth += 1,
+                            Tokens::RBrace(_) => depth -= 1,
+                            Tokens::Symbol(field, _) if depth == 0 => {
+                                match field.split_once(':') {
+                                    Some((field, kind)) => fields
+                                        .push((field.trim().to_string(), kind.trim().to_string())),
+                                    None => {
+                                        eprintln!("Expected a field such as `health: int`.");
+                                        exit(1);
+                                    }
+                                }
+                                continue;
+                            }
+                            Tokens::Comma(_) if depth == 0 => continue,
+                            _ => {}
+                        }
+                        method_tokens.push(token);
+                    }
+                    let methods =
+                        self.get_statements_from_tokens(&method_tokens, associate.clone());
+                    self.statements.push(ASTOperation::Struct(
+                        name,
+                        fields,
+                        methods.to_vec(),
+                        associate,
+                    ));
+                }
                 Tokens::While(name, iterator_tokens, associate) => {
                     let iterator_statements =
                         self.get_statements_from_tokens(&iterator_tokens, associate.clone());
@@ -306,8 +556,7 @@ impl AST {
                         exit(1);
                     }
                     self.index += 1;
-                    let (tokens, forwardness) =
-                        self.get_tokens_until(Tokens::RBrace(empty_associate()));
+                    let (tokens, forwardness) = self.get_block_tokens();
                     let statements = self.get_statements_from_tokens(&tokens, associate.clone());
                     self.index += forwardness;
                     // TODO: Here too :)
@@ -321,6 +570,110 @@ impl AST {
                         associate.clone(),
                     ));
                 }
+                Tokens::For(name, iterator_tokens, associate) => {
+                    let iterator_statements =
+                        self.get_statements_from_tokens(&iterator_tokens, associate.clone());
+                    // expect a Left curly brace
+                    if discriminant(&self.peek(1))
+                        != discriminant(&Tokens::LBrace(empty_associate()))
+                    {
+                        eprintln!("Expected Left curly brace.");
+                        exit(1);
+                    }
+                    self.index += 1;
+                    let (tokens, forwardness) = self.get_block_tokens();
+                    let statements = self.get_statements_from_tokens(&tokens, associate.clone());
+                    self.index += forwardness;
+                    self.statements.push(ASTOperation::For(
+                        name,
+                        iterator_statements.to_vec(),
+                        Box::new(ASTOperation::CodeBlock(
+                            statements.to_vec(),
+                            associate.clone(),
+                        )),
+                        associate.clone(),
+                    ));
+                }
+                Tokens::Execute(subcommands, associate) => {
+                    let mut steps = vec![];
+                    for (subcommand, argument_tokens) in subcommands {
+                        let arguments =
+                            self.get_statements_from_tokens(&argument_tokens, associate.clone());
+                        steps.push((subcommand, arguments));
+                    }
+                    // expect a Left curly brace
+                    if discriminant(&self.peek(1))
+                        != discriminant(&Tokens::LBrace(empty_associate()))
+                    {
+                        eprintln!("Expected Left curly brace.");
+                        exit(1);
+                    }
+                    self.index += 1;
+                    let (tokens, forwardness) = self.get_block_tokens();
+                    let statements = self.get_statements_from_tokens(&tokens, associate.clone());
+                    self.index += forwardness;
+                    self.statements.push(ASTOperation::Execute(
+                        steps,
+                        Box::new(ASTOperation::CodeBlock(
+                            statements.to_vec(),
+                            associate.clone(),
+                        )),
+                        associate,
+                    ));
+                }
+                Tokens::On(event, handler_tokens, associate) => {
+                    if event != "trigger" {
+                        eprintln!("Unknown event {}.", event);
+                        exit(1);
+                    }
+                    // on trigger "objective" (player, value) { ... }
+                    let objective = match handler_tokens.first() {
+                        Some(Tokens::DblQuote(objective, _)) => objective.clone(),
+                        _ => {
+                            eprintln!("Expected trigger objective name.");
+                            exit(1);
+                        }
+                    };
+                    let mut parameters: Vec<String> = vec![];
+                    match &handler_tokens[1..] {
+                        [] => {}
+                        [Tokens::Parens(variables, _)] => {
+                            for variable in variables {
+                                match variable {
+                                    Tokens::Symbol(str, _) => parameters.push(str.clone()),
+                                    Tokens::Comma(_) => {}
+                                    _ => {
+                                        eprintln!("Expected variable name.");
+                                        exit(1);
+                                    }
+                                }
+                            }
+                        }
+                        _ => {
+                            eprintln!("Expected trigger parameters.");
+                            exit(1);
+                        }
+                    }
+                    if discriminant(&self.peek(1))
+                        != discriminant(&Tokens::LBrace(empty_associate()))
+                    {
+                        eprintln!("Expected Left curly brace.");
+                        exit(1);
+                    }
+                    self.index += 1;
+                    let (tokens, forwardness) = self.get_block_tokens();
+                    let statements = self.get_statements_from_tokens(&tokens, associate.clone());
+                    self.index += forwardness;
+                    self.statements.push(ASTOperation::Trigger(
+                        objective,
+                        parameters,
+                        Box::new(ASTOperation::CodeBlock(
+                            statements.to_vec(),
+                            associate.clone(),
+                        )),
+                        associate,
+                    ));
+                }
                 Tokens::Period(statements, associate) => {
                     let statements =
                         self.get_statements_from_tokens(&statements, associate.clone());
@@ -332,11 +685,9 @@ impl AST {
                 Tokens::New(obj_name, statement_tokens, associate) => {
                     let statements =
                         self.get_statements_from_tokens(&statement_tokens, associate.clone());
-                    self.statements.push(ASTOperation::Create(
-                        obj_name,
-                        statements.to_vec(),
-                        associate,
-                    ));
+                    let object = ASTOperation::Create(obj_name, statements.to_vec(), associate);
+                    let object = self.chain(object);
+                    self.statements.push(object);
                 }
 
                 Tokens::Add(associate) => {
@@ -368,6 +719,9 @@ impl AST {
                         }
                     } else {
                         operand = Some(Operator::Add);
+                        // priming operation for next iteration
+                        self.index += 1;
+                        continue;
                     }
                 }
                 Tokens::Subtract(associate) => {
@@ -399,6 +753,9 @@ impl AST {
                         }
                     } else {
                         operand = Some(Operator::Subtract);
+                        // priming operation for next iteration
+                        self.index += 1;
+                        continue;
                     }
                 }
 
@@ -510,18 +867,29 @@ impl AST {
                             == discriminant(&ASTOperation::LiteralNumber(0, empty_associate()))
                         || pop_second_discrim
                             == discriminant(&ASTOperation::LiteralBool(true, empty_associate()))
+                        || pop_second_discrim
+                            == discriminant(&ASTOperation::BlockPredicate(
+                                vec![],
+                                empty_associate(),
+                            ))
                         || pop_second_discrim
                             == discriminant(&ASTOperation::UseVariable(
                                 "".to_string(),
                                 Box::new(ASTOperation::LiteralNumber(0, empty_associate())),
                                 empty_associate(),
+                            ))
+                        || pop_second_discrim
+                            == discriminant(&ASTOperation::Operation(
+                                Box::new(ASTOperation::LiteralNumber(0, empty_associate())),
+                                Operator::Add,
+                                Box::new(ASTOperation::LiteralNumber(0, empty_associate())),
+                                empty_associate(),
                             )))
                 {
-                    self.statements.push(ASTOperation::Operation(
+                    self.statements.push(binary(
                         Box::new(pop_second.unwrap()),
                         operand.as_ref().unwrap().clone(),
                         Box::new(pop_last.unwrap()),
-                        empty_associate(),
                     ));
                     operand = None;
                 } else {
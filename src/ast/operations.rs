@@ -3,29 +3,153 @@ use crate::errors::associate::CodeAssociate;
 #[derive(Debug, Clone)]
 pub enum ASTOperation {
     Function(String, Vec<ASTOperation>, CodeAssociate),
-    CreateFunction(String, Vec<String>, Vec<ASTOperation>, CodeAssociate),
+    /// A function: its name, its parameters with their optional type annotations and its code.
+    CreateFunction(
+        String,
+        Vec<(String, Option<String>)>,
+        Vec<ASTOperation>,
+        CodeAssociate,
+    ),
     Create(String, Vec<ASTOperation>, CodeAssociate),
     MutateVariable(String, Vec<ASTOperation>, CodeAssociate),
     CodeBlock(Vec<ASTOperation>, CodeAssociate),
     Access(String, CodeAssociate),
-    AssignVariable(String, Vec<ASTOperation>, CodeAssociate),
-    StaticVariable(String, Vec<ASTOperation>, CodeAssociate),
+    /// A variable: its name, its optional type annotation and its value.
+    AssignVariable(String, Option<String>, Vec<ASTOperation>, CodeAssociate),
+    StaticVariable(String, Option<String>, Vec<ASTOperation>, CodeAssociate),
     LiteralString(String, CodeAssociate),
     LiteralNumber(i64, CodeAssociate),
+    LiteralDecimal(f64, CodeAssociate),
+    LiteralCoordinate(String, CodeAssociate),
     LiteralBool(bool, CodeAssociate),
+    LiteralSelector(String, CodeAssociate),
+    BlockPredicate(Vec<ASTOperation>, CodeAssociate),
     Set(Vec<ASTOperation>, CodeAssociate),
+    List(Vec<ASTOperation>, CodeAssociate),
     AccessPart(Box<ASTOperation>, CodeAssociate),
     UseVariable(String, Box<ASTOperation>, CodeAssociate),
+    Chain(Box<ASTOperation>, Box<ASTOperation>, CodeAssociate),
     If(Vec<ASTOperation>, Box<ASTOperation>, CodeAssociate),
     While(String, Vec<ASTOperation>, Box<ASTOperation>, CodeAssociate),
+    For(String, Vec<ASTOperation>, Box<ASTOperation>, CodeAssociate),
+    Execute(
+        Vec<(String, Vec<ASTOperation>)>,
+        Box<ASTOperation>,
+        CodeAssociate,
+    ),
     Operation(
         Box<ASTOperation>,
         Operator,
         Box<ASTOperation>,
         CodeAssociate,
     ),
+    Trigger(String, Vec<String>, Box<ASTOperation>, CodeAssociate),
     Export(Box<ASTOperation>, CodeAssociate),
-    Import(String, CodeAssociate),
+    /// An export list: each name with the name it is exported as.
+    ExportList(Vec<(String, String)>, CodeAssociate),
+    /// A re-export: the path of the module and, for an export list, each of its names with the
+    /// name it is exported as.
+    ReExport(String, Option<Vec<(String, String)>>, CodeAssociate),
+    /// An import: the path of the module and, for an import list, each imported name with the
+    /// name it is bound to.
+    Import(String, Option<Vec<(String, String)>>, CodeAssociate),
+    Command(String, CodeAssociate),
+    /// A struct: its name, its fields with their types and its methods.
+    Struct(
+        String,
+        Vec<(String, String)>,
+        Vec<ASTOperation>,
+        CodeAssociate,
+    ),
+}
+
+impl ASTOperation {
+    /// The code an operation was parsed from.
+    pub fn associate(&self) -> &CodeAssociate {
+        match self {
+            ASTOperation::Function(_, _, associate)
+            | ASTOperation::CreateFunction(_, _, _, associate)
+            | ASTOperation::Create(_, _, associate)
+            | ASTOperation::MutateVariable(_, _, associate)
+            | ASTOperation::CodeBlock(_, associate)
+            | ASTOperation::Access(_, associate)
+            | ASTOperation::AssignVariable(_, _, _, associate)
+            | ASTOperation::StaticVariable(_, _, _, associate)
+            | ASTOperation::LiteralString(_, associate)
+            | ASTOperation::LiteralNumber(_, associate)
+            | ASTOperation::LiteralDecimal(_, associate)
+            | ASTOperation::LiteralCoordinate(_, associate)
+            | ASTOperation::LiteralBool(_, associate)
+            | ASTOperation::LiteralSelector(_, associate)
+            | ASTOperation::BlockPredicate(_, associate)
+            | ASTOperation::Set(_, associate)
+            | ASTOperation::List(_, associate)
+            | ASTOperation::AccessPart(_, associate)
+            | ASTOperation::UseVariable(_, _, associate)
+            | ASTOperation::Chain(_, _, associate)
+            | ASTOperation::If(_, _, associate)
+            | ASTOperation::While(_, _, _, associate)
+            | ASTOperation::For(_, _, _, associate)
+            | ASTOperation::Execute(_, _, associate)
+            | ASTOperation::Operation(_, _, _, associate)
+            | ASTOperation::Trigger(_, _, _, associate)
+            | ASTOperation::Export(_, associate)
+            | ASTOperation::ExportList(_, associate)
+            | ASTOperation::ReExport(_, _, associate)
+            | ASTOperation::Import(_, _, associate)
+            | ASTOperation::Command(_, associate)
+            | ASTOperation::Struct(_, _, _, associate) => associate,
+        }
+    }
+
+    /// The operations nested directly inside of this one.
+    pub fn children(&self) -> Vec<&ASTOperation> {
+        match self {
+            ASTOperation::Function(_, operations, _)
+            | ASTOperation::CreateFunction(_, _, operations, _)
+            | ASTOperation::Create(_, operations, _)
+            | ASTOperation::MutateVariable(_, operations, _)
+            | ASTOperation::CodeBlock(operations, _)
+            | ASTOperation::AssignVariable(_, _, operations, _)
+            | ASTOperation::StaticVariable(_, _, operations, _)
+            | ASTOperation::BlockPredicate(operations, _)
+            | ASTOperation::Set(operations, _)
+            | ASTOperation::List(operations, _)
+            | ASTOperation::Struct(_, _, operations, _) => operations.iter().collect(),
+            ASTOperation::AccessPart(operation, _)
+            | ASTOperation::UseVariable(_, operation, _)
+            | ASTOperation::Trigger(_, _, operation, _)
+            | ASTOperation::Export(operation, _) => vec![operation],
+            ASTOperation::Chain(first, second, _)
+            | ASTOperation::Operation(first, _, second, _) => vec![first, second],
+            ASTOperation::If(operations, body, _)
+            | ASTOperation::While(_, operations, body, _)
+            | ASTOperation::For(_, operations, body, _) => {
+                operations.iter().chain([&**body]).collect()
+            }
+            ASTOperation::Execute(steps, body, _) => steps
+                .iter()
+                .flat_map(|(_, operations)| operations)
+                .chain([&**body])
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Whether this operation, or one nested inside of it, may read the variable `name`.
+    /// Strings and commands read the variables of their `{name}` parts.
+    pub fn reads(&self, name: &str) -> bool {
+        let read = match self {
+            ASTOperation::Access(variable, _)
+            | ASTOperation::UseVariable(variable, _, _)
+            | ASTOperation::MutateVariable(variable, _, _) => variable == name,
+            ASTOperation::LiteralString(text, _) | ASTOperation::Command(text, _) => {
+                text.contains(&format!("{{{}", name))
+            }
+            _ => false,
+        };
+        read || self.children().iter().any(|child| child.reads(name))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -0,0 +1,1378 @@
+//! A pass over the syntax tree that runs before any code is generated. It infers the types of
+//! `let` bindings and checks the calls to built-in objects, built-in functions and user
+//! functions, so that mistakes are reported where they were written instead of by the object
+//! that runs into them while the datapack is being generated. It also follows the blocks
+//! variables are declared in, to report variables used before their definition and local
+//! variables that are never used.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    rc::Rc,
+};
+
+use crate::{
+    ast::{
+        constructor::AST,
+        operations::{ASTOperation, Operator},
+    },
+    errors::{
+        associate::CodeAssociate,
+        error::CompileErrors,
+        sources::{module_id, source},
+    },
+    lexer::lexer::Lexer,
+};
+
+use super::{
+    compiler::{interpolation_parts, Interpolated},
+    modules::resolve,
+    obj::{
+        blockpos::Coordinate,
+        storage::NbtType,
+        structs::FieldKind,
+        text::{TextContent, TextObject},
+    },
+    objects::{
+        builtin_function, builtin_variable, match_objects, name_into_object, Object, Objects,
+    },
+};
+
+/// The type of a value, as far as it is known before the code runs.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Type {
+    Number,
+    Decimal,
+    Boolean,
+    String,
+    Coordinate,
+    Entity,
+    BlockPos,
+    Dimension,
+    Item,
+    Text,
+    Storage,
+    StoragePath,
+    List,
+    Scoreboard,
+    /// The score of a player in a scoreboard.
+    Score,
+    World,
+    /// A list known at compile time, such as `[1, 2, 3]`.
+    Array,
+    Struct(String),
+    /// A command or a call, which has no value of its own.
+    Statement,
+    /// Anything the checker cannot tell, which is accepted everywhere.
+    Unknown,
+}
+
+impl Type {
+    /// The type named by an annotation such as `int` or `Entity`. Structs are not known here.
+    pub fn parse(annotation: &str) -> Option<Type> {
+        match annotation {
+            "int" => Some(Type::Number),
+            "bool" => Some(Type::Boolean),
+            "float" => Some(Type::Decimal),
+            "string" => Some(Type::String),
+            "Entity" => Some(Type::Entity),
+            "BlockPos" => Some(Type::BlockPos),
+            "Dimension" => Some(Type::Dimension),
+            "Item" => Some(Type::Item),
+            "Text" => Some(Type::Text),
+            "Storage" => Some(Type::Storage),
+            "List" => Some(Type::List),
+            "Scoreboard" => Some(Type::Scoreboard),
+            _ => None,
+        }
+    }
+
+    /// The types of the values that can be given where this type is expected: scores can be
+    /// given for numbers and booleans, numbers for decimals.
+    pub fn accepted(&self) -> Vec<Type> {
+        match self {
+            Type::Number | Type::Boolean => vec![self.clone(), Type::Score],
+            Type::Decimal => vec![Type::Decimal, Type::Number],
+            _ => vec![self.clone()],
+        }
+    }
+
+    pub fn accepts(&self, found: &Type) -> bool {
+        *self == Type::Unknown || *found == Type::Unknown || self.accepted().contains(found)
+    }
+
+    pub fn of(object: &Objects) -> Type {
+        match object {
+            Objects::Entity(_) => Type::Entity,
+            Objects::Dimension(_) => Type::Dimension,
+            Objects::BlockPos(..) => Type::BlockPos,
+            Objects::Coordinate(_) => Type::Coordinate,
+            Objects::Item(_) => Type::Item,
+            Objects::Text(_) => Type::Text,
+            Objects::Storage(_) => Type::Storage,
+            Objects::StoragePath(..) => Type::StoragePath,
+            Objects::List(..) => Type::List,
+            Objects::Struct(instance) => Type::Struct(instance.definition.name.clone()),
+            Objects::String(_) => Type::String,
+            Objects::Number(_) => Type::Number,
+            Objects::Decimal(_) => Type::Decimal,
+            Objects::Boolean(_) => Type::Boolean,
+            Objects::MCStatement(_) => Type::Statement,
+            Objects::Scoreboard(..) => Type::Scoreboard,
+            Objects::ScoreboardPlayerPair(..) => Type::Score,
+            Objects::Variable(value, _) => Type::of(value),
+            Objects::Array(_) => Type::Array,
+            Objects::World => Type::World,
+            _ => Type::Unknown,
+        }
+    }
+
+    /// A value of the type, to look up the methods and fields its object has.
+    fn sample(&self) -> Option<Rc<dyn Object>> {
+        let object = match self {
+            Type::Number => Objects::Number(0),
+            Type::Decimal => Objects::Decimal(0.0),
+            Type::Boolean => Objects::Boolean(false),
+            Type::String => Objects::String(String::new()),
+            Type::Text => Objects::Text(TextObject::new(TextContent::Text(String::new()))),
+            Type::StoragePath => Objects::StoragePath(String::new(), String::new(), NbtType::Int),
+            Type::List => Objects::List(String::new(), String::new(), NbtType::Int),
+            Type::Score => Objects::ScoreboardPlayerPair(
+                String::new(),
+                String::new(),
+                Box::new(Objects::Number(0)),
+            ),
+            Type::Array => Objects::Array(vec![]),
+            Type::World => Objects::World,
+            Type::Entity
+            | Type::BlockPos
+            | Type::Dimension
+            | Type::Item
+            | Type::Storage
+            | Type::Scoreboard => return Some(name_into_object(&self.to_string())),
+            _ => return None,
+        };
+        Some(match_objects(object))
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Type::Number => "number",
+            Type::Decimal => "decimal",
+            Type::Boolean => "boolean",
+            Type::String => "string",
+            Type::Coordinate => "coordinate",
+            Type::Entity => "Entity",
+            Type::BlockPos => "BlockPos",
+            Type::Dimension => "Dimension",
+            Type::Item => "Item",
+            Type::Text => "text",
+            Type::Storage => "Storage",
+            Type::StoragePath => "storage path",
+            Type::List => "list",
+            Type::Scoreboard => "Scoreboard",
+            Type::Score => "score",
+            Type::World => "world",
+            Type::Array => "array",
+            Type::Struct(name) => name,
+            Type::Statement => "statement",
+            Type::Unknown => "unknown",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The arguments a call accepts: the types allowed for each of them (any type when empty),
+/// how many are required and the type of the result.
+struct Signature {
+    params: Vec<Vec<Type>>,
+    required: usize,
+    returns: Type,
+}
+
+fn signature(required: usize, params: &[&[Type]], returns: Type) -> Option<Signature> {
+    Some(Signature {
+        params: params.iter().map(|types| types.to_vec()).collect(),
+        required,
+        returns,
+    })
+}
+
+/// The signature of a user function or method, whose annotated parameters only accept values
+/// of their type.
+fn user_signature(parameters: &[(String, Type)]) -> Signature {
+    Signature {
+        params: parameters
+            .iter()
+            .map(|(_, parameter_type)| match parameter_type {
+                Type::Unknown => vec![],
+                parameter_type => parameter_type.accepted(),
+            })
+            .collect(),
+        required: parameters.len(),
+        returns: Type::Statement,
+    }
+}
+
+/// Values a component of a position can be made of.
+const COORDINATE: [Type; 6] = [
+    Type::Number,
+    Type::Decimal,
+    Type::Coordinate,
+    Type::String,
+    Type::Score,
+    Type::StoragePath,
+];
+
+fn constructor(name: &str) -> Option<Signature> {
+    match name {
+        "Entity" => signature(1, &[&[Type::Entity, Type::String]], Type::Entity),
+        "Dimension" => signature(1, &[&[Type::String]], Type::Dimension),
+        "Item" => signature(1, &[&[Type::String]], Type::Item),
+        "Storage" => signature(1, &[&[Type::String]], Type::Storage),
+        "Scoreboard" => signature(2, &[&[Type::String], &[Type::String]], Type::Scoreboard),
+        _ => None,
+    }
+}
+
+fn builtin(name: &str) -> Option<Signature> {
+    match name {
+        "text" => signature(1, &[&[]], Type::Text),
+        "nbt" => signature(
+            2,
+            &[
+                &[Type::BlockPos, Type::String, Type::Storage, Type::Entity],
+                &[Type::String],
+            ],
+            Type::Text,
+        ),
+        "tellraw" | "title" | "subtitle" | "actionbar" => {
+            signature(2, &[&[Type::Entity], &[]], Type::Statement)
+        }
+        "bossbar" | "bossbar_name" => signature(2, &[&[Type::String], &[]], Type::Statement),
+        "cmd" => signature(1, &[&[Type::String], &[Type::Boolean]], Type::Statement),
+        _ => None,
+    }
+}
+
+/// The signatures of the methods of built-in objects. Methods that are missing here are
+/// still known to exist, only their arguments are not checked.
+fn method(receiver: &Type, name: &str) -> Option<Signature> {
+    let position: &[Type] = &[Type::BlockPos];
+    let string: &[Type] = &[Type::String];
+    let number: &[Type] = &[Type::Number, Type::Decimal];
+    match (receiver, name) {
+        (Type::Entity, "kill") => signature(0, &[], Type::Statement),
+        (Type::Entity, "tp") => signature(
+            1,
+            &[
+                &[Type::Dimension, Type::Entity, Type::BlockPos],
+                &[Type::Entity, Type::BlockPos],
+            ],
+            Type::Statement,
+        ),
+        (Type::Entity, "give") => signature(1, &[&[Type::Item]], Type::Statement),
+        (Type::Entity, "clear") => signature(0, &[&[Type::Item], &[Type::Number]], Type::Statement),
+        (Type::Entity, "replace_item") => signature(2, &[string, &[Type::Item]], Type::Statement),
+        (Type::World, "setblock") => signature(2, &[position, string, string], Type::Statement),
+        (Type::World, "fill") => signature(
+            3,
+            &[position, position, string, string, string],
+            Type::Statement,
+        ),
+        (Type::World, "clone") => signature(
+            3,
+            &[position, position, position, string, string],
+            Type::Statement,
+        ),
+        (Type::World, "summon") => signature(
+            2,
+            &[&[Type::String, Type::Item], position, string],
+            Type::Statement,
+        ),
+        (Type::World, "replace_item") => {
+            signature(3, &[position, string, &[Type::Item]], Type::Statement)
+        }
+        (Type::Item, "count") => signature(1, &[&[Type::Number]], Type::Item),
+        (Type::Item, "name") => signature(1, &[string], Type::Item),
+        (Type::Item, "lore") => signature(1, &[&[Type::Array]], Type::Item),
+        (Type::Item, "enchant") => signature(2, &[string, &[Type::Number]], Type::Item),
+        (Type::Item, "unbreakable") => signature(0, &[], Type::Item),
+        (Type::List, "push") => signature(1, &[&[]], Type::Statement),
+        (Type::List, "pop") => signature(0, &[&[]], Type::Statement),
+        (Type::List, "len") => signature(0, &[&[]], Type::Number),
+        (Type::List, "clear") => signature(0, &[], Type::Statement),
+        (Type::List, "get") => signature(1, &[&[Type::Number]], Type::StoragePath),
+        (Type::List, "read" | "write") => signature(2, &[&[], &[]], Type::Statement),
+        (Type::Scoreboard, "get_player") => {
+            signature(1, &[&[Type::String, Type::Entity]], Type::Score)
+        }
+        (Type::Scoreboard, "display" | "render_type") => signature(1, &[string], Type::Statement),
+        (Type::Scoreboard, "set_display_name" | "enable") => signature(1, &[&[]], Type::Statement),
+        (Type::Scoreboard, "reset") => signature(0, &[&[]], Type::Statement),
+        (Type::Scoreboard, "remove" | "list") => signature(0, &[], Type::Statement),
+        (Type::Score, "reset" | "enable" | "list") => signature(0, &[], Type::Statement),
+        (Type::Storage, "path") => signature(2, &[string, string], Type::StoragePath),
+        (Type::Storage, "list") => signature(2, &[string, string], Type::List),
+        (Type::Storage, "merge" | "remove") => signature(1, &[string], Type::Statement),
+        (Type::StoragePath, "at") => signature(2, &[string, string], Type::StoragePath),
+        (Type::StoragePath, "set" | "merge") => signature(1, &[&[]], Type::Statement),
+        (Type::StoragePath, "remove") => signature(0, &[], Type::Statement),
+        (Type::StoragePath, "get") => signature(0, &[number], Type::Statement),
+        (Type::StoragePath, "to_score" | "store") => signature(1, &[&[], number], Type::Statement),
+        (Type::Text, "color" | "click_run" | "click_suggest" | "click_url" | "click_copy") => {
+            signature(1, &[string], Type::Text)
+        }
+        (Type::Text, "bold" | "italic" | "underlined" | "strikethrough" | "obfuscated") => {
+            signature(0, &[&[Type::Boolean]], Type::Text)
+        }
+        (Type::Text, "hover" | "append") => signature(1, &[&[]], Type::Text),
+        (Type::BlockPos, "offset") => signature(3, &[number, number, number], Type::BlockPos),
+        _ => None,
+    }
+}
+
+/// The arguments of a call, which arrive as the parenthesized set.
+fn call_arguments(set: &[ASTOperation]) -> &[ASTOperation] {
+    match set.first() {
+        Some(ASTOperation::Set(arguments, _)) => arguments,
+        _ => &[],
+    }
+}
+
+/// The variable a statement declares.
+fn declared(operation: &ASTOperation) -> Option<&String> {
+    match operation {
+        ASTOperation::AssignVariable(name, _, _, _)
+        | ASTOperation::StaticVariable(name, _, _, _) => Some(name),
+        ASTOperation::Export(operation, _) => declared(operation),
+        _ => None,
+    }
+}
+
+/// The statements of a block, which arrive as a set when there are several.
+fn block_statements(operations: &[ASTOperation]) -> &[ASTOperation] {
+    match operations {
+        [ASTOperation::Set(statements, _)] => statements,
+        [ASTOperation::CodeBlock(statements, _)] => block_statements(statements),
+        _ => operations,
+    }
+}
+
+fn count(required: usize, max: usize) -> String {
+    let plural = if max == 1 { "" } else { "s" };
+    if required == max {
+        format!("{} argument{}", max, plural)
+    } else {
+        format!("{} to {} argument{}", required, max, plural)
+    }
+}
+
+/// The message for a call with the wrong number of arguments, such as
+/// `tp takes 1 to 2 arguments but 3 were given`.
+pub fn argument_count(name: &str, required: usize, max: usize, given: usize) -> String {
+    let given = match given {
+        1 => "1 was".to_string(),
+        given => format!("{} were", given),
+    };
+    format!(
+        "{} takes {} but {} given",
+        name,
+        count(required, max),
+        given
+    )
+}
+
+#[derive(Clone, Debug)]
+struct StructType {
+    fields: Vec<(String, Type)>,
+    /// The parameters of each method.
+    methods: HashMap<String, Vec<(String, Type)>>,
+}
+
+/// The names a file exports.
+#[derive(Clone, Debug, Default)]
+struct Exports {
+    variables: HashMap<String, Type>,
+    functions: HashMap<String, Vec<(String, Type)>>,
+}
+
+impl Exports {
+    /// The names of an import or export list with the names they are bound to, or every
+    /// export under its own name.
+    fn bindings(&self, names: &Option<Vec<(String, String)>>) -> Vec<(String, String)> {
+        match names {
+            Some(names) => names.clone(),
+            None => self
+                .variables
+                .keys()
+                .chain(self.functions.keys())
+                .map(|exported| (exported.clone(), exported.clone()))
+                .collect(),
+        }
+    }
+}
+
+/// The names in scope at some point of a file.
+#[derive(Clone, Debug, Default)]
+struct Environment {
+    variables: HashMap<String, Type>,
+    /// The parameters of each user function.
+    functions: HashMap<String, Vec<(String, Type)>>,
+    /// Function bodies also see the functions of wherever they are called from, and the
+    /// variables declared after them around their declaration, so names that are not known
+    /// there are not errors.
+    open: bool,
+    exports: Exports,
+    /// The module and exported name each imported name comes from.
+    imports: HashMap<String, (String, String)>,
+    /// Whether this is a block or function body rather than the top of a file.
+    nested: bool,
+    /// The variables declared inside of blocks, by their position in the checker's locals.
+    locals: HashMap<String, usize>,
+    /// The variables declared further down the block and the blocks around it.
+    later: HashSet<String>,
+}
+
+impl Environment {
+    fn child(&self) -> Environment {
+        Environment {
+            variables: self.variables.clone(),
+            functions: self.functions.clone(),
+            open: self.open,
+            exports: Exports::default(),
+            imports: self.imports.clone(),
+            nested: true,
+            locals: self.locals.clone(),
+            later: self.later.clone(),
+        }
+    }
+
+    fn function_body(&self, parameters: &[(String, Type)]) -> Environment {
+        let mut environment = self.child();
+        environment.open = true;
+        // the function runs after the rest of the block has declared its variables
+        environment.later.clear();
+        for (parameter, parameter_type) in parameters {
+            environment
+                .variables
+                .insert(parameter.clone(), parameter_type.clone());
+            environment.locals.remove(parameter);
+        }
+        environment
+    }
+}
+
+pub struct TypeChecker {
+    structs: HashMap<String, StructType>,
+    /// The exports of the files that were already checked.
+    checked: HashMap<String, Exports>,
+    /// The files being checked, innermost last, to stop at circular imports.
+    checking: Vec<String>,
+    /// The statement being checked. Operations nested inside of it are lexed on their own, so
+    /// their positions are only meaningful when they point into the same line.
+    statement: Option<CodeAssociate>,
+    /// The variables declared inside of blocks, where they were declared and whether they
+    /// were used.
+    locals: Vec<(String, CodeAssociate, bool)>,
+    pub errors: Vec<CompileErrors>,
+    pub warnings: Vec<(String, CodeAssociate)>,
+}
+
+impl TypeChecker {
+    pub fn new() -> TypeChecker {
+        TypeChecker {
+            structs: HashMap::new(),
+            checked: HashMap::new(),
+            checking: vec![],
+            statement: None,
+            locals: vec![],
+            errors: vec![],
+            warnings: vec![],
+        }
+    }
+
+    /// Checks the statements of a file and every file it imports.
+    pub fn check(&mut self, file: &str, operations: &[ASTOperation]) {
+        self.checking.push(file.to_string());
+        let locals = self.locals.len();
+        let mut environment = Environment::default();
+        self.check_block(operations, &mut environment);
+        for (name, associate, used) in self.locals.drain(locals..) {
+            if !used && !name.starts_with('_') {
+                self.warnings
+                    .push((format!("Variable {} is never used", name), associate));
+            }
+        }
+        self.checking.pop();
+        self.checked.insert(file.to_string(), environment.exports);
+    }
+
+    fn located(&self, associate: &CodeAssociate) -> CodeAssociate {
+        match &self.statement {
+            Some(statement)
+                if statement.line != associate.line || statement.lines != associate.lines =>
+            {
+                statement.clone()
+            }
+            _ => associate.clone(),
+        }
+    }
+
+    fn mismatch(&mut self, message: String, associate: &CodeAssociate) {
+        let associate = self.located(associate);
+        self.errors
+            .push(CompileErrors::TypeMismatch(message, associate));
+    }
+
+    fn import_error(&mut self, message: String, associate: &CodeAssociate) {
+        let associate = self.located(associate);
+        self.errors
+            .push(CompileErrors::ImportError(message, associate));
+    }
+
+    fn undefined(&mut self, message: String, associate: &CodeAssociate) {
+        let associate = self.located(associate);
+        self.errors
+            .push(CompileErrors::UndefinedName(message, associate));
+    }
+
+    fn check_block(&mut self, operations: &[ASTOperation], environment: &mut Environment) {
+        let outer = self.statement.clone();
+        let later = environment.later.clone();
+        let statements = block_statements(operations);
+        for (index, operation) in statements.iter().enumerate() {
+            self.statement = Some(operation.associate().clone());
+            environment.later = later.clone();
+            environment
+                .later
+                .extend(statements[index..].iter().filter_map(declared).cloned());
+            self.infer(operation, environment);
+        }
+        environment.later = later;
+        self.statement = outer;
+    }
+
+    /// Checks the variables a string or command reads through `{name}` parts.
+    fn interpolated(&mut self, str: &str, associate: &CodeAssociate, environment: &Environment) {
+        for part in interpolation_parts(str) {
+            if let Interpolated::Expression(expression) = part {
+                let name = expression.split('.').next().unwrap_or_default();
+                self.variable(name, associate, environment);
+            }
+        }
+    }
+
+    fn infer(&mut self, operation: &ASTOperation, environment: &mut Environment) -> Type {
+        match operation {
+            ASTOperation::LiteralString(str, associate) => {
+                self.interpolated(str, associate, environment);
+                Type::String
+            }
+            ASTOperation::LiteralNumber(..) => Type::Number,
+            ASTOperation::LiteralDecimal(..) => Type::Decimal,
+            ASTOperation::LiteralCoordinate(..) => Type::Coordinate,
+            ASTOperation::LiteralBool(..) => Type::Boolean,
+            ASTOperation::LiteralSelector(..) => Type::Entity,
+            ASTOperation::Command(command, associate) => {
+                self.interpolated(command, associate, environment);
+                Type::Statement
+            }
+            ASTOperation::BlockPredicate(operations, _) => {
+                for operation in operations {
+                    self.infer(operation, environment);
+                }
+                Type::Boolean
+            }
+            ASTOperation::List(elements, _) => {
+                for element in elements {
+                    self.infer(element, environment);
+                }
+                Type::Array
+            }
+            ASTOperation::Set(operations, _) => {
+                let types = operations
+                    .iter()
+                    .map(|operation| self.infer(operation, environment))
+                    .collect::<Vec<Type>>();
+                match types.as_slice() {
+                    [single] => single.clone(),
+                    _ => Type::Unknown,
+                }
+            }
+            ASTOperation::CodeBlock(operations, _) => {
+                self.check_block(operations, &mut environment.child());
+                Type::Statement
+            }
+            ASTOperation::AccessPart(operation, _) => self.infer(operation, environment),
+            ASTOperation::Access(name, associate) => self.variable(name, associate, environment),
+            ASTOperation::AssignVariable(name, annotation, operations, associate)
+            | ASTOperation::StaticVariable(name, annotation, operations, associate) => {
+                let mut value = match operations.as_slice() {
+                    [operation] => self.infer(operation, environment),
+                    _ => Type::Unknown,
+                };
+                if let Some(annotation) = annotation {
+                    let declared = self.annotation(annotation, associate);
+                    if !declared.accepts(&value) {
+                        self.mismatch(
+                            format!(
+                                "Variable {} is declared as {} but given {}",
+                                name, annotation, value
+                            ),
+                            associate,
+                        );
+                    }
+                    if declared != Type::Unknown {
+                        value = declared;
+                    }
+                }
+                environment.variables.insert(name.clone(), value.clone());
+                // a block's own variable shadows the one around it until the block ends
+                if environment.nested {
+                    environment.locals.insert(name.clone(), self.locals.len());
+                    self.locals.push((name.clone(), associate.clone(), false));
+                } else {
+                    environment.locals.remove(name);
+                }
+                value
+            }
+            ASTOperation::MutateVariable(name, operations, associate) => {
+                for operation in operations {
+                    self.infer(operation, environment);
+                }
+                self.variable(name, associate, environment);
+                Type::Statement
+            }
+            ASTOperation::UseVariable(name, operation, associate) => {
+                let receiver = self.variable(name, associate, environment);
+                self.member(&receiver, operation, environment)
+            }
+            ASTOperation::Chain(operation, method, _) => {
+                let receiver = self.infer(operation, environment);
+                self.member(&receiver, method, environment)
+            }
+            ASTOperation::Operation(first, operator, second, _) => {
+                let first = self.infer(first, environment);
+                let second = self.infer(second, environment);
+                match operator {
+                    Operator::Add
+                    | Operator::Subtract
+                    | Operator::Multiply
+                    | Operator::Divide
+                    | Operator::Modulus
+                    | Operator::Power => match (first, second) {
+                        (Type::Decimal, _) | (_, Type::Decimal) => Type::Decimal,
+                        (Type::Number | Type::Score, Type::Number | Type::Score) => Type::Number,
+                        _ => Type::Unknown,
+                    },
+                    Operator::Assignment => Type::Statement,
+                    _ => Type::Boolean,
+                }
+            }
+            ASTOperation::If(conditions, code, _) => {
+                for condition in conditions {
+                    self.infer(condition, environment);
+                }
+                self.infer(code, environment);
+                Type::Statement
+            }
+            ASTOperation::Execute(subcommands, code, _) => {
+                for (_, arguments) in subcommands {
+                    for argument in arguments {
+                        self.infer(argument, environment);
+                    }
+                }
+                self.infer(code, environment);
+                Type::Statement
+            }
+            ASTOperation::While(name, iterator, code, _) => {
+                for operation in iterator {
+                    self.infer(operation, environment);
+                }
+                let mut body = environment.child();
+                body.variables.insert(name.clone(), Type::Unknown);
+                self.check_block(std::slice::from_ref(code), &mut body);
+                Type::Statement
+            }
+            ASTOperation::For(name, iterator, code, _) => {
+                let iterator = match iterator.first() {
+                    Some(operation) => self.infer(operation, environment),
+                    None => Type::Unknown,
+                };
+                let mut body = environment.child();
+                // runtime lists are walked through the storage path of their current element
+                let element = match iterator {
+                    Type::List => Type::StoragePath,
+                    _ => Type::Unknown,
+                };
+                body.variables.insert(name.clone(), element);
+                self.check_block(std::slice::from_ref(code), &mut body);
+                Type::Statement
+            }
+            ASTOperation::Trigger(_, parameters, code, _) => {
+                let mut handler = environment.child();
+                for (parameter, value) in parameters.iter().zip([Type::Entity, Type::Score]) {
+                    handler.variables.insert(parameter.clone(), value);
+                }
+                self.check_block(std::slice::from_ref(code), &mut handler);
+                Type::Statement
+            }
+            ASTOperation::CreateFunction(name, parameters, code, associate) => {
+                let parameters = self.parameters(parameters, associate);
+                environment
+                    .functions
+                    .insert(name.clone(), parameters.clone());
+                self.check_block(code, &mut environment.function_body(&parameters));
+                Type::Statement
+            }
+            ASTOperation::Struct(name, fields, methods, associate) => {
+                let mut definition = StructType {
+                    fields: vec![],
+                    methods: HashMap::new(),
+                };
+                for (field, kind) in fields {
+                    let field_type = match FieldKind::parse(kind) {
+                        Some(FieldKind::Score) => Type::Score,
+                        Some(FieldKind::Storage(_)) => Type::StoragePath,
+                        None => {
+                            self.mismatch(
+                                format!("Unknown type {} of field {}", kind, field),
+                                associate,
+                            );
+                            Type::Unknown
+                        }
+                    };
+                    definition.fields.push((field.clone(), field_type));
+                }
+                let methods = block_statements(methods);
+                // methods can take instances of their own struct and call each other, so they
+                // are checked once all of them are known
+                self.structs.insert(name.clone(), definition.clone());
+                for method in methods {
+                    if let ASTOperation::CreateFunction(method, parameters, _, associate) = method {
+                        let parameters = self.parameters(parameters, associate);
+                        definition.methods.insert(method.clone(), parameters);
+                    }
+                }
+                self.structs.insert(name.clone(), definition.clone());
+                for method in methods {
+                    if let ASTOperation::CreateFunction(method, _, code, _) = method {
+                        let mut body = environment.function_body(&definition.methods[method]);
+                        body.variables
+                            .insert("self".to_string(), Type::Struct(name.clone()));
+                        self.check_block(code, &mut body);
+                    }
+                }
+                Type::Statement
+            }
+            ASTOperation::Export(operation, _) => {
+                let value = self.infer(operation, environment);
+                match &**operation {
+                    ASTOperation::AssignVariable(name, _, _, _)
+                    | ASTOperation::StaticVariable(name, _, _, _) => {
+                        let variable = Some(value.clone());
+                        self.export(name, variable, None, operation.associate(), environment);
+                    }
+                    ASTOperation::CreateFunction(name, _, _, _) => {
+                        let parameters = environment.functions.get(name).cloned();
+                        self.export(name, None, parameters, operation.associate(), environment);
+                    }
+                    _ => {}
+                }
+                value
+            }
+            ASTOperation::ExportList(names, associate) => {
+                for (name, exported) in names {
+                    let variable = environment.variables.get(name).cloned();
+                    let parameters = environment.functions.get(name).cloned();
+                    if variable.is_none() && parameters.is_none() {
+                        self.undefined(format!("{} is not defined", name), associate);
+                        continue;
+                    }
+                    self.export(exported, variable, parameters, associate, environment);
+                }
+                Type::Statement
+            }
+            ASTOperation::ReExport(path, names, associate) => {
+                let path = &self.resolve(path);
+                let Some(exports) = self.exports_of(path, associate) else {
+                    return Type::Statement;
+                };
+                for (name, exported) in exports.bindings(names) {
+                    let variable = exports.variables.get(&name).cloned();
+                    let parameters = exports.functions.get(&name).cloned();
+                    if variable.is_none() && parameters.is_none() {
+                        self.import_error(
+                            format!("{} is not exported by {}", name, path),
+                            associate,
+                        );
+                        continue;
+                    }
+                    self.export(&exported, variable, parameters, associate, environment);
+                }
+                Type::Statement
+            }
+            ASTOperation::Import(path, names, associate) => {
+                let path = self.resolve(path);
+                self.import(&path, names, associate, environment);
+                Type::Statement
+            }
+            ASTOperation::Function(name, set, associate) => {
+                let arguments = call_arguments(set);
+                let types = arguments
+                    .iter()
+                    .map(|argument| self.infer(argument, environment))
+                    .collect::<Vec<Type>>();
+                if let Some(parameters) = environment.functions.get(name) {
+                    let signature = user_signature(parameters);
+                    self.check_call(name, &signature, arguments, &types, associate)
+                } else if let Some(signature) = builtin(name) {
+                    self.check_call(name, &signature, arguments, &types, associate)
+                } else if builtin_function(name).is_some() || environment.open {
+                    Type::Unknown
+                } else {
+                    self.undefined(format!("Unknown function {}", name), associate);
+                    Type::Unknown
+                }
+            }
+            ASTOperation::Create(name, params, associate) => {
+                self.create(name, params, associate, environment)
+            }
+        }
+    }
+
+    /// The type an annotation names.
+    fn annotation(&mut self, annotation: &str, associate: &CodeAssociate) -> Type {
+        if let Some(annotated) = Type::parse(annotation) {
+            return annotated;
+        }
+        if self.structs.contains_key(annotation) {
+            return Type::Struct(annotation.to_string());
+        }
+        self.mismatch(format!("Unknown type {}", annotation), associate);
+        Type::Unknown
+    }
+
+    fn parameters(
+        &mut self,
+        parameters: &[(String, Option<String>)],
+        associate: &CodeAssociate,
+    ) -> Vec<(String, Type)> {
+        parameters
+            .iter()
+            .map(|(parameter, annotation)| {
+                let parameter_type = match annotation {
+                    Some(annotation) => self.annotation(annotation, associate),
+                    None => Type::Unknown,
+                };
+                (parameter.clone(), parameter_type)
+            })
+            .collect()
+    }
+
+    fn variable(
+        &mut self,
+        name: &str,
+        associate: &CodeAssociate,
+        environment: &Environment,
+    ) -> Type {
+        if let Some(value) = environment.variables.get(name) {
+            if let Some(local) = environment.locals.get(name) {
+                self.locals[*local].2 = true;
+            }
+            return value.clone();
+        }
+        if let Some(builtin) = builtin_variable(name) {
+            return Type::of(&builtin.value);
+        }
+        if environment.later.contains(name) {
+            self.undefined(
+                format!("Variable {} is used before it is defined", name),
+                associate,
+            );
+        } else if !environment.open {
+            self.undefined(format!("Unknown variable {}", name), associate);
+        }
+        Type::Unknown
+    }
+
+    /// The type of a field, or of one of the values of a struct.
+    fn field(&mut self, receiver: &Type, name: &str, associate: &CodeAssociate) -> Type {
+        if let Type::Struct(struct_name) = receiver {
+            let definition = self.structs.get(struct_name);
+            let field = definition.and_then(|definition| {
+                definition
+                    .fields
+                    .iter()
+                    .find(|(field, _)| field == name)
+                    .map(|(_, field_type)| field_type.clone())
+            });
+            return match field {
+                Some(field_type) => field_type,
+                None if name == "owner" => Type::Entity,
+                None if definition.is_none() => Type::Unknown,
+                None => {
+                    self.undefined(
+                        format!("Struct {} has no field {}", struct_name, name),
+                        associate,
+                    );
+                    Type::Unknown
+                }
+            };
+        }
+        if let Some(sample) = receiver.sample() {
+            if !sample.get_variables().contains_key(name) {
+                self.undefined(format!("{} has no field {}", receiver, name), associate);
+                return Type::Unknown;
+            }
+            if *receiver == Type::BlockPos {
+                return Type::Coordinate;
+            }
+        }
+        Type::Unknown
+    }
+
+    /// The type of using a field or method of a value.
+    fn member(
+        &mut self,
+        receiver: &Type,
+        operation: &ASTOperation,
+        environment: &mut Environment,
+    ) -> Type {
+        match operation {
+            ASTOperation::AccessPart(operation, _) => self.member(receiver, operation, environment),
+            ASTOperation::Chain(operation, method, _) => {
+                let receiver = self.member(receiver, operation, environment);
+                self.member(&receiver, method, environment)
+            }
+            ASTOperation::Access(name, associate) => self.field(receiver, name, associate),
+            ASTOperation::UseVariable(name, operation, associate) => {
+                let receiver = self.field(receiver, name, associate);
+                self.member(&receiver, operation, environment)
+            }
+            // `p.health = 20` assigns a field, the value is read in the scope
+            ASTOperation::MutateVariable(name, operations, associate) => {
+                for operation in operations {
+                    self.infer(operation, environment);
+                }
+                self.field(receiver, name, associate);
+                Type::Statement
+            }
+            ASTOperation::Function(name, set, associate) => {
+                let arguments = call_arguments(set);
+                let types = arguments
+                    .iter()
+                    .map(|argument| self.infer(argument, environment))
+                    .collect::<Vec<Type>>();
+                if let Type::Struct(struct_name) = receiver {
+                    let parameters = self
+                        .structs
+                        .get(struct_name)
+                        .map(|definition| definition.methods.get(name).cloned());
+                    return match parameters {
+                        Some(Some(parameters)) => self.check_call(
+                            name,
+                            &user_signature(&parameters),
+                            arguments,
+                            &types,
+                            associate,
+                        ),
+                        Some(None) => {
+                            self.undefined(
+                                format!("Struct {} has no method {}", struct_name, name),
+                                associate,
+                            );
+                            Type::Unknown
+                        }
+                        None => Type::Unknown,
+                    };
+                }
+                let sample = receiver.sample();
+                if sample.is_none() {
+                    return Type::Unknown;
+                }
+                if !sample.unwrap().get_functions().contains_key(name) {
+                    self.undefined(format!("{} has no method {}", receiver, name), associate);
+                    return Type::Unknown;
+                }
+                match method(receiver, name) {
+                    Some(signature) => {
+                        let name = format!("{}.{}", receiver, name);
+                        self.check_call(&name, &signature, arguments, &types, associate)
+                    }
+                    None => Type::Unknown,
+                }
+            }
+            _ => self.infer(operation, environment),
+        }
+    }
+
+    fn check_call(
+        &mut self,
+        name: &str,
+        signature: &Signature,
+        arguments: &[ASTOperation],
+        types: &[Type],
+        associate: &CodeAssociate,
+    ) -> Type {
+        let max = signature.params.len();
+        if types.len() < signature.required || types.len() > max {
+            self.errors.push(CompileErrors::ArgumentCount(
+                argument_count(name, signature.required, max, types.len()),
+                self.located(associate),
+            ));
+            return signature.returns.clone();
+        }
+        for (index, (expected, found)) in signature.params.iter().zip(types).enumerate() {
+            if expected.is_empty() || *found == Type::Unknown || expected.contains(found) {
+                continue;
+            }
+            let expected = expected
+                .iter()
+                .map(|expected| expected.to_string())
+                .collect::<Vec<String>>()
+                .join(" or ");
+            self.mismatch(
+                format!(
+                    "Argument {} of {} must be {}, found {}",
+                    index + 1,
+                    name,
+                    expected,
+                    found
+                ),
+                arguments[index].associate(),
+            );
+        }
+        signature.returns.clone()
+    }
+
+    fn create(
+        &mut self,
+        name: &str,
+        params: &[ASTOperation],
+        associate: &CodeAssociate,
+        environment: &mut Environment,
+    ) -> Type {
+        let arguments = match params {
+            [ASTOperation::Set(operations, _)] => operations.as_slice(),
+            _ => params,
+        };
+        let types = arguments
+            .iter()
+            .map(|argument| self.infer(argument, environment))
+            .collect::<Vec<Type>>();
+        if self.structs.contains_key(name) {
+            // a struct instance belongs to the entity it is created with, if any
+            let signature = Signature {
+                params: vec![vec![Type::Entity]],
+                required: 0,
+                returns: Type::Struct(name.to_string()),
+            };
+            return self.check_call(name, &signature, arguments, &types, associate);
+        }
+        if name == "BlockPos" {
+            return self.create_position(arguments, &types, associate);
+        }
+        match constructor(name) {
+            Some(signature) => self.check_call(name, &signature, arguments, &types, associate),
+            None => {
+                self.undefined(format!("Unknown type {}", name), associate);
+                Type::Unknown
+            }
+        }
+    }
+
+    /// A position is made of three coordinates, or a single string holding all of them.
+    fn create_position(
+        &mut self,
+        arguments: &[ASTOperation],
+        types: &[Type],
+        associate: &CodeAssociate,
+    ) -> Type {
+        let signature = match types.len() {
+            1 => signature(1, &[&[Type::String]], Type::BlockPos),
+            _ => signature(3, &[&COORDINATE, &COORDINATE, &COORDINATE], Type::BlockPos),
+        }
+        .unwrap();
+        self.check_call("BlockPos", &signature, arguments, types, associate);
+        let single = arguments.len() == 1;
+        for argument in arguments {
+            if let ASTOperation::LiteralString(value, associate) = argument {
+                let parts = value.split_whitespace().collect::<Vec<&str>>();
+                let valid = if single {
+                    parts.len() == 3 && parts.iter().all(|part| Coordinate::parse(part).is_some())
+                } else {
+                    Coordinate::parse(value).is_some()
+                };
+                if !valid {
+                    let expected = if single { "a position" } else { "a coordinate" };
+                    self.mismatch(format!("\"{}\" is not {}", value, expected), associate);
+                }
+            }
+        }
+        Type::BlockPos
+    }
+
+    /// The module an import in the file being checked refers to.
+    fn resolve(&self, path: &str) -> String {
+        resolve(path, self.checking.last().map_or("", |file| file.as_str()))
+    }
+
+    /// The exports of a module, checking it the first time it is used.
+    fn exports_of(&mut self, name: &str, associate: &CodeAssociate) -> Option<Exports> {
+        if !self.checked.contains_key(name) {
+            if self.checking.iter().any(|file| file == name) {
+                // a circular import sees nothing of the file that is still being checked
+                return None;
+            }
+            let Some(file) = module_id(name) else {
+                self.import_error(format!("Module {} does not exist", name), associate);
+                return None;
+            };
+            let mut lexer = Lexer::new(source(file).unwrap().code, file).quiet();
+            lexer.tokenizer();
+            let mut ast = AST::new(lexer.flush().to_vec());
+            ast.generate();
+            self.check(name, &ast.flush().to_vec());
+        }
+        self.checked.get(name).cloned()
+    }
+
+    fn import(
+        &mut self,
+        name: &str,
+        names: &Option<Vec<(String, String)>>,
+        associate: &CodeAssociate,
+        environment: &mut Environment,
+    ) {
+        let Some(exports) = self.exports_of(name, associate) else {
+            return;
+        };
+        let bindings = exports.bindings(names);
+        for (exported, local) in bindings {
+            let origin = (name.to_string(), exported.clone());
+            if environment.imports.get(&local) == Some(&origin) {
+                continue;
+            }
+            if !exports.variables.contains_key(&exported)
+                && !exports.functions.contains_key(&exported)
+            {
+                self.import_error(
+                    format!("{} is not exported by {}", exported, name),
+                    associate,
+                );
+                continue;
+            }
+            if environment.variables.contains_key(&local)
+                || environment.functions.contains_key(&local)
+            {
+                self.import_error(
+                    format!(
+                        "{} is already defined, use `{} as <name>` to import it",
+                        local, exported
+                    ),
+                    associate,
+                );
+                continue;
+            }
+            if let Some(variable) = exports.variables.get(&exported) {
+                environment
+                    .variables
+                    .insert(local.clone(), variable.clone());
+            } else if let Some(parameters) = exports.functions.get(&exported) {
+                environment
+                    .functions
+                    .insert(local.clone(), parameters.clone());
+            }
+            environment.imports.insert(local, origin);
+        }
+    }
+
+    /// Adds a variable or function under the name it is exported as.
+    fn export(
+        &mut self,
+        exported: &str,
+        variable: Option<Type>,
+        parameters: Option<Vec<(String, Type)>>,
+        associate: &CodeAssociate,
+        environment: &mut Environment,
+    ) {
+        let exports = &mut environment.exports;
+        if exports.variables.contains_key(exported) || exports.functions.contains_key(exported) {
+            let associate = self.located(associate);
+            self.errors.push(CompileErrors::ExportError(
+                format!("{} is already exported", exported),
+                associate,
+            ));
+            return;
+        }
+        if let Some(variable) = variable {
+            exports.variables.insert(exported.to_string(), variable);
+        } else if let Some(parameters) = parameters {
+            exports.functions.insert(exported.to_string(), parameters);
+        }
+    }
+
+    /// A summary of what a checked module exports, one name per line in alphabetical order.
+    pub fn interface(&self, name: &str) -> Option<String> {
+        let exports = self.checked.get(name)?;
+        let mut lines = vec![];
+        for (variable, variable_type) in &exports.variables {
+            lines.push((variable, format!("let {}: {}", variable, variable_type)));
+        }
+        for (function, parameters) in &exports.functions {
+            let parameters = parameters
+                .iter()
+                .map(|(parameter, parameter_type)| match parameter_type {
+                    Type::Unknown => parameter.clone(),
+                    parameter_type => format!("{}: {}", parameter, parameter_type),
+                })
+                .collect::<Vec<String>>();
+            lines.push((
+                function,
+                format!("fn {}({})", function, parameters.join(", ")),
+            ));
+        }
+        lines.sort();
+        let lines = lines
+            .into_iter()
+            .map(|(_, line)| line)
+            .collect::<Vec<String>>();
+        Some(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::sources::NO_FILE;
+
+    fn check(code: &str) -> TypeChecker {
+        let mut lexer = Lexer::new(code.to_string(), NO_FILE).quiet();
+        lexer.tokenizer();
+        let mut ast = AST::new(lexer.flush().to_vec());
+        ast.generate();
+        let mut checker = TypeChecker::new();
+        checker.check("code", ast.flush());
+        checker
+    }
+
+    fn errors(code: &str) -> Vec<String> {
+        check(code)
+            .errors
+            .iter()
+            .map(|error| match error {
+                CompileErrors::TypeMismatch(message, _)
+                | CompileErrors::ArgumentCount(message, _)
+                | CompileErrors::UndefinedName(message, _)
+                | CompileErrors::ImportError(message, _) => message.clone(),
+                _ => "other error".to_string(),
+            })
+            .collect()
+    }
+
+    fn warnings(code: &str) -> Vec<String> {
+        check(code)
+            .warnings
+            .into_iter()
+            .map(|(message, _)| message)
+            .collect()
+    }
+
+    #[test]
+    fn words_argument_counts() {
+        assert_eq!(
+            argument_count("tp", 1, 2, 3),
+            "tp takes 1 to 2 arguments but 3 were given"
+        );
+        assert_eq!(
+            argument_count("kill", 1, 1, 0),
+            "kill takes 1 argument but 0 were given"
+        );
+        assert_eq!(
+            argument_count("say", 0, 0, 1),
+            "say takes 0 arguments but 1 was given"
+        );
+    }
+
+    #[test]
+    fn accepts_well_typed_code() {
+        let code = "let points: int = 5;\nlet name = \"Steve\";\nfn greet(who) {\n    tellraw(@a, \"hi {who}\");\n}\ngreet(name);\n";
+        assert_eq!(errors(code), Vec::<String>::new());
+        assert_eq!(warnings(code), Vec::<String>::new());
+    }
+
+    #[test]
+    fn reports_mismatched_annotations() {
+        assert_eq!(
+            errors("let points: int = \"five\";\n"),
+            vec!["Variable points is declared as int but given string"]
+        );
+    }
+
+    #[test]
+    fn reports_unknown_names() {
+        assert_eq!(
+            errors("tellraw(target, \"hi\");\n"),
+            vec!["Unknown variable target"]
+        );
+        assert_eq!(errors("jump(1);\n"), vec!["Unknown function jump"]);
+        assert_eq!(
+            errors("tellraw(target, \"hi\");\nlet target = @a;\n"),
+            vec!["Variable target is used before it is defined"]
+        );
+    }
+
+    #[test]
+    fn checks_builtin_calls() {
+        assert_eq!(
+            errors("tellraw(@a);\n"),
+            vec!["tellraw takes 2 arguments but 1 was given"]
+        );
+        assert_eq!(errors("cmd(\"say hi\", false);\n"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn warns_about_unused_locals() {
+        let code = "let a = 1;\nif a == 1 {\n    let unused = 2;\n    let _skipped = 3;\n}\n";
+        assert_eq!(warnings(code), vec!["Variable unused is never used"]);
+    }
+
+    #[test]
+    fn indexes_lists_with_numbers() {
+        let code = "let st = new Storage(\"mypack:state\");\nlet items = st.list(\"items\", \"int\");\nlet score = new Scoreboard(\"points\", \"dummy\").get_player(\"@s\");\nitems.get(score);\n";
+        assert_eq!(
+            errors(code),
+            vec!["Argument 1 of list.get must be number, found score"]
+        );
+    }
+
+    #[test]
+    fn assigns_to_the_variable_around_a_block() {
+        let code = "let a = 1;\nlet b = 2;\nif a == b {\n    a = 5;\n}\n";
+        assert_eq!(errors(code), Vec::<String>::new());
+        assert_eq!(warnings(code), Vec::<String>::new());
+    }
+
+    #[test]
+    fn reports_strings_reading_later_variables() {
+        let code = "let a = 1;\nif a == 1 {\n    tellraw(@a, \"{b}\");\n    let b = 2;\n}\n";
+        assert_eq!(
+            errors(code),
+            vec!["Variable b is used before it is defined"]
+        );
+    }
+
+    #[test]
+    fn assigns_struct_fields() {
+        let code = "struct Player {\n    health: int\n    fn heal(amount: int) {\n        self.health = self.health + amount;\n    }\n}\nlet p = new Player(@s);\np.health = 20;\n";
+        assert_eq!(errors(code), Vec::<String>::new());
+        assert_eq!(
+            errors("struct Player {\n    health: int\n}\nlet p = new Player(@s);\np.mana = 2;\n"),
+            vec!["Struct Player has no field mana"]
+        );
+    }
+}
@@ -0,0 +1,736 @@
+//! Commands as text: raw commands written in the source, either as `/say hi` statements or
+//! with `cmd("...")`, and the vanilla command tree every generated command is checked against
+//! before the datapack is written.
+
+use std::collections::HashMap;
+
+use crate::compile::objects::Objects;
+
+/// The usages of every vanilla command, see the file for its syntax.
+const COMMAND_TREE: &str = include_str!("commands.tree");
+
+/// The kinds of arguments in the command tree, named like in the file.
+#[derive(Clone, Debug, PartialEq)]
+enum ArgumentType {
+    Int,
+    Float,
+    Bool,
+    Word,
+    Token,
+    Greedy,
+    Entity,
+    ScoreHolder,
+    Objective,
+    Operation,
+    Range,
+    BlockPos,
+    ColumnPos,
+    Vec3,
+    Vec2,
+    Rotation,
+    Swizzle,
+    Resource,
+    TagResource,
+    Function,
+    Block,
+    BlockPredicate,
+    Item,
+    ItemPredicate,
+    NbtCompound,
+    NbtTag,
+    NbtPath,
+    Component,
+    Time,
+    Slot,
+}
+
+fn is_word_char(char: char) -> bool {
+    char.is_ascii_alphanumeric() || matches!(char, '_' | '-' | '.' | '+')
+}
+
+fn is_resource_char(char: char) -> bool {
+    char.is_ascii_lowercase()
+        || char.is_ascii_digit()
+        || matches!(char, '_' | '-' | '.' | '/' | ':')
+}
+
+/// The length of the part of `input` made of the given characters.
+fn length_while(input: &str, accept: fn(char) -> bool) -> usize {
+    input.find(|char| !accept(char)).unwrap_or(input.len())
+}
+
+/// The length of a number at the start of `input`, or 0. A `.` directly followed by another
+/// one is left alone, so ranges like `1..5` keep their bounds apart.
+fn number_length(input: &str, decimals: bool) -> usize {
+    let bytes = input.as_bytes();
+    let mut length = 0;
+    if bytes.first() == Some(&b'-') {
+        length += 1;
+    }
+    let start = length;
+    while length < bytes.len() && bytes[length].is_ascii_digit() {
+        length += 1;
+    }
+    let digits = length - start;
+    if decimals
+        && length < bytes.len()
+        && bytes[length] == b'.'
+        && bytes.get(length + 1) != Some(&b'.')
+    {
+        length += 1;
+        while length < bytes.len() && bytes[length].is_ascii_digit() {
+            length += 1;
+        }
+    }
+    if digits == 0 && length - start <= 1 {
+        return 0;
+    }
+    length
+}
+
+/// The length of a quoted string at the start of `input`, quotes included.
+fn quoted_length(input: &str) -> Option<usize> {
+    let quote = input
+        .chars()
+        .next()
+        .filter(|char| *char == '"' || *char == '\'')?;
+    let mut chars = input.char_indices().skip(1);
+    while let Some((index, char)) = chars.next() {
+        if char == '\\' {
+            chars.next();
+        } else if char == quote {
+            return Some(index + 1);
+        }
+    }
+    None
+}
+
+/// The length of a `{...}` or `[...]` group at the start of `input`, brackets included.
+fn balanced_length(input: &str) -> Option<usize> {
+    let mut closers = vec![];
+    let mut index = 0;
+    while index < input.len() {
+        let rest = &input[index..];
+        let char = rest.chars().next().unwrap();
+        match char {
+            '"' | '\'' if !closers.is_empty() => {
+                index += quoted_length(rest)?;
+                continue;
+            }
+            '{' => closers.push('}'),
+            '[' => closers.push(']'),
+            '}' | ']' => {
+                if closers.pop() != Some(char) {
+                    return None;
+                }
+                if closers.is_empty() {
+                    return Some(index + 1);
+                }
+            }
+            _ if closers.is_empty() => return None,
+            _ => {}
+        }
+        index += char.len_utf8();
+    }
+    None
+}
+
+/// The length of `count` coordinates separated by spaces, such as `~ ~1 ~` or `^ ^ ^2`.
+fn coordinates_length(input: &str, count: usize, decimals: bool) -> Option<usize> {
+    let mut length = 0;
+    for index in 0..count {
+        if index > 0 {
+            if !input[length..].starts_with(' ') {
+                return None;
+            }
+            length += 1;
+        }
+        let part = &input[length..];
+        let relative = part.starts_with('~') || part.starts_with('^');
+        let prefix = if relative { 1 } else { 0 };
+        let number = number_length(&part[prefix..], decimals || relative);
+        if !relative && number == 0 {
+            return None;
+        }
+        length += prefix + number;
+    }
+    Some(length)
+}
+
+/// The length of an optional group at the start of `input`, which is 0 when it is missing.
+fn optional_group(input: &str, opener: char) -> Option<usize> {
+    if input.starts_with(opener) {
+        balanced_length(input)
+    } else {
+        Some(0)
+    }
+}
+
+/// The length of a resource location followed by optional `[...]` and `{...}` groups, such
+/// as a block state or an item stack.
+fn resource_with_groups(input: &str) -> Option<usize> {
+    let mut length = ArgumentType::Resource.read(input)?;
+    length += optional_group(&input[length..], '[')?;
+    length += optional_group(&input[length..], '{')?;
+    Some(length)
+}
+
+impl ArgumentType {
+    fn parse(name: &str) -> ArgumentType {
+        match name {
+            "int" => ArgumentType::Int,
+            "float" => ArgumentType::Float,
+            "bool" => ArgumentType::Bool,
+            "word" => ArgumentType::Word,
+            "token" => ArgumentType::Token,
+            "greedy" => ArgumentType::Greedy,
+            "entity" => ArgumentType::Entity,
+            "score_holder" => ArgumentType::ScoreHolder,
+            "objective" => ArgumentType::Objective,
+            "operation" => ArgumentType::Operation,
+            "range" => ArgumentType::Range,
+            "block_pos" => ArgumentType::BlockPos,
+            "column_pos" => ArgumentType::ColumnPos,
+            "vec3" => ArgumentType::Vec3,
+            "vec2" => ArgumentType::Vec2,
+            "rotation" => ArgumentType::Rotation,
+            "swizzle" => ArgumentType::Swizzle,
+            "resource" => ArgumentType::Resource,
+            "tag_resource" => ArgumentType::TagResource,
+            "function" => ArgumentType::Function,
+            "block" => ArgumentType::Block,
+            "block_predicate" => ArgumentType::BlockPredicate,
+            "item" => ArgumentType::Item,
+            "item_predicate" => ArgumentType::ItemPredicate,
+            "nbt_compound" => ArgumentType::NbtCompound,
+            "nbt_tag" => ArgumentType::NbtTag,
+            "nbt_path" => ArgumentType::NbtPath,
+            "component" => ArgumentType::Component,
+            "time" => ArgumentType::Time,
+            "slot" => ArgumentType::Slot,
+            _ => panic!("Unknown argument type {} in the command tree", name),
+        }
+    }
+
+    /// The length of the argument at the start of `input`, if there is a valid one.
+    fn read(&self, input: &str) -> Option<usize> {
+        let length = match self {
+            ArgumentType::Int => number_length(input, false),
+            ArgumentType::Float => number_length(input, true),
+            ArgumentType::Bool => ["true", "false"]
+                .iter()
+                .find(|value| input.starts_with(*value))
+                .map_or(0, |value| value.len()),
+            ArgumentType::Word | ArgumentType::Objective => length_while(input, is_word_char),
+            ArgumentType::Greedy => input.len(),
+            ArgumentType::Entity | ArgumentType::ScoreHolder if input.starts_with('@') => {
+                if !input[1..].starts_with(['a', 'e', 'n', 'p', 'r', 's']) {
+                    return None;
+                }
+                2 + optional_group(&input[2..], '[')?
+            }
+            ArgumentType::Entity => length_while(input, is_word_char),
+            ArgumentType::ScoreHolder => input.find(' ').unwrap_or(input.len()),
+            ArgumentType::Operation => ["+=", "-=", "*=", "/=", "%=", "><", "=", "<", ">"]
+                .iter()
+                .find(|operation| input.starts_with(*operation))
+                .map_or(0, |operation| operation.len()),
+            ArgumentType::Range => {
+                let low = number_length(input, true);
+                match input[low..].strip_prefix("..") {
+                    Some(rest) if low > 0 || number_length(rest, true) > 0 => {
+                        low + 2 + number_length(rest, true)
+                    }
+                    Some(_) => 0,
+                    None => low,
+                }
+            }
+            ArgumentType::BlockPos => coordinates_length(input, 3, false)?,
+            ArgumentType::ColumnPos => coordinates_length(input, 2, false)?,
+            ArgumentType::Vec3 => coordinates_length(input, 3, true)?,
+            ArgumentType::Vec2 | ArgumentType::Rotation => coordinates_length(input, 2, true)?,
+            ArgumentType::Swizzle => {
+                let length = length_while(input, |char| matches!(char, 'x' | 'y' | 'z'));
+                let axes = &input[..length];
+                if axes.chars().any(|axis| axes.matches(axis).count() > 1) {
+                    return None;
+                }
+                length
+            }
+            ArgumentType::Resource => {
+                let length = length_while(input, is_resource_char);
+                if input[..length].matches(':').count() > 1 {
+                    return None;
+                }
+                length
+            }
+            ArgumentType::TagResource | ArgumentType::Function if input.starts_with('#') => {
+                1 + ArgumentType::Resource.read(&input[1..])?
+            }
+            ArgumentType::TagResource | ArgumentType::Function => {
+                ArgumentType::Resource.read(input)?
+            }
+            ArgumentType::BlockPredicate | ArgumentType::ItemPredicate
+                if input.starts_with('#') =>
+            {
+                1 + resource_with_groups(&input[1..])?
+            }
+            ArgumentType::ItemPredicate if input.starts_with('*') => {
+                1 + optional_group(&input[1..], '[')?
+            }
+            ArgumentType::Block
+            | ArgumentType::BlockPredicate
+            | ArgumentType::Item
+            | ArgumentType::ItemPredicate => resource_with_groups(input)?,
+            ArgumentType::NbtCompound if input.starts_with('{') => balanced_length(input)?,
+            ArgumentType::NbtCompound => 0,
+            ArgumentType::NbtTag | ArgumentType::Component | ArgumentType::Token => {
+                if input.starts_with(['"', '\'']) {
+                    quoted_length(input)?
+                } else if input.starts_with(['{', '[']) {
+                    balanced_length(input)?
+                } else {
+                    input.find(' ').unwrap_or(input.len())
+                }
+            }
+            ArgumentType::NbtPath => {
+                let mut length = 0;
+                while length < input.len() && !input[length..].starts_with(' ') {
+                    let rest = &input[length..];
+                    length += if rest.starts_with(['"', '\'']) {
+                        quoted_length(rest)?
+                    } else if rest.starts_with(['{', '[']) {
+                        balanced_length(rest)?
+                    } else {
+                        rest.chars().next().unwrap().len_utf8()
+                    };
+                }
+                length
+            }
+            ArgumentType::Time => {
+                let length = number_length(input, true);
+                if length > 0 && input[length..].starts_with(['d', 's', 't']) {
+                    length + 1
+                } else {
+                    length
+                }
+            }
+            ArgumentType::Slot => length_while(input, |char| {
+                is_resource_char(char) && char != '/' || char == '*'
+            }),
+        };
+        if length == 0 {
+            None
+        } else {
+            Some(length)
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Node {
+    literals: Vec<(String, usize)>,
+    arguments: Vec<(String, ArgumentType, usize)>,
+    /// Whether a command can end after this node.
+    executable: bool,
+    /// The literal path, from the root, of the node whose children follow this one.
+    redirect: Option<Vec<String>>,
+}
+
+/// The vanilla commands of a Minecraft version, parsed like the game parses them: literals
+/// and arguments separated by single spaces, from the root to a node a command can end on.
+#[derive(Clone, Debug)]
+pub struct CommandTree {
+    nodes: Vec<Node>,
+}
+
+/// Splits a usage of the command tree into its version gate and its tokens.
+fn gated_tokens(line: &str) -> (Option<&str>, Vec<&str>) {
+    let mut tokens: Vec<&str> = line.split_whitespace().collect();
+    if tokens[0].starts_with(">=") || tokens[0].starts_with('<') && tokens[0].len() > 1 {
+        let gate = tokens.remove(0);
+        return (Some(gate), tokens);
+    }
+    (None, tokens)
+}
+
+/// Whether a version gate such as `>=18` or `<7` includes a pack format.
+fn gate_includes(gate: &str, pack_format: u32) -> bool {
+    match gate.strip_prefix(">=") {
+        Some(minimum) => pack_format >= minimum.parse::<u32>().unwrap(),
+        None => pack_format < gate[1..].parse::<u32>().unwrap(),
+    }
+}
+
+fn is_argument(token: &str) -> bool {
+    token.starts_with('<') && token.ends_with('>') && token.contains(':')
+}
+
+/// Expands the fragments and literal alternatives of a usage into every usage it stands for.
+fn expand(tokens: &[&str], fragments: &HashMap<String, Vec<String>>) -> Vec<Vec<String>> {
+    let mut usages: Vec<Vec<String>> = vec![vec![]];
+    for token in tokens {
+        let options: Vec<Vec<String>> = match token.strip_prefix('%') {
+            Some(name) => fragments[name]
+                .iter()
+                .map(|fragment| fragment.split_whitespace().map(String::from).collect())
+                .collect(),
+            None => vec![vec![token.to_string()]],
+        };
+        usages = usages
+            .iter()
+            .flat_map(|usage| {
+                options
+                    .iter()
+                    .map(move |option| [usage.clone(), option.clone()].concat())
+            })
+            .collect();
+    }
+
+    let mut expanded: Vec<Vec<String>> = vec![];
+    for usage in usages {
+        let mut alternatives: Vec<Vec<String>> = vec![vec![]];
+        for token in usage {
+            let optional = token.starts_with('[') && token.ends_with(']');
+            let inner = if optional {
+                &token[1..token.len() - 1]
+            } else {
+                &token[..]
+            };
+            let options: Vec<String> = if is_argument(inner) || !inner.contains('|') {
+                vec![token.clone()]
+            } else if optional {
+                inner
+                    .split('|')
+                    .map(|option| format!("[{}]", option))
+                    .collect()
+            } else {
+                inner.split('|').map(String::from).collect()
+            };
+            alternatives = alternatives
+                .iter()
+                .flat_map(|usage| {
+                    options
+                        .iter()
+                        .map(move |option| [usage.clone(), vec![option.clone()]].concat())
+                })
+                .collect();
+        }
+        expanded.extend(alternatives);
+    }
+    expanded
+}
+
+/// Whether a command exists in any version, so a missing one can be told apart from a typo.
+fn is_command(root: &str) -> bool {
+    COMMAND_TREE
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with(['#', '%']))
+        .any(|line| gated_tokens(line).1[0].split('|').any(|name| name == root))
+}
+
+impl CommandTree {
+    /// The commands available to the given pack format.
+    pub fn new(pack_format: u32) -> CommandTree {
+        let mut tree = CommandTree {
+            nodes: vec![Node::default()],
+        };
+        let mut fragments: HashMap<String, Vec<String>> = HashMap::new();
+        for line in COMMAND_TREE.lines().map(|line| line.trim()) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(fragment) = line.strip_prefix('%') {
+                let (name, alternatives) = fragment.split_once(' ').unwrap();
+                fragments.insert(
+                    name.to_string(),
+                    alternatives
+                        .split(';')
+                        .map(|alternative| alternative.trim().to_string())
+                        .collect(),
+                );
+                continue;
+            }
+            let (gate, tokens) = gated_tokens(line);
+            if gate.is_some_and(|gate| !gate_includes(gate, pack_format)) {
+                continue;
+            }
+            for usage in expand(&tokens, &fragments) {
+                tree.insert(&usage);
+            }
+        }
+        tree
+    }
+
+    fn insert(&mut self, usage: &[String]) {
+        let mut node = 0;
+        let mut tokens = usage.iter();
+        while let Some(token) = tokens.next() {
+            if token == "->" || token == "->?" {
+                let target = tokens.next().unwrap();
+                self.nodes[node].executable |= token == "->?";
+                self.nodes[node].redirect = Some(if target == "/" {
+                    vec![]
+                } else {
+                    target.split('.').map(String::from).collect()
+                });
+                return;
+            }
+            let token = match token
+                .strip_prefix('[')
+                .and_then(|token| token.strip_suffix(']'))
+            {
+                Some(token) => {
+                    self.nodes[node].executable = true;
+                    token
+                }
+                None => token,
+            };
+            node = self.child(node, token);
+        }
+        self.nodes[node].executable = true;
+    }
+
+    /// The child of a node for a token of a usage, which is added if it is new.
+    fn child(&mut self, node: usize, token: &str) -> usize {
+        let child = self.nodes.len();
+        if is_argument(token) {
+            let (name, kind) = token[1..token.len() - 1].split_once(':').unwrap();
+            let kind = ArgumentType::parse(kind);
+            let existing = self.nodes[node]
+                .arguments
+                .iter()
+                .find(|(existing, existing_kind, _)| existing == name && *existing_kind == kind);
+            if let Some((_, _, existing)) = existing {
+                return *existing;
+            }
+            self.nodes[node]
+                .arguments
+                .push((name.to_string(), kind, child));
+        } else {
+            let existing = self.nodes[node]
+                .literals
+                .iter()
+                .find(|(existing, _)| existing == token);
+            if let Some((_, existing)) = existing {
+                return *existing;
+            }
+            self.nodes[node].literals.push((token.to_string(), child));
+        }
+        self.nodes.push(Node::default());
+        child
+    }
+
+    fn resolve(&self, path: &[String]) -> usize {
+        path.iter().fold(0, |node, literal| {
+            self.nodes[node]
+                .literals
+                .iter()
+                .find(|(existing, _)| existing == literal)
+                .map(|(_, child)| *child)
+                .unwrap()
+        })
+    }
+
+    /// Checks that a command parses, e.g. `scoreboard objective add x dummy` fails on
+    /// `objective`.
+    pub fn validate(&self, command: &str) -> Result<(), String> {
+        if command.is_empty() {
+            return Err("Empty command".to_string());
+        }
+        if command.contains('\n') {
+            return Err("A command must be on a single line".to_string());
+        }
+        let root = command.split(' ').next().unwrap();
+        if !self.nodes[0]
+            .literals
+            .iter()
+            .any(|(literal, _)| literal == root)
+        {
+            if is_command(root) {
+                return Err(format!(
+                    "The command '{}' does not exist in the target version",
+                    root
+                ));
+            }
+            return Err(format!("Unknown command '{}'", root));
+        }
+        self.walk(0, command, 0).map_err(|(position, message)| {
+            format!("{} at `{}<--[HERE]`", message, &command[..position])
+        })
+    }
+
+    /// Parses the rest of a command after a node, returning the furthest position reached
+    /// and what was expected there when it does not parse.
+    fn walk(&self, node: usize, input: &str, position: usize) -> Result<(), (usize, String)> {
+        if input.is_empty() {
+            if self.nodes[node].executable {
+                return Ok(());
+            }
+            return Err((position, "Incomplete command".to_string()));
+        }
+        let (input, position) = if node == 0 {
+            (input, position)
+        } else {
+            match input.strip_prefix(' ') {
+                Some(input) => (input, position + 1),
+                None => return Err((position, "Expected a space".to_string())),
+            }
+        };
+        let children = match &self.nodes[node].redirect {
+            Some(path) => &self.nodes[self.resolve(path)],
+            None => &self.nodes[node],
+        };
+
+        let mut error: Option<(usize, String)> = None;
+        let mut attempt = |result: Result<(), (usize, String)>| match result {
+            Ok(()) => true,
+            Err(failure) => {
+                if error.as_ref().is_none_or(|error| failure.0 > error.0) {
+                    error = Some(failure);
+                }
+                false
+            }
+        };
+        for (literal, child) in &children.literals {
+            let rest = input.strip_prefix(literal.as_str());
+            if rest.is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+                && attempt(self.walk(*child, rest.unwrap(), position + literal.len()))
+            {
+                return Ok(());
+            }
+        }
+        for (_, kind, child) in &children.arguments {
+            if let Some(length) = kind.read(input) {
+                if attempt(self.walk(*child, &input[length..], position + length)) {
+                    return Ok(());
+                }
+            }
+        }
+
+        match error {
+            Some(error) if error.0 > position => Err(error),
+            _ => {
+                let mut expected: Vec<String> = children
+                    .literals
+                    .iter()
+                    .map(|(literal, _)| literal.clone())
+                    .collect();
+                expected.extend(
+                    children
+                        .arguments
+                        .iter()
+                        .map(|(name, _, _)| format!("<{}>", name)),
+                );
+                if expected.is_empty() {
+                    return Err((position, "Expected end of command".to_string()));
+                }
+                Err((position, format!("Expected {}", expected.join(" | "))))
+            }
+        }
+    }
+}
+
+/// How a value is written inside a command: constants as their literal, scores as
+/// `<player> <objective>` and storage paths as `storage <storage> <path>`.
+pub fn command_argument(value: &Objects) -> Option<String> {
+    match value {
+        Objects::String(value) => Some(value.clone()),
+        Objects::Number(value) => Some(value.to_string()),
+        Objects::Decimal(value) => Some(value.to_string()),
+        Objects::Boolean(value) => Some(value.to_string()),
+        Objects::Entity(selector) => Some(selector.clone()),
+        Objects::Dimension(name) => Some(name.clone()),
+        Objects::Coordinate(coordinate) => Some(coordinate.to_string()),
+        Objects::BlockPos(x, y, z) => Some(format!("{} {} {}", x, y, z)),
+        Objects::Scoreboard(name, _, _) => Some(name.clone()),
+        Objects::ScoreboardPlayerPair(objective, player, _) => {
+            Some(format!("{} {}", player, objective))
+        }
+        Objects::Storage(name) => Some(name.clone()),
+        Objects::StoragePath(storage, path, _) => Some(format!("storage {} {}", storage, path)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile::obj::storage::NbtType;
+
+    #[test]
+    fn accepts_vanilla_commands() {
+        let tree = CommandTree::new(48);
+        for command in [
+            "say hello there",
+            "scoreboard objectives add points dummy",
+            "scoreboard players operation @s points += $global points",
+            "execute as @a[tag=ready] at @s if score @s points matches 1.. run tp @s ~ ~1 ~",
+            "data modify storage mypack:state player.hp set value 20",
+            "tellraw @a {\"text\":\"hi\",\"color\":\"gold\"}",
+            "function mypack:code.0",
+        ] {
+            assert_eq!(tree.validate(command), Ok(()), "{}", command);
+        }
+    }
+
+    #[test]
+    fn points_at_the_first_invalid_token() {
+        let tree = CommandTree::new(48);
+        assert_eq!(
+            tree.validate("scoreboard objective add x dummy"),
+            Err("Expected objectives | players at `scoreboard <--[HERE]`".to_string())
+        );
+        assert_eq!(
+            tree.validate("reload now"),
+            Err("Expected end of command at `reload <--[HERE]`".to_string())
+        );
+        assert_eq!(
+            tree.validate("scoreboard"),
+            Err("Incomplete command at `scoreboard<--[HERE]`".to_string())
+        );
+        assert_eq!(
+            tree.validate("sya hi"),
+            Err("Unknown command 'sya'".to_string())
+        );
+    }
+
+    #[test]
+    fn gates_commands_by_pack_format() {
+        assert_eq!(
+            CommandTree::new(15).validate("return run say hi"),
+            Err("Expected <value> at `return <--[HERE]`".to_string())
+        );
+        assert_eq!(CommandTree::new(18).validate("return run say hi"), Ok(()));
+        assert_eq!(
+            CommandTree::new(10).validate("return 1"),
+            Err("The command 'return' does not exist in the target version".to_string())
+        );
+        assert_eq!(
+            CommandTree::new(4).validate("replaceitem entity @s weapon.mainhand stone"),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn writes_values_as_arguments() {
+        let score = Objects::ScoreboardPlayerPair(
+            "points".to_string(),
+            "@s".to_string(),
+            Box::new(Objects::Number(0)),
+        );
+        assert_eq!(command_argument(&score).unwrap(), "@s points");
+        let path = Objects::StoragePath(
+            "mypack:state".to_string(),
+            "player.hp".to_string(),
+            NbtType::Int,
+        );
+        assert_eq!(
+            command_argument(&path).unwrap(),
+            "storage mypack:state player.hp"
+        );
+        assert_eq!(command_argument(&Objects::Number(-3)).unwrap(), "-3");
+        assert_eq!(command_argument(&Objects::Unknown), None);
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::format,
     mem::{discriminant, Discriminant},
     process::exit,
@@ -12,41 +12,97 @@ use crate::{
         operations::{ASTOperation, Operator},
     },
     compile::obj::std::VariableObject,
-    errors::error::{compile_error, CompileErrors},
-    lexer::lexer::Lexer,
+    errors::{
+        associate::CodeAssociate,
+        error::{compile_error, CompileErrors},
+        sources::{module_id, source},
+    },
+    lexer::lexer::{empty_associate, Lexer},
 };
 
 use super::{
-    mcstatements::{
-        compile_into_mcstatement, execute_step_str, ExecuteSteps, MinecraftStatementObject,
-        Statements,
-    },
+    checker::{argument_count, Type},
+    commands::{command_argument, CommandTree},
+    macros::{macro_key, macro_placeholder, macro_placeholders, macro_setup, MACRO_ARGUMENTS_PATH},
+    mcstatements::{compile_into_mcstatement, execute_step_from, ExecuteSteps, Statements},
+    modules::{module_function, resolve},
+    names::{NameAllocator, Temporaries},
     obj::{
-        scoreboard::ScoreboardPlayerPairObject,
+        basic::StringObject,
+        block::parse_block,
+        blockpos::{BlockPosObject, Coordinate},
+        list::{runtime_list, ListObject},
+        scoreboard::{is_objective_name, ScoreboardObject, ScoreboardPlayerPairObject},
         std::{
-            compile_into_if_statement, compile_into_mutation_variable, compile_into_variable,
-            compile_into_while_loop,
+            compile_into_execute_block, compile_into_if_statement, compile_into_mutation_variable,
+            compile_into_variable, compile_into_while_loop,
         },
+        structs::{FieldKind, StructDefinition, StructObject},
+        text::{TextContent, TextObject},
+    },
+    objects::{
+        builtin_function, builtin_variable, match_objects, mk_variable, name_into_object, Object,
+        Objects,
     },
-    objects::{match_objects, name_into_object, Object, Objects},
+    symbols::SymbolTable,
+    version::{first_version, pack_format, Feature, DEFAULT_TARGET},
 };
 
 pub struct Compiler {
     pub scopes: Vec<Scope>,
     pub namespace: String,
+    /// The targeted Minecraft version, such as `1.20.4`.
+    pub target: String,
+    pub pack_format: u32,
     pub outputs: HashMap<String, String>,
-    pub prepared_files: HashMap<String, String>,
+    /// Commands for the function run when the datapack loads, with the code they come from.
+    pub load: Vec<(String, CodeAssociate)>,
+    /// Commands for the function run every tick, with the code they come from.
+    pub tick: Vec<(String, CodeAssociate)>,
+    /// Function tags such as `minecraft:tick` and the functions they run.
+    pub function_tags: HashMap<String, Vec<String>>,
+    /// Runtime values that commands can use through macro arguments, by argument name.
+    pub macro_arguments: HashMap<String, Objects>,
+    /// The number of macro functions generated so far.
+    pub macro_functions: usize,
+    /// The vanilla commands of the target version, which every generated command must parse
+    /// against.
+    pub commands: CommandTree,
+    /// Raw commands written with `cmd("...", false)`, by the call they are written in, which
+    /// are left unchecked.
+    pub unchecked_commands: HashSet<(CodeAssociate, String)>,
+    /// The structs declared so far, by name.
+    pub structs: HashMap<String, StructDefinition>,
+    /// The number of struct instances created so far.
+    pub struct_instances: usize,
+    /// The modules whose function was already run by the first import of them.
+    pub imported_modules: HashSet<String>,
+    /// The namespaces of dependencies left to their own datapack, whose exported functions are
+    /// called there instead of being written into this one.
+    pub external: HashSet<String>,
+    /// The objectives and tags given to the declarations of every module.
+    pub names: NameAllocator,
 }
 
 #[derive(Clone, Debug)]
 pub struct Function {
     pub name: String,
-    pub arguments: Vec<String>,
+    /// The parameters and their optional type annotations.
+    pub arguments: Vec<(String, Option<String>)>,
     pub code: Vec<ASTOperation>,
+    /// The scope the function is declared in, whose variables its code sees.
+    pub scope: String,
+    /// The functions and variables of the module an imported function comes from, which its
+    /// code sees wherever it is called from.
+    pub module: Option<(HashMap<String, Function>, SymbolTable)>,
+    /// The function an exported function is written as in the datapack of its module.
+    pub exported: Option<String>,
+    /// Where the function is declared.
+    pub associate: CodeAssociate,
 }
 #[derive(Clone, Debug)]
 pub struct Scope {
-    pub variables: HashMap<String, Variable>,
+    pub variables: SymbolTable,
     pub statements: Vec<ASTOperation>,
     pub namespace: String,
     pub functions: HashMap<String, Function>,
@@ -54,6 +110,24 @@ pub struct Scope {
     pub exported_variables: HashMap<String, Variable>,
     pub scopes: Vec<Scope>,
     pub name: String,
+    /// The module and exported name each imported name comes from.
+    pub imports: HashMap<String, (String, String)>,
+    /// Commands run before the statements of a function, passing its arguments in, with the
+    /// call they come from.
+    pub setup: Vec<(String, CodeAssociate)>,
+    /// Commands run after them, such as removing the tags of entity arguments.
+    pub teardown: Vec<(String, CodeAssociate)>,
+    /// The temporaries of the function.
+    pub temporaries: Temporaries,
+    /// Commands computing the values of the expressions of the statement being compiled, run
+    /// before it.
+    pub prelude: Vec<String>,
+    /// The temporaries holding the values of those expressions which are not used yet, by the
+    /// name of their scoreboard.
+    pub expressions: HashMap<String, usize>,
+    /// The objectives of expression temporaries already added in the function, which are only
+    /// added by their first use.
+    pub declared: HashSet<String>,
 }
 #[derive(Clone, Debug)]
 pub struct Variable {
@@ -62,12 +136,25 @@ pub struct Variable {
     pub static_variable: bool,
 }
 impl Compiler {
-    pub fn new(namespace: &'static str) -> Compiler {
+    pub fn new(namespace: &str) -> Compiler {
         Compiler {
             scopes: vec![],
             namespace: namespace.to_string(),
+            target: DEFAULT_TARGET.to_string(),
+            pack_format: pack_format(DEFAULT_TARGET).unwrap(),
             outputs: HashMap::new(),
-            prepared_files: HashMap::new(),
+            load: vec![],
+            tick: vec![],
+            function_tags: HashMap::new(),
+            macro_arguments: HashMap::new(),
+            macro_functions: 0,
+            commands: CommandTree::new(pack_format(DEFAULT_TARGET).unwrap()),
+            unchecked_commands: HashSet::new(),
+            structs: HashMap::new(),
+            struct_instances: 0,
+            imported_modules: HashSet::new(),
+            external: HashSet::new(),
+            names: NameAllocator::default(),
         }
     }
 
@@ -75,9 +162,24 @@ impl Compiler {
         &mut self,
         scope: &mut Scope,
         value: Rc<dyn Object>,
+        associate: &CodeAssociate,
     ) -> (String, Option<Scope>) {
         match value.get_type() {
-            Objects::MCStatement(statement) => return compile_into_mcstatement(statement),
+            Objects::MCStatement(Statements::Helper(name, body, arguments)) => {
+                // indexing a list with a score
+                self.require(Feature::Macros, associate);
+                // helpers are written once, the first time they are used
+                self.outputs
+                    .entry(name.clone())
+                    .or_insert(format!("\n{}", body));
+                return compile_into_mcstatement(
+                    Statements::Helper(format!("{}:{}", self.namespace, name), body, arguments),
+                    self.pack_format,
+                );
+            }
+            Objects::MCStatement(statement) => {
+                return compile_into_mcstatement(statement, self.pack_format)
+            }
             Objects::MutationVariable(left, left_obj, operand, right, right_obj) => {
                 eprintln!(
                     "Mutation Variable: {:#?} {:#?} {:#?} {:#?} {:#?}",
@@ -92,11 +194,14 @@ impl Compiler {
                 );
             }
             Objects::Variable(object, scoreboard) => {
-                return compile_into_variable(*object, *scoreboard)
+                return compile_into_variable(*object, *scoreboard, self)
             }
             Objects::IfStatement(statements, code_block) => {
                 return compile_into_if_statement(statements, code_block, scope, self)
             }
+            Objects::ExecuteBlock(steps, code_block) => {
+                return compile_into_execute_block(steps, *code_block, scope, self)
+            }
             Objects::While(name, iterator, code_block) => {
                 let compiled_value =
                     compile_into_while_loop(name, iterator, code_block, scope, self);
@@ -106,7 +211,7 @@ impl Compiler {
             Objects::Array(opts) => {
                 let mut output_str = String::new();
                 for opt in opts {
-                    let (compiled_value, _) = self.compile_into(scope, opt.clone());
+                    let (compiled_value, _) = self.compile_into(scope, opt.clone(), associate);
                     output_str.push_str(&format!("\n{}", compiled_value));
                 }
                 return (output_str, None);
@@ -124,10 +229,28 @@ impl Compiler {
         println!("-----------------");
 
         let mut output_str = String::new();
+        for (command, associate) in &current_scope.setup {
+            self.validate(command, associate);
+            output_str.push_str(&format!("\n{}", command));
+        }
+        // arguments the code never reads are free from the start
+        current_scope.temporaries.release(&current_scope.statements);
         while current_scope.statements.len() > index {
             let current_statement = current_scope.statements[index].clone();
+            current_scope.prelude.clear();
+            current_scope.expressions.clear();
             let value = current_scope.execute(&current_statement, None, self);
-            let (compiled_value, mut new_scope) = self.compile_into(current_scope, value);
+            let (compiled_value, mut new_scope) =
+                self.compile_into(current_scope, value, current_statement.associate());
+            let compiled_value =
+                self.expand_macros(current_scope, compiled_value, current_statement.associate());
+            let compiled_value = current_scope
+                .prelude
+                .drain(..)
+                .chain([compiled_value])
+                .collect::<Vec<String>>()
+                .join("\n");
+            self.validate(&compiled_value, current_statement.associate());
             output_str.push_str(&format!("\n{}", &compiled_value));
 
             index += 1;
@@ -135,9 +258,395 @@ impl Compiler {
                 current_scope.scopes.push(new_scope.clone().unwrap());
                 self.compile(new_scope.as_mut().unwrap());
             }
+            current_scope
+                .temporaries
+                .release(&current_scope.statements[index..]);
+        }
+        for (command, associate) in &current_scope.teardown {
+            self.validate(command, associate);
+            output_str.push_str(&format!("\n{}", command));
+        }
+
+        self.outputs
+            .insert(current_scope.output_name(&self.namespace), output_str);
+    }
+
+    /// Compiles a module into a function of its own.
+    pub fn compile_module(&mut self, path: &str) {
+        let file = module_id(path).unwrap();
+        let mut lexer = Lexer::new(source(file).unwrap().code, file);
+        lexer.tokenizer();
+        let mut ast = AST::new(lexer.flush().to_vec());
+        ast.generate();
+
+        let (namespace, name) = module_function(path, &self.namespace);
+        let mut scope = Scope::new(name, namespace, ast.flush().to_vec(), HashMap::new());
+        self.compile(&mut scope);
+        // exported functions are written on their own for the datapacks depending on this one
+        // without bundling it
+        if scope.namespace == self.namespace {
+            let mut names: Vec<&String> = scope.exported_functions.keys().collect();
+            names.sort();
+            for name in names {
+                let function = scope.exported_function(name).unwrap();
+                let Some(mut external) = Scope::external(&function, &function.associate, self)
+                else {
+                    continue;
+                };
+                let written = self.scopes.iter().any(|written| {
+                    written.namespace == external.namespace && written.name == external.name
+                });
+                if external.namespace == self.namespace && !written {
+                    self.compile(&mut external);
+                    self.scopes.push(external);
+                }
+            }
+        }
+        self.scopes.push(scope);
+    }
+
+    /// The objective or tag of `name` declared in `owner`, a scope or struct of `namespace`,
+    /// short enough for the target version.
+    pub fn name(&mut self, namespace: &str, owner: &str, name: &str) -> String {
+        let short = !self.supports(Feature::LongObjectiveNames);
+        self.names.allocate(namespace, owner, name, short)
+    }
+
+    /// The scoreboard of a variable declared in a scope. Only numbers and booleans are kept in
+    /// scores, other values have an unnamed scoreboard.
+    pub fn variable(
+        &mut self,
+        scope: &Scope,
+        name: &str,
+        value: &Objects,
+        associate: &CodeAssociate,
+    ) -> String {
+        if !matches!(value, Objects::Number(_) | Objects::Boolean(_)) {
+            return String::new();
+        }
+        let short = !self.supports(Feature::LongObjectiveNames);
+        let variable = self
+            .names
+            .variable(&scope.namespace, &scope.name, name, short);
+        self.create_shared(&variable, associate);
+        variable
+    }
+
+    /// The scoreboard of a function argument or loop item, which only lives while the function
+    /// of its scope runs.
+    pub fn temporary(
+        &mut self,
+        scope: &mut Scope,
+        name: &str,
+        value: &Objects,
+        associate: &CodeAssociate,
+    ) -> String {
+        if !matches!(value, Objects::Number(_) | Objects::Boolean(_)) {
+            return String::new();
+        }
+        let slot = scope.temporaries.variable(name);
+        let short = !self.supports(Feature::LongObjectiveNames);
+        let variable = self
+            .names
+            .temporary(&scope.namespace, &scope.name, name, slot, short);
+        self.create_shared(&variable, associate);
+        variable
+    }
+
+    /// The scoreboard holding the value of an expression, which only lives while the statement
+    /// computing it runs.
+    pub fn expression(&mut self, scope: &mut Scope, associate: &CodeAssociate) -> String {
+        let slot = scope.temporaries.expression();
+        let short = !self.supports(Feature::LongObjectiveNames);
+        // not a name the source can declare
+        let name = format!("tmp-{}", slot);
+        let variable = self
+            .names
+            .temporary(&scope.namespace, &scope.name, &name, slot, short);
+        self.create_shared(&variable, associate);
+        if !self.names.shared(&variable) && scope.declared.insert(variable.clone()) {
+            scope
+                .prelude
+                .push(format!("scoreboard objectives add {} dummy", variable));
+        }
+        scope.expressions.insert(variable.clone(), slot);
+        variable
+    }
+
+    /// Creates the objective registers share when the datapack loads.
+    fn create_shared(&mut self, variable: &str, associate: &CodeAssociate) {
+        if self.names.shared(variable) {
+            let (_, objective) = self.score(variable);
+            let create = format!("scoreboard objectives add {} dummy", objective);
+            push_once(&mut self.load, create, associate);
+        }
+    }
+
+    /// The `(holder, objective)` of the score the value of a variable is kept in.
+    pub fn score(&self, variable: &str) -> (String, String) {
+        match (self.names.holder(variable), self.names.objective(variable)) {
+            (Some(holder), Some(objective)) => (holder, objective),
+            _ => {
+                compile_error(CompileErrors::InternalError(
+                    format!("Variable {} has no score", variable),
+                    empty_associate(),
+                ));
+                exit(1);
+            }
+        }
+    }
+
+    /// The value a variable refers to, with the ones kept in scores as the score they are
+    /// kept in. Objects cannot look up scores, so they are given values resolved here.
+    pub fn resolve(&self, value: Rc<dyn Object>) -> Rc<dyn Object> {
+        let Objects::Variable(inner, scoreboard) = value.get_type() else {
+            return value;
+        };
+        match (*inner, *scoreboard) {
+            (
+                inner @ (Objects::Number(_) | Objects::Boolean(_)),
+                Objects::Scoreboard(name, _, _),
+            ) if !name.is_empty() => {
+                let (holder, objective) = self.score(&name);
+                match_objects(Objects::ScoreboardPlayerPair(
+                    objective,
+                    holder,
+                    Box::new(inner),
+                ))
+            }
+            (inner, _) => self.resolve(match_objects(inner)),
+        }
+    }
+
+    /// The command copying a value into the score of a variable, for arguments passed as
+    /// scores.
+    fn copy_score(&self, value: &Objects, variable: &str) -> Option<String> {
+        let (holder, objective) = self.score(variable);
+        let target = format!("{} {}", holder, objective);
+        match value {
+            Objects::Number(number) => {
+                Some(format!("scoreboard players set {} {}", target, number))
+            }
+            Objects::Boolean(boolean) => Some(format!(
+                "scoreboard players set {} {}",
+                target, *boolean as i32
+            )),
+            Objects::ScoreboardPlayerPair(source, player, _) => Some(format!(
+                "scoreboard players operation {} = {} {}",
+                target, player, source
+            )),
+            Objects::Variable(..) => self.copy_score(
+                &self.resolve(match_objects(value.clone())).get_type(),
+                variable,
+            ),
+            _ => None,
+        }
+    }
+
+    /// Targets a Minecraft version such as `1.20.4`.
+    pub fn set_target(&mut self, version: &str) -> Result<(), String> {
+        match pack_format(version) {
+            Some(pack_format) => {
+                self.target = version.to_string();
+                self.pack_format = pack_format;
+                self.commands = CommandTree::new(pack_format);
+                Ok(())
+            }
+            None => Err(format!("Unknown Minecraft version {}", version)),
+        }
+    }
+
+    pub fn supports(&self, feature: Feature) -> bool {
+        feature.supported_by(self.pack_format)
+    }
+
+    /// Explains which version a feature needs, e.g. `function macros (Minecraft 1.20.2 or
+    /// newer), but the target is 1.19.4`.
+    fn requirement(&self, feature: Feature) -> String {
+        format!(
+            "{} (Minecraft {} or newer), but the target is {}",
+            feature.name(),
+            first_version(feature.pack_format()),
+            self.target
+        )
+    }
+
+    /// Stops with a compile error when the targeted version lacks a feature.
+    pub fn require(&self, feature: Feature, associate: &CodeAssociate) {
+        if !self.supports(feature.clone()) {
+            compile_error(CompileErrors::VersionError(
+                format!("This needs {}", self.requirement(feature)),
+                associate.clone(),
+            ));
+            exit(1);
+        }
+    }
+
+    /// Stops with a compile error when a command uses syntax the targeted version lacks.
+    pub fn require_syntax(&self, command: &str, associate: &CodeAssociate) {
+        for feature in Feature::used_by(command) {
+            self.require(feature, associate);
+        }
+    }
+
+    /// The type an annotation such as `int` or `Player` names.
+    pub fn annotation(&self, annotation: &str, associate: &CodeAssociate) -> Type {
+        if let Some(annotated) = Type::parse(annotation) {
+            return annotated;
+        }
+        if self.structs.contains_key(annotation) {
+            return Type::Struct(annotation.to_string());
+        }
+        compile_error(CompileErrors::TypeMismatch(
+            format!("Unknown type {}", annotation),
+            associate.clone(),
+        ));
+        exit(1);
+    }
+
+    /// Stops with a type error when a value does not match the annotation of the variable or
+    /// parameter it is given to, and returns the annotated type.
+    pub fn annotate(
+        &self,
+        name: &str,
+        annotation: &str,
+        value: &Objects,
+        associate: &CodeAssociate,
+    ) -> Type {
+        let expected = self.annotation(annotation, associate);
+        let found = Type::of(value);
+        if !expected.accepts(&found) {
+            compile_error(CompileErrors::TypeMismatch(
+                format!("{} is declared as {} but given {}", name, annotation, found),
+                associate.clone(),
+            ));
+            exit(1);
+        }
+        expected
+    }
+
+    /// The name of the folders holding functions and function tags.
+    pub fn function_folder(&self) -> &'static str {
+        if self.supports(Feature::SingularFolders) {
+            "function"
+        } else {
+            "functions"
+        }
+    }
+
+    /// Checks generated commands against the command tree of the target version. A command
+    /// that does not parse is a bug in the compiler, reported at the code it came from. Macro
+    /// lines are only complete once their arguments are known, so they are skipped.
+    pub fn validate(&self, commands: &str, associate: &CodeAssociate) {
+        for command in commands.lines().map(|command| command.trim()) {
+            if command.is_empty()
+                || command.starts_with('$')
+                || self
+                    .unchecked_commands
+                    .contains(&(associate.clone(), command.to_string()))
+            {
+                continue;
+            }
+            self.require_syntax(command, associate);
+            if let Err(message) = self.commands.validate(command) {
+                compile_error(CompileErrors::InternalError(
+                    format!("Generated an invalid command `{}`: {}", command, message),
+                    associate.clone(),
+                ));
+                exit(1);
+            }
+        }
+    }
+
+    /// Remembers a runtime value so commands can use it through a macro argument.
+    pub fn register_macro_argument(&mut self, value: &Objects) {
+        if let Some(key) = macro_key(value) {
+            let value = self.resolve(match_objects(value.clone())).get_type();
+            self.macro_arguments.insert(key, value);
+        }
+    }
+
+    /// Moves every command using a macro argument into a macro function of its own, called
+    /// once the runtime values it uses are copied into the arguments.
+    fn expand_macros(
+        &mut self,
+        scope: &Scope,
+        compiled_value: String,
+        associate: &CodeAssociate,
+    ) -> String {
+        let mut lines = vec![];
+        for line in compiled_value.split('\n') {
+            if line.starts_with('$') || !line.contains("$(") {
+                lines.push(line.to_string());
+                continue;
+            }
+            let keys = macro_placeholders(line);
+            if keys.is_empty()
+                || keys
+                    .iter()
+                    .any(|key| !self.macro_arguments.contains_key(key))
+            {
+                compile_error(CompileErrors::CommandError(
+                    format!(
+                        "`{}` uses a macro argument without a runtime value",
+                        line.trim()
+                    ),
+                    associate.clone(),
+                ));
+                exit(1);
+            }
+            self.require(Feature::Macros, associate);
+            let name = format!("{}.macro{}", scope.name, self.macro_functions);
+            self.macro_functions += 1;
+            for key in &keys {
+                match macro_setup(&self.macro_arguments[key], key, &self.namespace) {
+                    Some(setup) => lines.push(setup),
+                    None => {
+                        compile_error(CompileErrors::InternalError(
+                            format!(
+                                "`{}` uses a macro argument that is not a runtime value",
+                                key
+                            ),
+                            associate.clone(),
+                        ));
+                        exit(1);
+                    }
+                }
+            }
+            lines.push(format!(
+                "function {}:{} with storage {}:macro {}",
+                self.namespace, name, self.namespace, MACRO_ARGUMENTS_PATH
+            ));
+            // placeholders are checked as the numbers scores are copied into them
+            let mut body = line.trim_start().to_string();
+            for key in &keys {
+                body = body.replace(&macro_placeholder(key), "0");
+            }
+            self.validate(&body, associate);
+            self.outputs
+                .insert(name, format!("\n${}", line.trim_start()));
         }
+        lines.join("\n")
+    }
 
-        self.outputs.insert(current_scope.name.clone(), output_str);
+    /// Writes the load and tick functions collected while compiling, and tags them so the
+    /// game runs them.
+    pub fn finish(&mut self) {
+        for (name, commands) in [("load", self.load.clone()), ("tick", self.tick.clone())] {
+            if commands.is_empty() {
+                continue;
+            }
+            for (command, associate) in &commands {
+                self.validate(command, associate);
+            }
+            let commands: Vec<String> = commands.into_iter().map(|(command, _)| command).collect();
+            self.outputs
+                .insert(name.to_string(), format!("\n{}", commands.join("\n")));
+            self.function_tags
+                .entry(format!("minecraft:{}", name))
+                .or_default()
+                .push(format!("{}:{}", self.namespace, name));
+        }
     }
 
     pub fn flush(&self) -> &HashMap<String, String> {
@@ -145,6 +654,107 @@ impl Compiler {
     }
 }
 
+/// A piece of a string that may contain `{name}` parts.
+pub enum Interpolated {
+    Literal(String),
+    Expression(String),
+}
+
+/// Splits a string into its literal text and the `{name}` and `{name.field}` parts to
+/// evaluate. `\{` writes a literal brace, and braces around anything else (like NBT) are
+/// part of the text.
+pub fn interpolation_parts(str: &str) -> Vec<Interpolated> {
+    let mut parts = vec![];
+    let mut literal = String::new();
+    let mut rest = str;
+    while let Some(char) = rest.chars().next() {
+        if rest.starts_with("\\{") {
+            literal.push('{');
+            rest = &rest[2..];
+            continue;
+        }
+        let expression = rest
+            .strip_prefix('{')
+            .and_then(|inner| inner.split_once('}'))
+            .map(|(expression, _)| expression)
+            .filter(|expression| {
+                expression.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                    && expression
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+            });
+        if expression.is_none() {
+            literal.push(char);
+            rest = &rest[char.len_utf8()..];
+            continue;
+        }
+        let expression = expression.unwrap();
+        rest = &rest[expression.len() + 2..];
+        if !literal.is_empty() {
+            parts.push(Interpolated::Literal(literal.clone()));
+            literal.clear();
+        }
+        parts.push(Interpolated::Expression(expression.to_string()));
+    }
+    if !literal.is_empty() {
+        parts.push(Interpolated::Literal(literal));
+    }
+    parts
+}
+
+/// The number held by the expression temporary of a scoreboard.
+/// Adds a command to the load or tick function, unless an earlier line already runs it.
+fn push_once(
+    commands: &mut Vec<(String, CodeAssociate)>,
+    command: String,
+    associate: &CodeAssociate,
+) {
+    if !commands.iter().any(|(existing, _)| *existing == command) {
+        commands.push((command, associate.clone()));
+    }
+}
+
+fn expression_value(variable: String) -> Rc<dyn Object> {
+    match_objects(Objects::Variable(
+        Box::new(Objects::Number(0)),
+        Box::new(Objects::Scoreboard(
+            variable,
+            "dummy".to_string(),
+            Box::new(Objects::Number(0)),
+        )),
+    ))
+}
+
+/// The result of arithmetic on two numbers known while compiling, rounded down like scores
+/// are. Division by zero has none.
+fn fold(first: i64, operator: &Operator, second: i64) -> Option<i64> {
+    match operator {
+        Operator::Add => Some(first.wrapping_add(second)),
+        Operator::Subtract => Some(first.wrapping_sub(second)),
+        Operator::Multiply => Some(first.wrapping_mul(second)),
+        Operator::Divide if second != 0 => {
+            let quotient = first / second;
+            match first % second != 0 && (first < 0) != (second < 0) {
+                true => Some(quotient - 1),
+                false => Some(quotient),
+            }
+        }
+        Operator::Modulus if second != 0 => {
+            Some(first - fold(first, &Operator::Divide, second)? * second)
+        }
+        _ => None,
+    }
+}
+
+/// Whether a selector may pick a different entity each time it is used.
+fn picks_anew(selector: &str) -> bool {
+    ["@p", "@r", "@n"]
+        .iter()
+        .any(|prefix| selector.starts_with(prefix))
+        || selector.contains("sort=")
+        || selector.contains("limit=")
+}
+
 impl Scope {
     pub fn new(
         name: String,
@@ -153,7 +763,7 @@ impl Scope {
         functions: HashMap<String, Function>,
     ) -> Scope {
         Scope {
-            variables: HashMap::new(),
+            variables: SymbolTable::new(&name),
             statements,
             namespace,
             functions,
@@ -161,7 +771,661 @@ impl Scope {
             exported_functions: HashMap::new(),
             exported_variables: HashMap::new(),
             name,
+            imports: HashMap::new(),
+            setup: vec![],
+            teardown: vec![],
+            temporaries: Temporaries::default(),
+            prelude: vec![],
+            expressions: HashMap::new(),
+            declared: HashSet::new(),
+        }
+    }
+
+    /// The field of a variable, such as `health` in `p.health`.
+    fn field(&self, owner: &Variable, name: &str, associate: &CodeAssociate) -> Variable {
+        let field = owner
+            .value
+            .as_any()
+            .downcast_ref::<VariableObject>()
+            .and_then(|owner| {
+                match_objects(*owner.value.clone())
+                    .get_variables()
+                    .get(name)
+                    .cloned()
+            });
+        let Some(field) = field else {
+            compile_error(CompileErrors::UndefinedName(
+                format!("{} has no field {}", owner.name, name),
+                associate.clone(),
+            ));
+            exit(1);
+        };
+        Variable {
+            name: name.to_string(),
+            value: field,
+            static_variable: false,
+        }
+    }
+
+    /// Lexes and runs the expression inside a `{...}` part of a string.
+    fn evaluate(
+        &mut self,
+        expression: &str,
+        associate: &CodeAssociate,
+        compiler: &mut Compiler,
+    ) -> Rc<dyn Object> {
+        let mut lexer = Lexer::new(expression.to_string(), associate.file);
+        lexer.tokenizer();
+        let mut ast = AST::new(lexer.flush().to_vec());
+        ast.generate();
+        let statements = ast.flush().to_vec();
+        if statements.len() != 1 {
+            compile_error(CompileErrors::SyntaxError(associate.clone()));
+            exit(1);
         }
+        let value = self.execute(&statements[0], None, compiler);
+        self.value(value, associate, compiler)
+    }
+
+    /// The value of an arithmetic expression such as `a * 2` or of a command resulting in a
+    /// number such as `list.len()`, computed into a temporary before the statement using it
+    /// runs. Other values are returned as they are. Expressions written as statements change
+    /// their left side in place instead.
+    fn value(
+        &mut self,
+        value: Rc<dyn Object>,
+        associate: &CodeAssociate,
+        compiler: &mut Compiler,
+    ) -> Rc<dyn Object> {
+        let (left, operator, right) = match value.get_type() {
+            Objects::MutationVariable(left, _, operator, _, right) => (left, operator, right),
+            Objects::MCStatement(Statements::Query(command)) => {
+                let variable = compiler.expression(self, associate);
+                let (holder, objective) = compiler.score(&variable);
+                self.prelude.push(format!(
+                    "execute store result score {} {} run {}",
+                    holder, objective, command
+                ));
+                return expression_value(variable);
+            }
+            _ => return value,
+        };
+        // the value of an inner expression is only used here, so it is changed in place
+        let variable = match self.expression_of(&left.get_type(), compiler) {
+            Some(variable) => variable,
+            None => {
+                let variable = compiler.expression(self, associate);
+                let target = self.target(&variable, compiler);
+                let command =
+                    self.operate(&target, Operator::Assignment, left, associate, compiler);
+                self.prelude.push(command);
+                variable
+            }
+        };
+        let target = self.target(&variable, compiler);
+        let command = self.operate(
+            &target,
+            operator,
+            match_objects(*right),
+            associate,
+            compiler,
+        );
+        self.prelude.push(command);
+        expression_value(variable)
+    }
+
+    /// The score of a variable, as the target of an operation.
+    fn target(&self, variable: &str, compiler: &Compiler) -> ScoreboardPlayerPairObject {
+        let (holder, objective) = compiler.score(variable);
+        ScoreboardPlayerPairObject {
+            objective_name: objective,
+            player_name: holder,
+            objective_type: Objects::Number(0),
+        }
+    }
+
+    /// The scoreboard of the expression temporary a score is, if it is one.
+    fn expression_of(&self, score: &Objects, compiler: &Compiler) -> Option<String> {
+        let Objects::ScoreboardPlayerPair(objective, holder, _) = score else {
+            return None;
+        };
+        self.expressions
+            .keys()
+            .find(|variable| compiler.score(variable) == (holder.clone(), objective.clone()))
+            .cloned()
+    }
+
+    /// The command applying `operator` with `value` to the score of `target`. A value held by
+    /// an expression temporary is no longer used afterwards, and the temporary is freed.
+    fn operate(
+        &mut self,
+        target: &ScoreboardPlayerPairObject,
+        operator: Operator,
+        value: Rc<dyn Object>,
+        associate: &CodeAssociate,
+        compiler: &mut Compiler,
+    ) -> String {
+        let mut value = compiler.resolve(value);
+        if let (
+            Operator::Multiply | Operator::Divide | Operator::Modulus,
+            Objects::Number(number),
+        ) = (&operator, value.get_type())
+        {
+            // scores are only multiplied and divided by scores
+            let constant = compiler.expression(self, associate);
+            let (holder, objective) = compiler.score(&constant);
+            self.prelude.push(format!(
+                "scoreboard players set {} {} {}",
+                holder, objective, number
+            ));
+            value = match_objects(Objects::ScoreboardPlayerPair(
+                objective,
+                holder,
+                Box::new(Objects::Number(number)),
+            ));
+        }
+        let (command, _) = compile_into_mutation_variable(
+            target,
+            target.objective_type.clone(),
+            operator,
+            value.as_any().downcast_ref(),
+            value.get_type(),
+        );
+        if let Some(variable) = self.expression_of(&value.get_type(), compiler) {
+            let slot = self.expressions.remove(&variable).unwrap();
+            self.temporaries.free(slot);
+        }
+        command.trim().to_string()
+    }
+
+    /// Replaces `{name}` and `{name.field}` parts of a string with the text component of
+    /// the value they refer to, such as a score. `\{` writes a literal brace, and braces
+    /// around anything else (like NBT) are left alone.
+    pub fn interpolate(
+        &mut self,
+        str: &str,
+        associate: &CodeAssociate,
+        compiler: &mut Compiler,
+    ) -> Rc<dyn Object> {
+        let mut parts: Vec<TextObject> = vec![];
+        let mut literal = String::new();
+        for part in interpolation_parts(str) {
+            let expression = match part {
+                Interpolated::Literal(text) => {
+                    literal = text;
+                    continue;
+                }
+                Interpolated::Expression(expression) => expression,
+            };
+            if !literal.is_empty() {
+                parts.push(TextObject::new(TextContent::Text(literal.clone())));
+                literal.clear();
+            }
+
+            let value = self.evaluate(&expression, associate, compiler);
+            match TextObject::from_object(&compiler.resolve(value)) {
+                Some(text) => parts.push(text),
+                None => {
+                    compile_error(CompileErrors::TypeError(associate.clone()));
+                    exit(1);
+                }
+            }
+        }
+
+        if parts.is_empty() {
+            return match_objects(Objects::String(literal));
+        }
+        if !literal.is_empty() {
+            parts.push(TextObject::new(TextContent::Text(literal)));
+        }
+        if parts.len() == 1 {
+            return Rc::new(parts.remove(0));
+        }
+        let mut text = TextObject::new(TextContent::Text(String::new()));
+        text.extra = parts;
+        Rc::new(text)
+    }
+
+    /// Builds a raw command written in the source. `{name}` parts are replaced by the value
+    /// they refer to, e.g. a score becomes `<player> <objective>`. Unless `check` is off (for
+    /// commands added by mods), the command must start with a known command.
+    pub fn command(
+        &mut self,
+        str: &str,
+        check: bool,
+        associate: &CodeAssociate,
+        compiler: &mut Compiler,
+    ) -> Rc<dyn Object> {
+        let mut command = String::new();
+        for part in interpolation_parts(str) {
+            match part {
+                Interpolated::Literal(text) => command.push_str(&text),
+                Interpolated::Expression(expression) => {
+                    let value = self.evaluate(&expression, associate, compiler);
+                    match command_argument(&compiler.resolve(value).get_type()) {
+                        Some(argument) => command.push_str(&argument),
+                        None => {
+                            compile_error(CompileErrors::TypeError(associate.clone()));
+                            exit(1);
+                        }
+                    }
+                }
+            }
+        }
+
+        let command = command.trim();
+        let command = command.strip_prefix('/').unwrap_or(command).to_string();
+        if !check {
+            compiler
+                .unchecked_commands
+                .insert((associate.clone(), command.clone()));
+        } else {
+            compiler.require_syntax(&command, associate);
+            if let Err(message) = compiler.commands.validate(&command) {
+                compile_error(CompileErrors::CommandError(
+                    format!("{}, use cmd(\"...\", false) to emit it anyway", message),
+                    associate.clone(),
+                ));
+                exit(1);
+            }
+        }
+        match_objects(Objects::MCStatement(Statements::Raw(command)))
+    }
+
+    /// The names of an import or export list with the names they are bound to, or every
+    /// export under its own name.
+    fn bindings(&self, names: &Option<Vec<(String, String)>>) -> Vec<(String, String)> {
+        match names {
+            Some(names) => names.clone(),
+            None => self
+                .exported_variables
+                .keys()
+                .chain(self.exported_functions.keys())
+                .map(|exported| (exported.clone(), exported.clone()))
+                .collect(),
+        }
+    }
+
+    /// The scope of an imported module, and whether this is the first import of it, which runs
+    /// its function.
+    fn module(
+        &mut self,
+        path: &str,
+        associate: &CodeAssociate,
+        compiler: &mut Compiler,
+    ) -> (Scope, bool) {
+        let (namespace, name) = module_function(path, &compiler.namespace);
+        // modules are compiled before the code that imports them
+        let existing_scope = compiler
+            .scopes
+            .iter()
+            .find(|scope| scope.name == name && scope.namespace == namespace);
+        let Some(existing_scope) = existing_scope else {
+            compile_error(CompileErrors::ImportError(
+                format!("Module {} does not exist", path),
+                associate.clone(),
+            ));
+            exit(1);
+        };
+        let existing_scope = existing_scope.clone();
+        let first_import = compiler
+            .imported_modules
+            .insert(format!("{}:{}", namespace, name));
+        (existing_scope, first_import)
+    }
+
+    /// The module an import refers to, from the modules of a dependency.
+    fn resolve(&self, path: &str, compiler: &Compiler) -> String {
+        if self.namespace == compiler.namespace {
+            return path.to_string();
+        }
+        resolve(path, &format!("{}:{}", self.namespace, self.name))
+    }
+
+    /// An exported function of a module, which keeps the functions and variables of the module.
+    fn exported_function(&self, name: &str) -> Option<Function> {
+        let mut function = self.exported_functions.get(name)?.clone();
+        function
+            .module
+            .get_or_insert_with(|| (self.functions.clone(), self.variables.clone()));
+        function
+            .exported
+            .get_or_insert_with(|| format!("{}:{}.{}", self.namespace, self.name, name));
+        Some(function)
+    }
+
+    /// The name of the scope's function in the outputs, with the namespace of functions outside
+    /// of the datapack's own namespace, as for the modules of dependencies.
+    pub fn output_name(&self, namespace: &str) -> String {
+        if self.namespace == namespace {
+            self.name.clone()
+        } else {
+            format!("{}:{}", self.namespace, self.name)
+        }
+    }
+
+    /// Adds a variable or function under the name it is exported as, stopping when another
+    /// export already has that name.
+    fn export(
+        &mut self,
+        exported: &str,
+        variable: Option<Variable>,
+        function: Option<Function>,
+        associate: &CodeAssociate,
+    ) {
+        if self.exported_variables.contains_key(exported)
+            || self.exported_functions.contains_key(exported)
+        {
+            compile_error(CompileErrors::ExportError(
+                format!("{} is already exported", exported),
+                associate.clone(),
+            ));
+            exit(1);
+        }
+        if let Some(mut variable) = variable {
+            variable.name = exported.to_string();
+            self.exported_variables
+                .insert(exported.to_string(), variable);
+        } else if let Some(mut function) = function {
+            function.name = exported.to_string();
+            self.exported_functions
+                .insert(exported.to_string(), function);
+        }
+    }
+
+    /// Runs a user function in a scope of its own, which is returned to be compiled into the
+    /// called function. Struct methods also get their instance as `self`.
+    fn call(
+        &mut self,
+        function: &Function,
+        items: &[Rc<dyn Object>],
+        instance: Option<StructObject>,
+        associate: &CodeAssociate,
+        compiler: &mut Compiler,
+    ) -> Scope {
+        // the statements of the function arrive as a set
+        let code = match function.code.as_slice() {
+            [ASTOperation::Set(statements, _)] => statements.clone(),
+            code => code.to_vec(),
+        };
+        // pass the arguments to the function
+        let mut function_scope = Scope::new(
+            format!("{}.{}", self.name, self.scopes.len()),
+            self.namespace.clone(),
+            code.clone(),
+            self.functions.clone(),
+        );
+
+        // the code sees the variables of where the function is declared, not of the caller
+        let declared = match &function.module {
+            Some((_, variables)) => variables.clone(),
+            None => self.variables.lexical(&function.scope),
+        };
+        function_scope.variables = declared.block(&function_scope.name);
+        function_scope.temporaries = self.temporaries.above();
+        function_scope.imports = self.imports.clone();
+        if let Some((functions, _)) = &function.module {
+            function_scope.functions.extend(functions.clone());
+        }
+        if items.len() != function.arguments.len() {
+            let count = function.arguments.len();
+            compile_error(CompileErrors::ArgumentCount(
+                argument_count(&function.name, count, count, items.len()),
+                associate.clone(),
+            ));
+            exit(1);
+        }
+        for (item, (argument, annotation)) in items.iter().zip(&function.arguments) {
+            // runtime arguments can be used where commands need literals
+            compiler.register_macro_argument(&item.get_type());
+            let annotated = annotation.as_ref().map(|annotation| {
+                compiler.annotate(argument, annotation, &item.get_type(), associate)
+            });
+            // unannotated scores are copied as well, so the function cannot change the
+            // variable of the caller
+            let annotated = annotated.or_else(|| {
+                let score = compiler.resolve(item.clone()).get_type();
+                matches!(score, Objects::ScoreboardPlayerPair(..)).then(|| {
+                    match Type::of(&item.get_type()) {
+                        Type::Boolean => Type::Boolean,
+                        _ => Type::Number,
+                    }
+                })
+            });
+            let passed = match annotated {
+                // scores are copied into a score of the function
+                Some(Type::Number) | Some(Type::Boolean) => {
+                    let variable = compiler.temporary(
+                        &mut function_scope,
+                        argument,
+                        &Objects::Number(0),
+                        associate,
+                    );
+                    let Some(copy) = compiler.copy_score(&item.get_type(), &variable) else {
+                        compile_error(CompileErrors::TypeMismatch(
+                            format!("{} cannot be passed as a score", argument),
+                            associate.clone(),
+                        ));
+                        exit(1);
+                    };
+                    let mut setup = vec![copy];
+                    if !compiler.names.shared(&variable) {
+                        setup.insert(0, format!("scoreboard objectives add {} dummy", variable));
+                    }
+                    for command in setup {
+                        compiler.validate(&command, associate);
+                        function_scope.setup.push((command, associate.clone()));
+                    }
+                    let value = match Type::of(&item.get_type()) {
+                        Type::Boolean => Objects::Boolean(false),
+                        _ => Objects::Number(0),
+                    };
+                    Some(Variable {
+                        name: argument.clone(),
+                        value: Rc::new(VariableObject {
+                            value: Box::new(value.clone()),
+                            scoreboard: Box::new(Objects::Scoreboard(
+                                variable,
+                                "dummy".to_string(),
+                                Box::new(value),
+                            )),
+                        }),
+                        static_variable: false,
+                    })
+                }
+                // selectors that pick anew each time are pinned down with a tag
+                Some(Type::Entity) => {
+                    let mut selector = match item.get_type() {
+                        Objects::Entity(selector) => selector,
+                        Objects::Variable(value, _) => match *value {
+                            Objects::Entity(selector) => selector,
+                            _ => "@s".to_string(),
+                        },
+                        _ => "@s".to_string(),
+                    };
+                    if picks_anew(&selector) {
+                        let tag = compiler.name(
+                            &function_scope.namespace,
+                            &function_scope.name,
+                            argument,
+                        );
+                        let setup = format!("tag {} add {}", selector, tag);
+                        let teardown = format!("tag @e[tag={}] remove {}", tag, tag);
+                        compiler.validate(&setup, associate);
+                        compiler.validate(&teardown, associate);
+                        function_scope.setup.push((setup, associate.clone()));
+                        function_scope.teardown.push((teardown, associate.clone()));
+                        selector = format!("@e[tag={}]", tag);
+                    }
+                    Some(Variable {
+                        name: argument.clone(),
+                        value: Rc::new(VariableObject {
+                            value: Box::new(Objects::Entity(selector)),
+                            scoreboard: Box::new(Objects::Unknown),
+                        }),
+                        static_variable: true,
+                    })
+                }
+                _ => None,
+            };
+            if let Some(passed) = passed {
+                function_scope.variables.insert(argument.clone(), passed);
+            } else if let Objects::Variable(value, _) = item.get_type() {
+                let variable_object = item.as_any().downcast_ref::<VariableObject>().unwrap();
+                function_scope.variables.insert(
+                    argument.clone(),
+                    Variable {
+                        name: argument.clone(),
+                        value: Rc::new(VariableObject {
+                            value: Box::new(*value.clone()),
+                            scoreboard: variable_object.scoreboard.clone(),
+                        }),
+                        static_variable: false,
+                    },
+                );
+            } else {
+                function_scope.variables.insert(
+                    argument.clone(),
+                    Variable {
+                        name: argument.clone(),
+                        value: Rc::new(VariableObject {
+                            value: Box::new(item.get_type()),
+                            scoreboard: Box::new(Objects::Scoreboard(
+                                "".to_string(),
+                                "dummy".to_string(),
+                                Box::new(item.get_type()),
+                            )),
+                        }),
+                        static_variable: true,
+                    },
+                );
+            }
+        }
+
+        if let Some(instance) = instance {
+            function_scope.variables.insert(
+                "self".to_string(),
+                Variable {
+                    name: "self".to_string(),
+                    value: mk_variable(Objects::Struct(instance), Objects::Unknown),
+                    static_variable: true,
+                },
+            );
+        }
+
+        // a first pass declares what the code needs, its expressions are computed again when
+        // the function is compiled
+        let temporaries = function_scope.temporaries.clone();
+        for statement in code.iter() {
+            function_scope.execute(statement, None, compiler);
+        }
+        function_scope.temporaries = temporaries;
+        function_scope.prelude.clear();
+        function_scope.expressions.clear();
+        function_scope.declared.clear();
+        self.scopes.push(function_scope.clone());
+        function_scope
+    }
+
+    /// The scope of an exported function as it is written into the datapack of its module, to
+    /// be called from other datapacks. Its arguments are scores the caller sets before calling
+    /// it, so only functions taking numbers and booleans can be written so.
+    fn external(
+        function: &Function,
+        associate: &CodeAssociate,
+        compiler: &mut Compiler,
+    ) -> Option<Scope> {
+        let (namespace, name) = function.exported.as_ref()?.split_once(':')?;
+        let (functions, variables) = function.module.clone()?;
+        let code = match function.code.as_slice() {
+            [ASTOperation::Set(statements, _)] => statements.clone(),
+            code => code.to_vec(),
+        };
+        let mut scope = Scope::new(name.to_string(), namespace.to_string(), code, functions);
+        scope.variables = variables.block(name);
+        for (argument, annotation) in &function.arguments {
+            let value = match annotation.as_deref() {
+                None | Some("int") => Objects::Number(0),
+                Some("bool") => Objects::Boolean(false),
+                Some(_) => return None,
+            };
+            let variable = compiler.temporary(&mut scope, argument, &value, associate);
+            if !compiler.names.shared(&variable) {
+                let declaration = format!("scoreboard objectives add {} dummy", variable);
+                scope.setup.push((declaration, associate.clone()));
+            }
+            scope.variables.insert(
+                argument.clone(),
+                Variable {
+                    name: argument.clone(),
+                    value: Rc::new(VariableObject {
+                        value: Box::new(value.clone()),
+                        scoreboard: Box::new(Objects::Scoreboard(
+                            variable,
+                            "dummy".to_string(),
+                            Box::new(value),
+                        )),
+                    }),
+                    static_variable: false,
+                },
+            );
+        }
+        Some(scope)
+    }
+
+    /// Calls an exported function of a dependency left to its own datapack, copying the
+    /// arguments into the scores the function reads them from.
+    fn call_external(
+        &mut self,
+        function: &Function,
+        items: &[Rc<dyn Object>],
+        associate: &CodeAssociate,
+        compiler: &mut Compiler,
+    ) -> Rc<dyn Object> {
+        if items.len() != function.arguments.len() {
+            let count = function.arguments.len();
+            compile_error(CompileErrors::ArgumentCount(
+                argument_count(&function.name, count, count, items.len()),
+                associate.clone(),
+            ));
+            exit(1);
+        }
+        let Some(scope) = Scope::external(function, associate, compiler) else {
+            compile_error(CompileErrors::TypeMismatch(
+                format!(
+                    "{} of a dependency that is not bundled can only take numbers and booleans",
+                    function.name
+                ),
+                associate.clone(),
+            ));
+            exit(1);
+        };
+        let mut commands = vec![];
+        for (item, (argument, _)) in items.iter().zip(&function.arguments) {
+            let variable = scope.variables.get(argument).unwrap();
+            let variable = variable.value.as_any().downcast_ref::<VariableObject>();
+            let Some(Objects::Scoreboard(score, ..)) =
+                variable.map(|variable| &*variable.scoreboard)
+            else {
+                unreachable!()
+            };
+            let Some(copy) = compiler.copy_score(&item.get_type(), score) else {
+                compile_error(CompileErrors::TypeMismatch(
+                    format!("{} cannot be passed as a score", argument),
+                    associate.clone(),
+                ));
+                exit(1);
+            };
+            if !compiler.names.shared(score) {
+                commands.push(format!("scoreboard objectives add {} dummy", score));
+            }
+            commands.push(copy);
+        }
+        commands.push(format!("function {}:{}", scope.namespace, scope.name));
+        for command in &commands {
+            compiler.validate(command, associate);
+        }
+        match_objects(Objects::MCStatement(Statements::Raw(commands.join("\n"))))
     }
 
     pub fn execute(
@@ -177,62 +1441,135 @@ impl Scope {
             ASTOperation::LiteralNumber(num, associate) => {
                 return match_objects(Objects::Number(*num));
             }
-            ASTOperation::LiteralBool(bool, associate) => {
-                return match_objects(Objects::Boolean(*bool));
+            ASTOperation::LiteralBool(bool, associate) => {
+                return match_objects(Objects::Boolean(*bool));
+            }
+            ASTOperation::LiteralDecimal(num, _) => match_objects(Objects::Decimal(*num)),
+            ASTOperation::LiteralCoordinate(coordinate, associate) => {
+                let coordinate = Coordinate::parse(coordinate);
+                if coordinate.is_none() {
+                    compile_error(CompileErrors::ValueError(associate.clone()));
+                    exit(1);
+                }
+                match_objects(Objects::Coordinate(coordinate.unwrap()))
+            }
+            ASTOperation::BlockPredicate(position, associate) => {
+                if position.len() != 1 {
+                    compile_error(CompileErrors::MissingParams(associate.clone()));
+                    exit(1);
+                }
+                let mut position = self.execute(&position[0], current_variable, compiler);
+                if let Objects::Variable(value, _) = position.get_type() {
+                    position = match_objects(*value);
+                }
+                let position = BlockPosObject::from_object(&position.get_type());
+                if position.is_none() {
+                    compile_error(CompileErrors::TypeError(associate.clone()));
+                    exit(1);
+                }
+                Rc::new(position.unwrap())
+            }
+            ASTOperation::LiteralSelector(selector, _) => {
+                match_objects(Objects::Entity(selector.clone()))
             }
             ASTOperation::Export(statement, associate) => {
                 let value = self.execute(&statement, current_variable, compiler);
                 println!("Exporting: {:?}", value.get_type());
-                if let Objects::Variable(_, _) = value.get_type() {
-                    // get the last variable added
-                    let last_variable = self.variables.values().last().unwrap();
-                    self.exported_variables
-                        .insert(last_variable.name.clone(), last_variable.clone());
-                } else if let Objects::CreatedFunction = value.get_type() {
-                    let last_function = self.functions.values().last().unwrap();
-                    self.exported_functions
-                        .insert(last_function.name.clone(), last_function.clone());
+                // the declaration names what is exported
+                match &**statement {
+                    ASTOperation::AssignVariable(name, _, _, _)
+                    | ASTOperation::StaticVariable(name, _, _, _) => {
+                        let variable = self.variables.get(name).cloned();
+                        self.export(name, variable, None, associate);
+                    }
+                    ASTOperation::CreateFunction(name, _, _, _) => {
+                        let function = self.functions.get(name).cloned();
+                        self.export(name, None, function, associate);
+                    }
+                    _ => {}
                 }
                 return match_objects(value.get_type());
             }
-            ASTOperation::Import(name, associate) => {
-                let existing_scope = compiler.scopes.iter().find(|scope| scope.name == *name);
-                let mut just_initialized = false;
-                let existing_scope = if existing_scope.is_none() {
-                    let reference = compiler.prepared_files.get(name);
-                    if reference.is_none() {
-                        eprintln!("File {} does not exist.", name);
+            ASTOperation::ExportList(names, associate) => {
+                for (name, exported) in names {
+                    let variable = self.variables.get(name).cloned();
+                    let function = self.functions.get(name).cloned();
+                    if variable.is_none() && function.is_none() {
+                        compile_error(CompileErrors::ExportError(
+                            format!("{} is not defined", name),
+                            associate.clone(),
+                        ));
                         exit(1);
                     }
-
-                    let reference = reference.unwrap().to_string();
-                    let mut lexer = Lexer::new(reference, "test".to_string());
-                    lexer.tokenizer();
-                    let mut ast = AST::new(lexer.flush().to_vec());
-                    ast.generate();
-
-                    let mut scope = Scope::new(
-                        format!("{}", name),
-                        self.namespace.clone(),
-                        ast.flush().to_vec(),
-                        HashMap::new(),
-                    );
-                    compiler.scopes.push(scope.clone());
-                    compiler.compile(&mut scope);
-                    just_initialized = true;
-                    Some(scope)
+                    self.export(exported, variable, function, associate);
+                }
+                match_objects(Objects::Unknown)
+            }
+            ASTOperation::ReExport(path, names, associate) => {
+                let path = &self.resolve(path, compiler);
+                let (existing_scope, first_import) = self.module(path, associate, compiler);
+                for (name, exported) in existing_scope.bindings(names) {
+                    let variable = existing_scope.exported_variables.get(&name).cloned();
+                    let function = existing_scope.exported_function(&name);
+                    if variable.is_none() && function.is_none() {
+                        compile_error(CompileErrors::ImportError(
+                            format!("{} is not exported by {}", name, path),
+                            associate.clone(),
+                        ));
+                        exit(1);
+                    }
+                    self.export(&exported, variable, function, associate);
+                }
+                if first_import {
+                    match_objects(Objects::MCStatement(Statements::Raw(format!(
+                        "function {}:{}",
+                        existing_scope.namespace, existing_scope.name,
+                    ))))
                 } else {
-                    Some(existing_scope.unwrap().clone())
-                };
-                let existing_scope = existing_scope.unwrap();
-
-                for (name, value) in existing_scope.exported_variables {
-                    self.variables.insert(name, value);
+                    match_objects(Objects::Unknown)
                 }
-                for (name, value) in existing_scope.exported_functions {
-                    self.functions.insert(name, value);
+            }
+            ASTOperation::Import(path, names, associate) => {
+                let path = &self.resolve(path, compiler);
+                let (existing_scope, first_import) = self.module(path, associate, compiler);
+
+                let bindings = existing_scope.bindings(names);
+                for (exported, local) in bindings {
+                    let origin = (path.clone(), exported.clone());
+                    if self.imports.get(&local) == Some(&origin) {
+                        // importing the same name again
+                        continue;
+                    }
+                    if !existing_scope.exported_variables.contains_key(&exported)
+                        && !existing_scope.exported_functions.contains_key(&exported)
+                    {
+                        compile_error(CompileErrors::ImportError(
+                            format!("{} is not exported by {}", exported, path),
+                            associate.clone(),
+                        ));
+                        exit(1);
+                    }
+                    if self.variables.contains_key(&local) || self.functions.contains_key(&local) {
+                        compile_error(CompileErrors::ImportError(
+                            format!(
+                                "{} is already defined, use `{} as <name>` to import it",
+                                local, exported
+                            ),
+                            associate.clone(),
+                        ));
+                        exit(1);
+                    }
+                    if let Some(variable) = existing_scope.exported_variables.get(&exported) {
+                        let mut variable = variable.clone();
+                        variable.name = local.clone();
+                        self.variables.insert(local.clone(), variable);
+                    } else if let Some(mut function) = existing_scope.exported_function(&exported) {
+                        function.name = local.clone();
+                        self.functions.insert(local.clone(), function);
+                    }
+                    self.imports.insert(local, origin);
                 }
-                if just_initialized {
+                if first_import {
                     return match_objects(Objects::MCStatement(Statements::Raw(format!(
                         "function {}:{}",
                         existing_scope.namespace, existing_scope.name,
@@ -241,25 +1578,33 @@ impl Scope {
                     return match_objects(Objects::Unknown);
                 }
             }
-            ASTOperation::AssignVariable(name, operation, associate) => {
+            ASTOperation::Command(command, associate) => {
+                self.command(command, true, associate, compiler)
+            }
+            ASTOperation::AssignVariable(name, annotation, operation, associate) => {
                 if operation.len() != 1 {
                     eprintln!("More than 1 operation in assign variable");
                     exit(1);
                 }
                 let value = self.execute(&operation[0], current_variable, compiler);
+                let value = self.value(value, associate, compiler);
+                if let Some(annotation) = annotation {
+                    compiler.annotate(name, annotation, &value.get_type(), associate);
+                }
                 if discriminant(&value.get_type())
                     == discriminant(&Objects::Variable(
                         Box::new(Objects::Unknown),
                         Box::new(Objects::Unknown),
                     ))
                 {
-                    if let Objects::Variable(value, _) = value.get_type() {
+                    if let Objects::Variable(inner, _) = value.get_type() {
+                        let score = compiler.variable(self, name, &inner, associate);
                         let variable = Objects::Variable(
-                            Box::new(*value.clone()),
+                            Box::new(*inner.clone()),
                             Box::new(Objects::Scoreboard(
-                                format!("v_{}_{}", self.name, self.variables.len()),
+                                score.clone(),
                                 "dummy".to_string(),
-                                Box::new(*value.clone()),
+                                Box::new(*inner.clone()),
                             )),
                         );
                         let variable = match_objects(variable);
@@ -271,13 +1616,25 @@ impl Scope {
                                 static_variable: false,
                             },
                         );
+                        // scores are copied when the line runs, as the other variable may have
+                        // changed since it was declared
+                        if let Objects::Number(_) | Objects::Boolean(_) = *inner {
+                            let mut commands = vec![];
+                            if !compiler.names.shared(&score) {
+                                commands.push(format!("scoreboard objectives add {} dummy", score));
+                            }
+                            commands.extend(compiler.copy_score(&value.get_type(), &score));
+                            return match_objects(Objects::MCStatement(Statements::Raw(
+                                commands.join("\n"),
+                            )));
+                        }
                         return variable;
                     }
                 }
                 let variable = Objects::Variable(
                     Box::new(value.clone().get_type()),
                     Box::new(Objects::Scoreboard(
-                        format!("v_{}_{}", self.name, self.variables.len()),
+                        compiler.variable(self, name, &value.get_type(), associate),
                         "dummy".to_string(),
                         Box::new(value.clone().get_type()),
                     )),
@@ -293,17 +1650,21 @@ impl Scope {
                 );
                 return variable;
             }
-            ASTOperation::StaticVariable(name, operation, associate) => {
+            ASTOperation::StaticVariable(name, annotation, operation, associate) => {
                 if operation.len() != 1 {
                     eprintln!("More than 1 operation in assign variable");
                     exit(1);
                 }
                 let value = self.execute(&operation[0], current_variable, compiler);
+                if let Some(annotation) = annotation {
+                    compiler.annotate(name, annotation, &value.get_type(), associate);
+                }
 
+                // static values are written where they are used, and have no score
                 let variable = Objects::Variable(
                     Box::new(value.clone().get_type()),
                     Box::new(Objects::Scoreboard(
-                        format!("v_{}_{}", self.name, self.variables.len()),
+                        String::new(),
                         "dummy".to_string(),
                         Box::new(value.clone().get_type()),
                     )),
@@ -324,142 +1685,90 @@ impl Scope {
                     eprintln!("More than 1 operation in assign variable");
                     exit(1);
                 }
-                let evaluated_operation = self.execute(&operation[0], current_variable, compiler);
-                if !self.variables.contains_key(name) {
-                    eprintln!("Variable {} does not exist", name);
-                    exit(1);
-                }
-                let original_variable = self.variables.get(name).unwrap().clone();
-
-                if original_variable.static_variable {
-                    eprintln!("Attempting to mutate static variable.");
-                    exit(1);
-                }
-                let mut first_scoreboard_pair: Option<ScoreboardPlayerPairObject> = None;
-                let mut second_scoreboard_pair: Option<ScoreboardPlayerPairObject> = None;
-
-                let (new_obj, operand, scoreboard_name) =
-                    if let Objects::Variable(value, scoreboard) = evaluated_operation.get_type() {
-                        let scoreboard = if let Objects::Scoreboard(name, _, _) = *scoreboard {
-                            name
-                        } else {
-                            eprintln!("Invalid variable (Variable SCOREBOARD)");
+                // `p.health = 20` assigns a field of the variable it is used on
+                let owner = current_variable.as_ref().map(|owner| owner.name.clone());
+                let original_variable = match &current_variable {
+                    Some(owner) => self.field(owner, name, associate),
+                    None => {
+                        if !self.variables.contains_key(name) {
+                            eprintln!("Variable {} does not exist", name);
                             exit(1);
-                        };
-                        (value, Operator::Assignment, scoreboard)
-                    } else if let Objects::MutationVariable(_, _, operand, _, new_obj) =
-                        evaluated_operation.get_type()
-                    {
-                        let scoreboard = if let Objects::Variable(_, scoreboard) = *new_obj.clone()
-                        {
-                            if let Objects::Scoreboard(name, _, _) = *scoreboard {
-                                name
-                            } else {
-                                eprintln!("Invalid variable (Variable SCOREBOARD)");
-                                exit(1);
-                            }
-                        } else {
-                            "".to_string()
-                        };
-                        (new_obj, operand.clone(), scoreboard)
-                    } else {
-                        (
-                            Box::new(evaluated_operation.get_type()),
-                            Operator::Assignment,
-                            "".to_string(),
-                        )
-                    };
-
-                match *new_obj.clone() {
-                    Objects::Number(num) => {
-                        second_scoreboard_pair = Some(ScoreboardPlayerPairObject {
-                            objective_name: scoreboard_name,
-                            player_name: "value".to_string(),
-                            objective_type: Objects::Number(num),
-                        });
-                    }
-                    Objects::Boolean(bool) => {
-                        second_scoreboard_pair = Some(ScoreboardPlayerPairObject {
-                            objective_name: scoreboard_name,
-                            player_name: "value".to_string(),
-                            objective_type: Objects::Boolean(bool),
-                        });
-                    }
-                    Objects::ScoreboardPlayerPair(
-                        new_objective_name,
-                        new_player_name,
-                        new_obj_type,
-                    ) => {
-                        second_scoreboard_pair = Some(ScoreboardPlayerPairObject {
-                            objective_name: new_objective_name,
-                            player_name: new_player_name.clone(),
-                            objective_type: *new_obj_type.clone(),
-                        });
-                    }
-                    _ => {
-                        eprintln!("Invalid variable (Variable MATCH), {:?}", new_obj.clone());
-                        exit(1);
+                        }
+                        let original_variable = self.variables.get(name).unwrap().clone();
+                        if original_variable.static_variable {
+                            eprintln!("Attempting to mutate static variable.");
+                            exit(1);
+                        }
+                        original_variable
                     }
+                };
+                // storage fields are set like any other storage path
+                let path = compiler.resolve(original_variable.value.clone());
+                if let Objects::StoragePath(..) = path.get_type() {
+                    let value = self.execute(&operation[0], None, compiler);
+                    let value = self.value(value, associate, compiler);
+                    let value = compiler.resolve(value);
+                    let functions = path.get_functions();
+                    let variable = mk_variable(path.get_type(), Objects::Unknown);
+                    return functions["set"](vec![value], Some(variable));
                 }
-
-                let (old_obj, scoreboard_name) = if let Objects::Variable(value, scoreboard) =
-                    original_variable.value.get_type()
-                {
-                    let scoreboard = if let Objects::Scoreboard(name, _, _) = *scoreboard {
-                        name
-                    } else {
-                        eprintln!("Invalid variable (Variable SCOREBOARD)");
-                        exit(1);
-                    };
-                    (value, scoreboard)
-                } else {
-                    (Box::new(evaluated_operation.get_type()), "".to_string())
+                // the value is read in the scope, not in the variable a field belongs to
+                let reads_target = |left: &ASTOperation| match (left, &owner) {
+                    (ASTOperation::Access(left, _), None) => left == name,
+                    (ASTOperation::UseVariable(used, field, _), Some(owner)) => {
+                        let field = match &**field {
+                            ASTOperation::AccessPart(field, _) => field,
+                            field => field,
+                        };
+                        used == owner
+                            && matches!(field, ASTOperation::Access(field, _) if field == name)
+                    }
+                    _ => false,
                 };
-
-                match *old_obj.clone() {
-                    Objects::Number(num) => {
-                        first_scoreboard_pair = Some(ScoreboardPlayerPairObject {
-                            objective_name: scoreboard_name,
-                            player_name: "value".to_string(),
-                            objective_type: Objects::Number(num),
-                        });
-                    }
-                    Objects::Boolean(bool) => {
-                        first_scoreboard_pair = Some(ScoreboardPlayerPairObject {
-                            objective_name: scoreboard_name,
-                            player_name: "value".to_string(),
-                            objective_type: Objects::Boolean(bool),
-                        });
-                    }
-                    Objects::ScoreboardPlayerPair(objective_name, player_name, obj_type) => {
-                        first_scoreboard_pair = Some(ScoreboardPlayerPairObject {
-                            objective_name,
-                            player_name: player_name.clone(),
-                            objective_type: *obj_type.clone(),
-                        });
-                    }
-                    _ => {
-                        eprintln!("Invalid variable (Variable MATCH), {:?}", new_obj.clone());
-                        exit(1);
+                // `a += b` changes the score of `a` in place, other values are assigned to it
+                let (operator, value) = match &operation[0] {
+                    ASTOperation::Operation(left, operator, right, _)
+                        if reads_target(left)
+                            && matches!(
+                                operator,
+                                Operator::Add
+                                    | Operator::Subtract
+                                    | Operator::Multiply
+                                    | Operator::Divide
+                                    | Operator::Modulus
+                            ) =>
+                    {
+                        (operator.clone(), self.execute(right, None, compiler))
                     }
-                }
-
-                if first_scoreboard_pair.is_none() || second_scoreboard_pair.is_none() {
-                    eprintln!("Invalid evalutation for if statement (Variable)");
+                    operation => (
+                        Operator::Assignment,
+                        self.execute(operation, None, compiler),
+                    ),
+                };
+                let value = self.value(value, associate, compiler);
+                let Objects::ScoreboardPlayerPair(objective_name, player_name, objective_type) =
+                    compiler.resolve(original_variable.value.clone()).get_type()
+                else {
+                    compile_error(CompileErrors::TypeMismatch(
+                        format!("Variable {} is not kept in a score", name),
+                        associate.clone(),
+                    ));
                     exit(1);
-                }
-
-                return match_objects(Objects::MutationVariable(
-                    Rc::new(first_scoreboard_pair.unwrap()),
-                    Box::new(*old_obj.clone()),
-                    operand.clone(),
-                    Rc::new(second_scoreboard_pair.unwrap()),
-                    Box::new(*new_obj.clone()),
-                ));
+                };
+                let variable = ScoreboardPlayerPairObject {
+                    objective_name,
+                    player_name,
+                    objective_type: *objective_type,
+                };
+                let command = self.operate(&variable, operator, value, associate, compiler);
+                match_objects(Objects::MCStatement(Statements::Raw(command)))
             }
             ASTOperation::Access(name, associate) => {
                 if current_variable.is_none() {
                     if !self.variables.contains_key(name) {
+                        if let Some(builtin) = builtin_variable(name) {
+                            return builtin;
+                        }
                         eprintln!("Variable {} does not exist", name);
                         exit(1);
                     }
@@ -504,8 +1813,15 @@ impl Scope {
             }
             ASTOperation::UseVariable(name, operation, associate) => {
                 if current_variable.is_none() {
-                    let variable = self.variables.get(name).expect("Variable not found");
-                    let value = self.execute(&operation, Some(variable.clone()), compiler);
+                    let variable = match self.variables.get(name) {
+                        Some(variable) => variable.clone(),
+                        None => Variable {
+                            name: name.clone(),
+                            value: builtin_variable(name).expect("Variable not found"),
+                            static_variable: true,
+                        },
+                    };
+                    let value = self.execute(operation, Some(variable), compiler);
                     return value;
                 }
 
@@ -530,6 +1846,19 @@ impl Scope {
                 let value = self.execute(&operation, Some(variable), compiler);
                 return value;
             }
+            ASTOperation::Chain(operation, method, _) => {
+                // the method runs on the result of the call before it
+                let mut value = self.execute(operation, current_variable, compiler);
+                if let Objects::Variable(inner, _) = value.get_type() {
+                    value = match_objects(*inner);
+                }
+                let variable = Variable {
+                    name: String::new(),
+                    value: mk_variable(value.get_type(), Objects::Unknown),
+                    static_variable: true,
+                };
+                self.execute(method, Some(variable), compiler)
+            }
             // runs this inside of the variable
             ASTOperation::AccessPart(operation, associate) => {
                 let value = self.execute(&operation, current_variable, compiler);
@@ -538,8 +1867,41 @@ impl Scope {
             ASTOperation::Operation(first_statement, operator, second_statement, associate) => {
                 let first_value =
                     self.execute(&first_statement, current_variable.clone(), compiler);
+                let first_value = self.value(first_value, associate, compiler);
                 let second_value =
                     self.execute(&second_statement, current_variable.clone(), compiler);
+                let second_value = self.value(second_value, associate, compiler);
+                if let (Objects::Number(first), Objects::Number(second)) =
+                    (first_value.get_type(), second_value.get_type())
+                {
+                    if let Some(value) = fold(first, operator, second) {
+                        return match_objects(Objects::Number(value));
+                    }
+                }
+                if let ASTOperation::BlockPredicate(_, associate) = &**first_statement {
+                    let block = second_value.as_any().downcast_ref::<StringObject>();
+                    if block.is_none()
+                        || (*operator != Operator::Equal && *operator != Operator::NotEqual)
+                    {
+                        compile_error(CompileErrors::TypeError(associate.clone()));
+                        exit(1);
+                    }
+                    let block = block.unwrap();
+                    let block = match parse_block(&block.value, true) {
+                        Ok(block) => block.to_string(),
+                        Err(message) => {
+                            eprintln!("{}", message);
+                            compile_error(CompileErrors::ValueError(associate.clone()));
+                            exit(1);
+                        }
+                    };
+                    let step = if *operator == Operator::Equal {
+                        ExecuteSteps::IfBlock(first_value.get_type(), block)
+                    } else {
+                        ExecuteSteps::UnlessBlock(first_value.get_type(), block)
+                    };
+                    return match_objects(Objects::MCStatement(Statements::Execute(vec![step])));
+                }
                 if *operator == Operator::Add
                     || *operator == Operator::Subtract
                     || *operator == Operator::Assignment
@@ -547,141 +1909,40 @@ impl Scope {
                     || *operator == Operator::Divide
                     || *operator == Operator::Modulus
                 {
-                    // TODO: Make this system
-                    let first_value = first_value.as_any().downcast_ref::<VariableObject>();
-
-                    if first_value.is_none() {
-                        eprintln!("Invalid variable {:?}", first_value);
+                    // the left side is a score: a variable or a field kept in one
+                    let first_scoreboard_pair = compiler.resolve(first_value).get_type();
+                    if !matches!(first_scoreboard_pair, Objects::ScoreboardPlayerPair(..)) {
+                        eprintln!("Invalid variable (SB1)");
                         exit(1);
                     }
-
-                    let first_value = first_value.unwrap();
-
-                    let mut first_scoreboard_pair: Option<Rc<dyn Object>> = None;
-                    if let Objects::Scoreboard(name, objective, _) = *first_value.scoreboard.clone()
-                    {
-                        // assigns first player pair
-                        match *first_value.value.clone() {
-                            Objects::Number(_) | Objects::Boolean(_) => {
-                                first_scoreboard_pair =
-                                    Some(match_objects(Objects::ScoreboardPlayerPair(
-                                        name,
-                                        "value".to_string(),
-                                        first_value.value.clone(),
-                                    )));
-                            }
-                            Objects::ScoreboardPlayerPair(player_name, objective_name, _) => {
-                                first_scoreboard_pair =
-                                    Some(match_objects(Objects::ScoreboardPlayerPair(
-                                        objective_name,
-                                        player_name,
-                                        first_value.value.clone(),
-                                    )));
+                    let second_scoreboard_pair =
+                        match compiler.resolve(second_value.clone()).get_type() {
+                            pair @ Objects::ScoreboardPlayerPair(..) => pair,
+                            value @ (Objects::Number(_) | Objects::Boolean(_)) => {
+                                Objects::ScoreboardPlayerPair(
+                                    "value".to_string(),
+                                    "".to_string(),
+                                    Box::new(value),
+                                )
                             }
                             _ => {
-                                eprintln!("Invalid variable Access");
-                                exit(1);
-                            }
-                        }
-                        let second_value_original = second_value.clone();
-                        let second_value = second_value.as_any().downcast_ref::<VariableObject>();
-
-                        let mut second_scoreboard_pair: Option<Rc<dyn Object>> = None;
-
-                        if second_value.is_some() {
-                            let second_value = second_value.unwrap();
-
-                            if let Objects::Scoreboard(name, objective, _) =
-                                *second_value.scoreboard.clone()
-                            {
-                                println!("ASSIGNING SECOND");
-                                // assigns first player pair
-                                match *second_value.value.clone() {
-                                    Objects::Number(_) | Objects::Boolean(_) => {
-                                        second_scoreboard_pair =
-                                            Some(match_objects(Objects::ScoreboardPlayerPair(
-                                                name,
-                                                "value".to_string(),
-                                                second_value.value.clone(),
-                                            )));
-                                    }
-                                    Objects::ScoreboardPlayerPair(
-                                        objective_name,
-                                        player_name,
-                                        obj_type,
-                                    ) => {
-                                        println!("MEEEEEEEEEEEEEEEEEE");
-                                        second_scoreboard_pair =
-                                            Some(match_objects(Objects::ScoreboardPlayerPair(
-                                                objective_name,
-                                                player_name,
-                                                second_value.value.clone(),
-                                            )));
-                                    }
-                                    _ => {
-                                        eprintln!("Invalid variable");
-                                        exit(1);
-                                    }
-                                }
-                            } else {
                                 eprintln!("Invalid variable (SB)");
                                 exit(1);
                             }
-                            // TODO: ADD MUTATE VARIABLE LOGIC
-                        } else {
-                            match second_value_original.get_type() {
-                                Objects::Number(_) | Objects::Boolean(_) => {
-                                    second_scoreboard_pair =
-                                        Some(match_objects(Objects::ScoreboardPlayerPair(
-                                            "value".to_string(),
-                                            "".to_string(),
-                                            Box::new(second_value_original.get_type()),
-                                        )));
-                                }
-                                _ => {
-                                    eprintln!("Invalid variable (SB1)");
-                                    exit(1);
-                                }
-                            }
-                        }
-
-                        let first_scoreboard_pair = if first_scoreboard_pair.is_some() {
-                            first_scoreboard_pair.unwrap().get_type()
-                        } else {
-                            Objects::ScoreboardPlayerPair(
-                                "".to_string(),
-                                "value".to_string(),
-                                Box::new(Objects::Number(0)),
-                            )
-                        };
-
-                        let second_scoreboard_pair = if second_scoreboard_pair.is_some() {
-                            second_scoreboard_pair.unwrap().get_type()
-                        } else {
-                            Objects::ScoreboardPlayerPair(
-                                "".to_string(),
-                                "value".to_string(),
-                                Box::new(Objects::Number(0)),
-                            )
                         };
-
-                        return match_objects(Objects::MutationVariable(
-                            match_objects(first_scoreboard_pair.clone()),
-                            Box::new(first_scoreboard_pair),
-                            operator.clone(),
-                            match_objects(second_scoreboard_pair.clone()),
-                            Box::new(second_value_original.get_type()),
-                        ));
-                    } else {
-                        eprintln!("Invalid variable (SB1)");
-                        exit(1);
-                    }
+                    return match_objects(Objects::MutationVariable(
+                        match_objects(first_scoreboard_pair.clone()),
+                        Box::new(first_scoreboard_pair),
+                        operator.clone(),
+                        match_objects(second_scoreboard_pair),
+                        Box::new(second_value.get_type()),
+                    ));
                 } else {
                     return match_objects(Objects::MCStatement(Statements::Execute(vec![
                         ExecuteSteps::Compare(
-                            first_value.get_type().clone(),
+                            compiler.resolve(first_value).get_type(),
                             operator.clone(),
-                            second_value.get_type().clone(),
+                            compiler.resolve(second_value).get_type(),
                         ),
                     ])));
                 }
@@ -695,11 +1956,161 @@ impl Scope {
 
                 return match_objects(Objects::IfStatement(values, codeblock.clone()));
             }
+            ASTOperation::Execute(subcommands, codeblock, associate) => {
+                let mut steps: Vec<ExecuteSteps> = vec![];
+                for (subcommand, arguments) in subcommands {
+                    let mut values: Vec<Rc<dyn Object>> = vec![];
+                    for argument in arguments {
+                        let value = self.execute(argument, current_variable.clone(), compiler);
+                        if let Objects::Variable(value, _) = value.get_type() {
+                            values.push(match_objects(*value));
+                        } else {
+                            values.push(value);
+                        }
+                    }
+                    let step = execute_step_from(subcommand, values);
+                    if step.is_none() {
+                        compile_error(CompileErrors::TypeError(associate.clone()));
+                        exit(1);
+                    }
+                    steps.push(step.unwrap());
+                }
+
+                match_objects(Objects::ExecuteBlock(steps, codeblock.clone()))
+            }
+            ASTOperation::Trigger(objective, parameters, code, associate) => {
+                if !is_objective_name(objective) || parameters.len() > 2 {
+                    compile_error(CompileErrors::SyntaxError(associate.clone()));
+                    exit(1);
+                }
+                let mut statements = vec![];
+                if let ASTOperation::CodeBlock(operations, _) = &**code {
+                    statements = operations.clone();
+                    if let [ASTOperation::Set(operations, _)] = statements.as_slice() {
+                        statements = operations.clone();
+                    }
+                }
+
+                let mut handler = Scope::new(
+                    format!("{}.{}", self.name, self.scopes.len()),
+                    self.namespace.clone(),
+                    statements,
+                    self.functions.clone(),
+                );
+                handler.variables = self.variables.block(&handler.name);
+                handler.temporaries = self.temporaries.above();
+                // the handler runs as the player, whose score holds the value they picked
+                let values = [
+                    Objects::Entity("@s".to_string()),
+                    Objects::ScoreboardPlayerPair(
+                        objective.clone(),
+                        "@s".to_string(),
+                        Box::new(Objects::Number(0)),
+                    ),
+                ];
+                for (parameter, value) in parameters.iter().zip(values) {
+                    handler.variables.insert(
+                        parameter.clone(),
+                        Variable {
+                            name: parameter.clone(),
+                            value: mk_variable(value, Objects::Unknown),
+                            static_variable: true,
+                        },
+                    );
+                }
+                compiler.compile(&mut handler);
+                self.scopes.push(handler.clone());
+
+                let scoreboard = ScoreboardObject {
+                    name: objective.clone(),
+                    objective: "trigger".to_string(),
+                    objective_type: Objects::Number(0),
+                };
+                let (create, _) =
+                    compile_into_variable(scoreboard.get_type(), Objects::Unknown, compiler);
+                let everyone = match_objects(Objects::Entity("@a".to_string()));
+                let player = match_objects(Objects::Entity("@s".to_string()));
+                let enable = scoreboard.command("enable", vec![everyone]);
+                let reset = scoreboard.command("reset", vec![player]);
+
+                compiler.validate(&format!("{}\n{}\n{}", reset, create, enable), associate);
+                let output_name = handler.output_name(&compiler.namespace);
+                if let Some(output) = compiler.outputs.get_mut(&output_name) {
+                    output.push_str(&format!("\n{}", reset));
+                }
+                push_once(&mut compiler.load, create.trim().to_string(), associate);
+                push_once(&mut compiler.tick, enable, associate);
+                for scores in ["1..", "..-1"] {
+                    let run = format!(
+                        "execute as @a[scores={{{}={}}}] at @s run function {}:{}",
+                        objective, scores, handler.namespace, handler.name
+                    );
+                    compiler.validate(&run, associate);
+                    compiler.tick.push((run, associate.clone()));
+                }
+                match_objects(Objects::Unknown)
+            }
+            ASTOperation::Struct(name, fields, methods, associate) => {
+                let mut definition = StructDefinition {
+                    name: name.clone(),
+                    fields: vec![],
+                    methods: HashMap::new(),
+                    objectives: HashMap::new(),
+                };
+                for (field, kind) in fields {
+                    let kind = FieldKind::parse(kind);
+                    if kind.is_none() {
+                        compile_error(CompileErrors::TypeError(associate.clone()));
+                        exit(1);
+                    }
+                    let kind = kind.unwrap();
+                    match kind {
+                        FieldKind::Score => {
+                            let objective = compiler.name(&self.namespace, name, field);
+                            // objectives are created when the datapack loads
+                            let create = format!("scoreboard objectives add {} dummy", objective);
+                            compiler.validate(&create, associate);
+                            push_once(&mut compiler.load, create, associate);
+                            definition.objectives.insert(field.clone(), objective);
+                        }
+                        FieldKind::Storage(_) => compiler.require(Feature::DataStorage, associate),
+                    }
+                    definition.fields.push((field.clone(), kind));
+                }
+                // several methods arrive as a set
+                let methods = match methods.as_slice() {
+                    [ASTOperation::Set(methods, _)] => methods,
+                    _ => methods,
+                };
+                for method in methods {
+                    if let ASTOperation::CreateFunction(method, arguments, code, associate) = method
+                    {
+                        definition.methods.insert(
+                            method.clone(),
+                            Function {
+                                name: method.clone(),
+                                arguments: arguments.clone(),
+                                code: code.clone(),
+                                scope: self.name.clone(),
+                                module: None,
+                                exported: None,
+                                associate: associate.clone(),
+                            },
+                        );
+                    }
+                }
+                compiler.structs.insert(name.clone(), definition);
+                match_objects(Objects::Unknown)
+            }
             ASTOperation::CreateFunction(name, arguments, code, associate) => {
                 let function = Function {
                     name: name.clone(),
                     arguments: arguments.clone(),
                     code: code.clone(),
+                    scope: self.name.clone(),
+                    module: None,
+                    exported: None,
+                    associate: associate.clone(),
                 };
                 self.functions.insert(name.clone(), function);
                 return match_objects(Objects::CreatedFunction);
@@ -709,6 +2120,12 @@ impl Scope {
                 if let ASTOperation::Set(operations, associate) = &set[0] {
                     for operation in operations {
                         let execution = self.execute(&operation, None, compiler);
+                        let execution = self.value(execution, associate, compiler);
+                        if let Objects::ScoreboardPlayerPair(..) | Objects::StoragePath(..) =
+                            execution.get_type()
+                        {
+                            compiler.register_macro_argument(&execution.get_type());
+                        }
 
                         items.push(execution);
                     }
@@ -724,59 +2141,52 @@ impl Scope {
                     let own_function = own_function.get(name);
 
                     if own_function.is_none() {
+                        if name == "cmd" {
+                            // cmd("...", false) skips the check of the command's root
+                            let command = items
+                                .first()
+                                .and_then(|item| item.as_any().downcast_ref::<StringObject>());
+                            let check = match items.get(1).map(|item| item.get_type()) {
+                                None => true,
+                                Some(Objects::Boolean(check)) => check,
+                                Some(_) => {
+                                    compile_error(CompileErrors::TypeError(associate.clone()));
+                                    exit(1);
+                                }
+                            };
+                            if command.is_none() || items.len() > 2 {
+                                compile_error(CompileErrors::MissingParams(associate.clone()));
+                                exit(1);
+                            }
+                            let command = command.unwrap().value.clone();
+                            return self.command(&command, check, associate, compiler);
+                        }
+                        if let Some(builtin) = builtin_function(name) {
+                            let mut arguments = vec![];
+                            for item in items {
+                                match item.as_any().downcast_ref::<StringObject>() {
+                                    Some(str) => arguments
+                                        .push(self.interpolate(&str.value, associate, compiler)),
+                                    None => arguments.push(compiler.resolve(item)),
+                                }
+                            }
+                            return builtin(arguments, None);
+                        }
                         compile_error(CompileErrors::UnknownIdentifier(associate.clone()));
                         exit(1);
                     }
 
-                    let own_function = own_function.unwrap();
-                    // pass the arguments to the function
-                    let mut function_scope = Scope::new(
-                        format!("{}.{}", self.name, self.scopes.len()),
-                        self.namespace.clone(),
-                        own_function.code.clone(),
-                        self.functions.clone(),
-                    );
-
-                    function_scope.variables = self.variables.clone();
-                    for (index, item) in items.iter().enumerate() {
-                        if let Objects::Variable(value, _) = item.get_type() {
-                            let variable_object =
-                                item.as_any().downcast_ref::<VariableObject>().unwrap();
-                            function_scope.variables.insert(
-                                own_function.arguments[index].clone(),
-                                Variable {
-                                    name: own_function.arguments[index].clone(),
-                                    value: Rc::new(VariableObject {
-                                        value: Box::new(*value.clone()),
-                                        scoreboard: variable_object.scoreboard.clone(),
-                                    }),
-                                    static_variable: false,
-                                },
-                            );
-                        } else {
-                            function_scope.variables.insert(
-                                own_function.arguments[index].clone(),
-                                Variable {
-                                    name: own_function.arguments[index].clone(),
-                                    value: Rc::new(VariableObject {
-                                        value: Box::new(item.get_type()),
-                                        scoreboard: Box::new(Objects::Scoreboard(
-                                            "".to_string(),
-                                            "dummy".to_string(),
-                                            Box::new(item.get_type()),
-                                        )),
-                                    }),
-                                    static_variable: true,
-                                },
-                            );
-                        }
-                    }
-
-                    for statement in own_function.code.iter() {
-                        function_scope.execute(statement, None, compiler);
+                    let own_function = own_function.unwrap().clone();
+                    let external = own_function.exported.as_ref().is_some_and(|exported| {
+                        exported
+                            .split_once(':')
+                            .is_some_and(|(namespace, _)| compiler.external.contains(namespace))
+                    });
+                    if external {
+                        return self.call_external(&own_function, &items, associate, compiler);
                     }
-                    self.scopes.push(function_scope.clone());
-
+                    let function_scope =
+                        self.call(&own_function, &items, None, associate, compiler);
                     let function_call =
                         &format!("{}:{}", function_scope.namespace, function_scope.name);
                     return match_objects(Objects::MCStatement(Statements::Function(
@@ -798,7 +2208,31 @@ impl Scope {
                     eprintln!("Failed to convert to variable in function call.");
                     exit(1);
                 }
-                let function = match_objects(*function.unwrap().value.clone()).get_functions();
+                let function = match_objects(*function.unwrap().value.clone());
+                if let Some(instance) = function.as_any().downcast_ref::<StructObject>() {
+                    let method = instance.definition.methods.get(name);
+                    if method.is_none() {
+                        compile_error(CompileErrors::UnknownIdentifier(associate.clone()));
+                        exit(1);
+                    }
+                    let method = method.unwrap().clone();
+                    let method_scope = self.call(
+                        &method,
+                        &items,
+                        Some(instance.as_self()),
+                        associate,
+                        compiler,
+                    );
+                    let function_call = format!("{}:{}", method_scope.namespace, method_scope.name);
+                    // methods run as the entity owning the instance
+                    return match_objects(Objects::MCStatement(match &instance.owner {
+                        Some(owner) => {
+                            Statements::FunctionAs(owner.clone(), function_call, method_scope)
+                        }
+                        None => Statements::Function(function_call, method_scope),
+                    }));
+                }
+                let function = function.get_functions();
 
                 let function = function.get(name);
                 if function.is_none() {
@@ -807,33 +2241,10 @@ impl Scope {
                 }
                 let function = function.unwrap();
 
-                let mut pass_items: Vec<Rc<dyn Object>> = vec![];
-                for execution in items {
-                    if let Objects::Variable(value, _) = execution.get_type() {
-                        match *value {
-                            Objects::Number(_) | Objects::Boolean(_) => {
-                                let variable_object =
-                                    execution.as_any().downcast_ref::<VariableObject>().unwrap();
-                                if let Objects::Scoreboard(name, _, _) =
-                                    *variable_object.scoreboard.clone()
-                                {
-                                    pass_items.push(match_objects(Objects::ScoreboardPlayerPair(
-                                        name,
-                                        "value".to_string(),
-                                        Box::new(*value.clone()),
-                                    )));
-                                } else {
-                                    pass_items.push(match_objects(*value));
-                                }
-                            }
-                            _ => {
-                                pass_items.push(match_objects(*value));
-                            }
-                        }
-                    } else {
-                        pass_items.push(execution);
-                    }
-                }
+                let pass_items = items
+                    .into_iter()
+                    .map(|item| compiler.resolve(item))
+                    .collect();
 
                 return function(
                     pass_items,
@@ -858,8 +2269,109 @@ impl Scope {
                     exit(1);
                 }
             }
+            ASTOperation::For(name, set, code, associate) => {
+                let mut iterator = self.execute(&set[0], current_variable.clone(), compiler);
+                let list = runtime_list(&iterator);
+                if list.is_none() {
+                    // arrays known at compile time are unrolled like while loops
+                    if let Some(variable) = iterator.as_any().downcast_ref::<VariableObject>() {
+                        iterator = match_objects(*variable.value.clone());
+                    }
+                    if let Objects::Array(iterator) = iterator.get_type() {
+                        return match_objects(Objects::While(name.clone(), iterator, code.clone()));
+                    }
+                    compile_error(CompileErrors::TypeError(associate.clone()));
+                    exit(1);
+                }
+                let list = list.unwrap();
+                let mut statements = vec![];
+                if let ASTOperation::CodeBlock(operations, _) = &**code {
+                    statements = operations.clone();
+                    if let [ASTOperation::Set(operations, _)] = statements.as_slice() {
+                        statements = operations.clone();
+                    }
+                }
+
+                let mut body = Scope::new(
+                    format!("{}.{}", self.name, self.scopes.len()),
+                    self.namespace.clone(),
+                    statements,
+                    self.functions.clone(),
+                );
+                body.variables = self.variables.block(&body.name);
+                body.temporaries = self.temporaries.above();
+                // the loop consumes a copy of the list, so the current item is always its first
+                // element
+                let queue = ListObject {
+                    storage: list.storage.clone(),
+                    path: format!(
+                        "__for.{}",
+                        body.name.replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+                    ),
+                    element: list.element.clone(),
+                };
+                body.variables.insert(
+                    name.clone(),
+                    Variable {
+                        name: name.clone(),
+                        value: mk_variable(queue.element(0).get_type(), Objects::Unknown),
+                        static_variable: true,
+                    },
+                );
+                compiler.compile(&mut body);
+                self.scopes.push(body.clone());
+
+                let next = format!(
+                    "execute if data storage {} {}[0] run function {}:{}",
+                    queue.storage, queue.path, body.namespace, body.name
+                );
+                let advance = format!(
+                    "data remove storage {} {}[0]\n{}",
+                    queue.storage, queue.path, next
+                );
+                compiler.validate(&advance, associate);
+                let output_name = body.output_name(&compiler.namespace);
+                if let Some(output) = compiler.outputs.get_mut(&output_name) {
+                    output.push_str(&format!("\n{}", advance));
+                }
+                match_objects(Objects::Array(vec![
+                    match_objects(Objects::MCStatement(Statements::Raw(format!(
+                        "data modify storage {} {} set from storage {} {}",
+                        queue.storage, queue.path, list.storage, list.path
+                    )))),
+                    match_objects(Objects::MCStatement(Statements::Raw(next))),
+                ]))
+            }
 
             ASTOperation::Create(object_name, params, associate) => {
+                if object_name == "Storage" {
+                    compiler.require(Feature::DataStorage, associate);
+                }
+                if let Some(definition) = compiler.structs.get(object_name).cloned() {
+                    let instance = format!(
+                        "{}{}",
+                        definition.name.to_lowercase(),
+                        compiler.struct_instances
+                    );
+                    compiler.struct_instances += 1;
+                    let instance = StructObject {
+                        definition,
+                        instance,
+                        owner: None,
+                        namespace: self.namespace.clone(),
+                    };
+                    let operations = match params.first() {
+                        None => vec![],
+                        Some(ASTOperation::Set(operations, _)) => operations.clone(),
+                        Some(operation) => vec![operation.clone()],
+                    };
+                    let mut owners = vec![];
+                    for operation in &operations {
+                        let owner = self.execute(operation, None, compiler);
+                        owners.push(compiler.resolve(owner).get_type());
+                    }
+                    return Rc::new(instance.instantiate(&owners, associate));
+                }
                 let object = name_into_object(object_name);
                 let object = object.get_functions();
                 let function = object.get("instantiate");
@@ -878,10 +2390,26 @@ impl Scope {
 
                     for operation in operations {
                         let execution = self.execute(&operation, None, compiler);
-                        if let Objects::Variable(value, _) = execution.get_type() {
-                            items.push(match_objects(*value));
-                        } else {
-                            items.push(execution);
+                        match execution.get_type() {
+                            // variables kept in scores can change at runtime, so their
+                            // values are passed through macro arguments
+                            Objects::Variable(value, scoreboard) => {
+                                let variable = Objects::Variable(value.clone(), scoreboard);
+                                let runtime = matches!(
+                                    compiler.resolve(execution.clone()).get_type(),
+                                    Objects::ScoreboardPlayerPair(..)
+                                );
+                                if runtime {
+                                    compiler.register_macro_argument(&variable);
+                                    items.push(execution);
+                                } else {
+                                    items.push(match_objects(*value));
+                                }
+                            }
+                            value => {
+                                compiler.register_macro_argument(&value);
+                                items.push(execution);
+                            }
                         }
                     }
 
@@ -897,6 +2425,18 @@ impl Scope {
                     exit(1);
                 }
             }
+            ASTOperation::List(elements, _) => {
+                let mut values: Vec<Rc<dyn Object>> = vec![];
+                for operation in elements {
+                    let value = self.execute(operation, current_variable.clone(), compiler);
+                    if let Objects::Variable(value, _) = value.get_type() {
+                        values.push(match_objects(*value));
+                    } else {
+                        values.push(value);
+                    }
+                }
+                match_objects(Objects::Array(values))
+            }
             ASTOperation::Set(multiple, associate) => {
                 let mut set_values: Vec<Rc<dyn Object>> = vec![];
                 for operation in multiple {
@@ -910,3 +2450,121 @@ impl Scope {
         }
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::errors::sources::NO_FILE;
+
+    /// The commands of the functions compiled from `code` for a target version, without empty
+    /// lines.
+    pub fn compiled_for(target: &str, code: &str) -> HashMap<String, String> {
+        let mut lexer = Lexer::new(code.to_string(), NO_FILE).quiet();
+        lexer.tokenizer();
+        let mut ast = AST::new(lexer.flush().to_vec());
+        ast.generate();
+        let mut compiler = Compiler::new("test");
+        compiler.set_target(target).unwrap();
+        let mut scope = Scope::new(
+            "code".to_string(),
+            "test".to_string(),
+            ast.flush().to_vec(),
+            HashMap::new(),
+        );
+        compiler.scopes.push(scope.clone());
+        compiler.compile(&mut scope);
+        compiler.finish();
+        compiler
+            .outputs
+            .into_iter()
+            .map(|(name, commands)| {
+                let commands = commands.lines().filter(|line| !line.trim().is_empty());
+                (name, commands.collect::<Vec<&str>>().join("\n"))
+            })
+            .collect()
+    }
+
+    /// The functions compiled from `code` for the default target.
+    pub fn compiled(code: &str) -> HashMap<String, String> {
+        compiled_for(DEFAULT_TARGET, code)
+    }
+
+    #[test]
+    fn handles_triggers_every_tick() {
+        let outputs = compiled(
+            "on trigger \"menu\" (player, value) {\n    tellraw(player, \"You picked {value}\");\n}\n",
+        );
+        assert_eq!(outputs["load"], "scoreboard objectives add menu trigger");
+        assert_eq!(
+            outputs["tick"],
+            "scoreboard players enable @a menu\nexecute as @a[scores={menu=1..}] at @s run function test:code.0\nexecute as @a[scores={menu=..-1}] at @s run function test:code.0"
+        );
+        assert_eq!(
+            outputs["code.0"],
+            "tellraw @s {\"text\":\"\",\"extra\":[{\"text\":\"You picked \"},{\"score\":{\"name\":\"@s\",\"objective\":\"menu\"}}]}\nscoreboard players reset @s menu"
+        );
+    }
+
+    #[test]
+    fn copies_unannotated_score_arguments() {
+        let outputs = compiled("fn f(n) {\n    n = n + 1;\n}\nlet a = 1;\nf(a);\n");
+        // the function changes its own copy, `a` keeps its value
+        assert_eq!(
+            outputs["code.0"],
+            "scoreboard objectives add test.code.0.n dummy\nscoreboard players operation $test test.code.0.n = $test test.code.a\nscoreboard players add $test test.code.0.n 1"
+        );
+        assert_eq!(
+            outputs["code"],
+            "scoreboard objectives add test.code.a dummy\nscoreboard players set $test test.code.a 1\nfunction test:code.0"
+        );
+    }
+
+    #[test]
+    fn declares_temporaries_once_per_function() {
+        let outputs = compiled("let a = 1;\nlet b = a * 2;\nlet c = a * 3;\n");
+        // the second statement reuses the objectives the first one added
+        assert_eq!(
+            outputs["code"],
+            "scoreboard objectives add test.code.a dummy\nscoreboard players set $test test.code.a 1\nscoreboard objectives add test.code.tmp-0 dummy\nscoreboard players operation $test test.code.tmp-0 = $test test.code.a\nscoreboard objectives add test.code.tmp-1 dummy\nscoreboard players set $test test.code.tmp-1 2\nscoreboard players operation $test test.code.tmp-0 *= $test test.code.tmp-1\nscoreboard objectives add test.code.b dummy\nscoreboard players operation $test test.code.b = $test test.code.tmp-0\nscoreboard players operation $test test.code.tmp-0 = $test test.code.a\nscoreboard players set $test test.code.tmp-1 3\nscoreboard players operation $test test.code.tmp-0 *= $test test.code.tmp-1\nscoreboard objectives add test.code.c dummy\nscoreboard players operation $test test.code.c = $test test.code.tmp-0"
+        );
+    }
+
+    #[test]
+    fn calls_functions_of_unbundled_dependencies() {
+        let parsed = |code: &str| {
+            let mut lexer = Lexer::new(code.to_string(), NO_FILE).quiet();
+            lexer.tokenizer();
+            let mut ast = AST::new(lexer.flush().to_vec());
+            ast.generate();
+            ast.flush().to_vec()
+        };
+        let lines = |commands: &str| {
+            let commands = commands.lines().filter(|line| !line.trim().is_empty());
+            commands.collect::<Vec<&str>>().join("\n")
+        };
+        // the dependency writes the function into its own datapack
+        let mut library = Compiler::new("math");
+        let code = parsed("export fn hello(n) {\n    n += 1;\n}\n");
+        let mut module = Scope::new("util".to_string(), "math".to_string(), code, HashMap::new());
+        library.compile(&mut module);
+        let hello = module.exported_function("hello").unwrap();
+        let mut external = Scope::external(&hello, &hello.associate, &mut library).unwrap();
+        library.compile(&mut external);
+        assert_eq!(
+            lines(&library.outputs["util.hello"]),
+            "scoreboard objectives add math.util.hello.n dummy\nscoreboard players add $math math.util.hello.n 1"
+        );
+
+        // the project depending on it sets the argument and calls it there
+        let mut compiler = Compiler::new("game");
+        compiler.external.insert("math".to_string());
+        let functions = HashMap::from([("hello".to_string(), hello)]);
+        let code = parsed("let a = 1;\nhello(a);\n");
+        let mut scope = Scope::new("code".to_string(), "game".to_string(), code, functions);
+        compiler.compile(&mut scope);
+        assert_eq!(
+            lines(&compiler.outputs["code"]),
+            "scoreboard objectives add game.code.a dummy\nscoreboard players set $game game.code.a 1\nscoreboard objectives add math.util.hello.n dummy\nscoreboard players operation $math math.util.hello.n = $game game.code.a\nfunction math:util.hello"
+        );
+    }
+}
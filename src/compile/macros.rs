@@ -0,0 +1,119 @@
+//! Function macros (1.20.2+) let a command use values that are only known at runtime, such as
+//! the score passed to a function, in positions where commands only accept literals. Lines
+//! that use them are moved into a macro function, which is called `with` the compound the
+//! values are copied into.
+
+use crate::compile::objects::Objects;
+
+/// The storage path, inside the `<namespace>:macro` storage, holding the macro arguments.
+pub const MACRO_ARGUMENTS_PATH: &str = "args";
+
+/// Joins the parts of a macro argument name. Placeholders only allow letters, digits and
+/// `_`, so every other byte is written as `_` and two hex digits, and parts are separated by
+/// `__`, which an escaped byte never starts with. Different values never share a name.
+fn encode(parts: &[&str]) -> String {
+    parts
+        .iter()
+        .map(|part| {
+            part.bytes()
+                .map(|byte| {
+                    if byte.is_ascii_alphanumeric() {
+                        (byte as char).to_string()
+                    } else {
+                        format!("_{:02x}", byte)
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("__")
+}
+
+/// The name of the macro argument for a runtime value: a score or a storage path. Values
+/// known at compile time have none.
+pub fn macro_key(value: &Objects) -> Option<String> {
+    match value {
+        Objects::Variable(value, scoreboard) => match (&**value, &**scoreboard) {
+            (Objects::Number(_) | Objects::Boolean(_), Objects::Scoreboard(name, _, _)) => {
+                Some(encode(&["var", name]))
+            }
+            // a variable holding a score or a storage path
+            (value, _) => macro_key(value),
+        },
+        Objects::ScoreboardPlayerPair(objective, player, _) => {
+            Some(encode(&["score", objective, player]))
+        }
+        Objects::StoragePath(storage, path, _) => Some(encode(&["storage", storage, path])),
+        _ => None,
+    }
+}
+
+/// The placeholder a command uses for a runtime value, e.g. `$(points_value)`.
+pub fn macro_placeholder(key: &str) -> String {
+    format!("$({})", key)
+}
+
+/// The command that copies a runtime value into the macro arguments before the call, if the
+/// value is a score or a storage path.
+pub fn macro_setup(value: &Objects, key: &str, namespace: &str) -> Option<String> {
+    let target = format!(
+        "storage {}:macro {}.{}",
+        namespace, MACRO_ARGUMENTS_PATH, key
+    );
+    match value {
+        Objects::ScoreboardPlayerPair(objective, player, _) => Some(format!(
+            "execute store result {} int 1 run scoreboard players get {} {}",
+            target, player, objective
+        )),
+        Objects::StoragePath(storage, path, _) => Some(format!(
+            "data modify {} set from storage {} {}",
+            target, storage, path
+        )),
+        _ => None,
+    }
+}
+
+/// The names of the placeholders used in a command.
+pub fn macro_placeholders(command: &str) -> Vec<String> {
+    let mut keys = vec![];
+    let mut rest = command;
+    while let Some(start) = rest.find("$(") {
+        rest = &rest[start + 2..];
+        match rest.find(')') {
+            Some(end) => {
+                let key = &rest[..end];
+                if !key.is_empty()
+                    && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                    && !keys.contains(&key.to_string())
+                {
+                    keys.push(key.to_string());
+                }
+                rest = &rest[end + 1..];
+            }
+            None => break,
+        }
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::macro_key;
+    use crate::compile::objects::Objects;
+
+    fn score(objective: &str, player: &str) -> Objects {
+        Objects::ScoreboardPlayerPair(
+            objective.to_string(),
+            player.to_string(),
+            Box::new(Objects::Number(0)),
+        )
+    }
+
+    #[test]
+    fn gives_different_values_different_keys() {
+        let first = macro_key(&score("hp", "#x")).unwrap();
+        let second = macro_key(&score("hp_", "x")).unwrap();
+        assert_ne!(first, second);
+        assert!(first.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+    }
+}
@@ -4,21 +4,47 @@ use crate::ast::operations::Operator;
 
 use super::{
     compiler::Scope,
-    obj::{scoreboard::ScoreboardPlayerPairObject, std::VariableObject},
-    objects::{mk_variable, Object, Objects},
+    obj::{
+        blockpos::{BlockPosObject, Coordinate},
+        item::{ItemObject, ItemSyntax},
+        scoreboard::ScoreboardPlayerPairObject,
+        std::VariableObject,
+        text::TextObject,
+    },
+    objects::{mk_variable, Object, ObjectFunction, Objects},
 };
 
 #[derive(Clone, Debug)]
 pub enum Statements {
     Execute(Vec<ExecuteSteps>),
     Function(String, Scope),
+    /// A call to a function run as an entity: its selector, the function and its scope.
+    FunctionAs(String, String, Scope),
     Raw(String),
+    /// A command resulting in a number, such as `data get`, which can be stored in a score.
+    Query(String),
+    /// A command around an item, which is rendered once the target pack format is known.
+    Item(String, ItemObject, ItemSyntax, String),
+    /// A command around a text component, rendered the same way as items.
+    Text(String, TextObject, String),
+    /// A call to a helper macro function shared by the whole datapack: its name, its body
+    /// and the source of its arguments, e.g. `storage mypack:state __args`.
+    Helper(String, String, String),
 }
 #[derive(Clone, Debug)]
 pub enum ExecuteSteps {
     As(Objects),
     At(Objects),
     In(Objects),
+    Positioned(Objects),
+    PositionedAs(Objects),
+    Facing(Objects),
+    FacingEntity(Objects),
+    Rotated(Objects),
+    RotatedAs(Objects),
+    Anchored(String),
+    IfBlock(Objects, String),
+    UnlessBlock(Objects, String),
     Compare(Objects, Operator, Objects),
 }
 
@@ -43,12 +69,7 @@ impl Object for MinecraftStatementObject {
         self
     }
 
-    fn get_functions(
-        &self,
-    ) -> HashMap<
-        String,
-        Box<dyn Fn(Vec<Rc<dyn Object>>, Option<Rc<VariableObject>>) -> Rc<dyn Object>>,
-    > {
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
         HashMap::new()
     }
 }
@@ -71,6 +92,80 @@ pub fn execute_step_str(step: ExecuteSteps) -> String {
                 exit(1);
             }
         }
+        ExecuteSteps::In(dimension) => match dimension {
+            Objects::Dimension(dimension) => format!("in {}", dimension),
+            Objects::String(dimension) if dimension.contains(':') => format!("in {}", dimension),
+            Objects::String(dimension) => format!("in minecraft:{}", dimension),
+            _ => {
+                eprintln!("Incorrect argument");
+                exit(1);
+            }
+        },
+        ExecuteSteps::Positioned(position) => match position {
+            Objects::BlockPos(x, y, z) => format!("positioned {} {} {}", x, y, z),
+            Objects::String(position) => format!("positioned {}", position),
+            _ => {
+                eprintln!("Incorrect argument");
+                exit(1);
+            }
+        },
+        ExecuteSteps::PositionedAs(entity) => {
+            if let Objects::Entity(selector) = entity {
+                format!("positioned as {}", selector)
+            } else {
+                eprintln!("Incorrect argument");
+                exit(1);
+            }
+        }
+        ExecuteSteps::Facing(position) => match position {
+            Objects::BlockPos(x, y, z) => format!("facing {} {} {}", x, y, z),
+            Objects::String(position) => format!("facing {}", position),
+            _ => {
+                eprintln!("Incorrect argument");
+                exit(1);
+            }
+        },
+        ExecuteSteps::FacingEntity(entity) => {
+            if let Objects::Entity(selector) = entity {
+                format!("facing entity {} eyes", selector)
+            } else {
+                eprintln!("Incorrect argument");
+                exit(1);
+            }
+        }
+        ExecuteSteps::Rotated(rotation) => {
+            if let Objects::String(rotation) = rotation {
+                format!("rotated {}", rotation)
+            } else {
+                eprintln!("Incorrect argument");
+                exit(1);
+            }
+        }
+        ExecuteSteps::RotatedAs(entity) => {
+            if let Objects::Entity(selector) = entity {
+                format!("rotated as {}", selector)
+            } else {
+                eprintln!("Incorrect argument");
+                exit(1);
+            }
+        }
+        ExecuteSteps::Anchored(anchor) => format!("anchored {}", anchor),
+        ExecuteSteps::IfBlock(position, block) => {
+            if let Objects::BlockPos(x, y, z) = position {
+                format!("if block {} {} {} {}", x, y, z, block)
+            } else {
+                eprintln!("Incorrect argument");
+                exit(1);
+            }
+        }
+        ExecuteSteps::UnlessBlock(position, block) => {
+            if let Objects::BlockPos(x, y, z) = position {
+                format!("unless block {} {} {} {}", x, y, z, block)
+            } else {
+                eprintln!("Incorrect argument");
+                exit(1);
+            }
+        }
         ExecuteSteps::Compare(first, operand, second) => {
             if let Objects::MCStatement(statement_first) = &first {
                 let mut parts: Vec<String> = vec![];
@@ -109,37 +204,10 @@ pub fn execute_step_str(step: ExecuteSteps) -> String {
                     }
                 }
             }
+            // variables are resolved to their scores when the comparison is built
             let mut first_scoreboard_pair: Option<ScoreboardPlayerPairObject> = None;
-
-            if let Objects::Variable(value, scoreboard) = first.clone() {
-                if let Objects::Scoreboard(scoreboard_first_name, _, first_objective_type) =
-                    *scoreboard
-                {
-                    first_scoreboard_pair = match *value {
-                        Objects::Number(_) | Objects::Boolean(_) => {
-                            Some(ScoreboardPlayerPairObject {
-                                objective_type: *first_objective_type.clone(),
-                                objective_name: scoreboard_first_name.clone(),
-                                player_name: "value".to_string(),
-                            })
-                        }
-                        Objects::ScoreboardPlayerPair(
-                            objective_name,
-                            player_name,
-                            objective_type,
-                        ) => Some(ScoreboardPlayerPairObject {
-                            objective_type: *objective_type.clone(),
-                            objective_name: objective_name.clone(),
-                            player_name: player_name.clone(),
-                        }),
-                        _ => None,
-                    };
-                }
-            } else if let Objects::ScoreboardPlayerPair(
-                objective_name,
-                player_name,
-                objective_type,
-            ) = first.clone()
+            if let Objects::ScoreboardPlayerPair(objective_name, player_name, objective_type) =
+                first.clone()
             {
                 first_scoreboard_pair = Some(ScoreboardPlayerPairObject {
                     objective_type: *objective_type.clone(),
@@ -149,36 +217,8 @@ pub fn execute_step_str(step: ExecuteSteps) -> String {
             }
 
             let mut second_scoreboard_pair: Option<ScoreboardPlayerPairObject> = None;
-            if let Objects::Variable(value_second, scoreboard_second) = second.clone() {
-                if let Objects::Scoreboard(scoreboard_second_name, _, objective_type) =
-                    *scoreboard_second.clone()
-                {
-                    println!("{:?} {:?}", value_second, scoreboard_second);
-                    second_scoreboard_pair = match *value_second {
-                        Objects::Number(_) | Objects::Boolean(_) => {
-                            Some(ScoreboardPlayerPairObject {
-                                objective_type: *objective_type.clone(),
-                                objective_name: scoreboard_second_name.clone(),
-                                player_name: "value".to_string(),
-                            })
-                        }
-                        Objects::ScoreboardPlayerPair(
-                            objective_name,
-                            player_name,
-                            objective_type,
-                        ) => Some(ScoreboardPlayerPairObject {
-                            objective_type: *objective_type.clone(),
-                            objective_name: objective_name.clone(),
-                            player_name: player_name.clone(),
-                        }),
-                        _ => None,
-                    };
-                }
-            } else if let Objects::ScoreboardPlayerPair(
-                objective_name,
-                player_name,
-                objective_type,
-            ) = second.clone()
+            if let Objects::ScoreboardPlayerPair(objective_name, player_name, objective_type) =
+                second.clone()
             {
                 second_scoreboard_pair = Some(ScoreboardPlayerPairObject {
                     objective_type: *objective_type.clone(),
@@ -254,13 +294,71 @@ pub fn execute_step_str(step: ExecuteSteps) -> String {
 
             return String::new();
         }
-        _ => {
-            return "".to_string();
+    }
+}
+
+/// Builds the execute step for a subcommand of an execute block from its evaluated argument.
+pub fn execute_step_from(subcommand: &str, arguments: Vec<Rc<dyn Object>>) -> Option<ExecuteSteps> {
+    if arguments.len() != 1 {
+        return None;
+    }
+    let argument = match arguments[0].get_type() {
+        // coordinates written inline, e.g. `positioned (~ ~1 ~)` or `rotated (~ 90)`
+        Objects::Array(values) => {
+            let values = values
+                .iter()
+                .map(|value| value.get_type())
+                .collect::<Vec<Objects>>();
+            if values.len() == 3 {
+                BlockPosObject::from_objects(&values).ok()?.get_type()
+            } else if values.len() == 2 {
+                let rotation = values
+                    .iter()
+                    .map(|value| Coordinate::from_object(value).map(|value| value.to_string()))
+                    .collect::<Option<Vec<String>>>()?;
+                Objects::String(rotation.join(" "))
+            } else {
+                return None;
+            }
+        }
+        argument => argument,
+    };
+    match (subcommand, argument) {
+        ("as", Objects::Entity(selector)) => Some(ExecuteSteps::As(Objects::Entity(selector))),
+        ("at", Objects::Entity(selector)) => Some(ExecuteSteps::At(Objects::Entity(selector))),
+        ("in", Objects::String(dimension)) => Some(ExecuteSteps::In(Objects::String(dimension))),
+        ("in", Objects::Dimension(dimension)) => {
+            Some(ExecuteSteps::In(Objects::Dimension(dimension)))
+        }
+        ("positioned", Objects::Entity(selector)) => {
+            Some(ExecuteSteps::PositionedAs(Objects::Entity(selector)))
+        }
+        ("positioned", position @ (Objects::BlockPos(..) | Objects::String(_))) => {
+            Some(ExecuteSteps::Positioned(position))
+        }
+        ("facing", Objects::Entity(selector)) => {
+            Some(ExecuteSteps::FacingEntity(Objects::Entity(selector)))
         }
+        ("facing", position @ (Objects::BlockPos(..) | Objects::String(_))) => {
+            Some(ExecuteSteps::Facing(position))
+        }
+        ("rotated", Objects::Entity(selector)) => {
+            Some(ExecuteSteps::RotatedAs(Objects::Entity(selector)))
+        }
+        ("rotated", Objects::String(rotation)) => {
+            Some(ExecuteSteps::Rotated(Objects::String(rotation)))
+        }
+        ("anchored", Objects::String(anchor)) if anchor == "eyes" || anchor == "feet" => {
+            Some(ExecuteSteps::Anchored(anchor))
+        }
+        _ => None,
     }
 }
 
-pub fn compile_into_mcstatement(statement: Statements) -> (String, Option<Scope>) {
+pub fn compile_into_mcstatement(
+    statement: Statements,
+    pack_format: u32,
+) -> (String, Option<Scope>) {
     match statement {
         Statements::Execute(steps) => {
             let mut built_str = String::new();
@@ -270,11 +368,74 @@ pub fn compile_into_mcstatement(statement: Statements) -> (String, Option<Scope>
             }
             return (built_str, None);
         }
-        Statements::Raw(raw) => {
+        Statements::Raw(raw) | Statements::Query(raw) => {
             return (raw, None);
         }
+        Statements::Item(prefix, item, syntax, suffix) => (
+            format!("{}{}{}", prefix, item.render(&syntax, pack_format), suffix),
+            None,
+        ),
+        Statements::Text(prefix, text, suffix) => (
+            format!("{}{}{}", prefix, text.render(pack_format), suffix),
+            None,
+        ),
         Statements::Function(name, function) => {
             return (format!("function {}", &name), Some(function));
         }
+        Statements::FunctionAs(selector, name, function) => (
+            format!("execute as {} run function {}", selector, &name),
+            Some(function),
+        ),
+        Statements::Helper(name, _, arguments) => {
+            (format!("function {} with {}", name, arguments), None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile::objects::match_objects;
+
+    fn step(subcommand: &str, argument: Objects) -> Option<String> {
+        execute_step_from(subcommand, vec![match_objects(argument)]).map(execute_step_str)
+    }
+
+    #[test]
+    fn renders_execute_subcommands() {
+        let entity = || Objects::Entity("@a".to_string());
+        assert_eq!(step("as", entity()).unwrap(), "as @a");
+        assert_eq!(step("at", entity()).unwrap(), "at @a");
+        assert_eq!(step("positioned", entity()).unwrap(), "positioned as @a");
+        assert_eq!(step("facing", entity()).unwrap(), "facing entity @a eyes");
+        assert_eq!(step("rotated", entity()).unwrap(), "rotated as @a");
+        assert_eq!(
+            step("anchored", Objects::String("eyes".to_string())).unwrap(),
+            "anchored eyes"
+        );
+    }
+
+    #[test]
+    fn renders_positions_and_dimensions() {
+        let position = Objects::BlockPos(
+            Coordinate::Relative(0.0),
+            Coordinate::Relative(1.0),
+            Coordinate::Local(0.5),
+        );
+        assert_eq!(
+            step("positioned", position).unwrap(),
+            "positioned ~ ~1 ^0.5"
+        );
+        let nether = Objects::String("the_nether".to_string());
+        assert_eq!(step("in", nether).unwrap(), "in minecraft:the_nether");
+        let custom = Objects::String("mypack:void".to_string());
+        assert_eq!(step("in", custom).unwrap(), "in mypack:void");
+    }
+
+    #[test]
+    fn rejects_mismatched_arguments() {
+        assert!(step("as", Objects::Number(1)).is_none());
+        assert!(step("anchored", Objects::String("head".to_string())).is_none());
+        assert!(step("teleport", Objects::Entity("@s".to_string())).is_none());
     }
 }
@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    ast::{constructor::AST, operations::ASTOperation},
+    errors::{
+        associate::CodeAssociate,
+        error::CompileErrors,
+        sources::{module_id, record_import, source},
+    },
+    lexer::lexer::Lexer,
+};
+
+/// The modules a program is made of and the imports between them, read before anything is
+/// compiled.
+pub struct ModuleGraph {
+    /// The modules each module imports, in the order they are first imported.
+    pub imports: BTreeMap<String, Vec<String>>,
+    /// Every module after the modules it imports, which is the order they are compiled in.
+    pub order: Vec<String>,
+}
+
+impl ModuleGraph {
+    /// Reads the modules reachable from `root`, stopping at the first missing module or
+    /// circular import.
+    pub fn build(root: &str) -> Result<ModuleGraph, CompileErrors> {
+        let mut graph = ModuleGraph {
+            imports: BTreeMap::new(),
+            order: vec![],
+        };
+        graph.visit(root, &mut vec![])?;
+        Ok(graph)
+    }
+
+    fn visit(&mut self, module: &str, chain: &mut Vec<String>) -> Result<(), CompileErrors> {
+        chain.push(module.to_string());
+        let file = module_id(module).unwrap();
+        let mut lexer = Lexer::new(source(file).unwrap().code, file).quiet();
+        lexer.tokenizer();
+        let mut ast = AST::new(lexer.flush().to_vec());
+        ast.generate();
+
+        let mut imports = vec![];
+        for operation in ast.flush() {
+            imports_of(operation, &mut imports);
+        }
+        let mut modules = vec![];
+        for (path, _) in &imports {
+            let path = resolve(path, module);
+            if !modules.contains(&path) {
+                modules.push(path);
+            }
+        }
+        self.imports.insert(module.to_string(), modules);
+
+        for (path, associate) in imports {
+            let path = resolve(&path, module);
+            if chain.contains(&path) {
+                // the whole chain from the root module, ending where the cycle closes
+                return Err(CompileErrors::ImportError(
+                    format!("Circular import {} -> {}", chain.join(" -> "), path),
+                    associate,
+                ));
+            }
+            if self.imports.contains_key(&path) {
+                continue;
+            }
+            let Some(imported) = module_id(&path) else {
+                return Err(CompileErrors::ImportError(
+                    format!("Module {} does not exist", path),
+                    associate,
+                ));
+            };
+            record_import(imported, &associate);
+            self.visit(&path, chain)?;
+        }
+        chain.pop();
+        self.order.push(module.to_string());
+        Ok(())
+    }
+
+    /// The graph in the DOT language, with an edge from each module to the modules it imports.
+    pub fn dot(&self) -> String {
+        let mut lines = vec!["digraph modules {".to_string()];
+        for (module, imports) in &self.imports {
+            lines.push(format!("    \"{}\";", module));
+            for import in imports {
+                lines.push(format!("    \"{}\" -> \"{}\";", module, import));
+            }
+        }
+        lines.push("}".to_string());
+        lines.join("\n")
+    }
+}
+
+/// The module an import refers to. The modules of a dependency import each other without the
+/// `<dependency>:` prefix.
+pub fn resolve(path: &str, from: &str) -> String {
+    match from.split_once(':') {
+        Some((dependency, _)) if !path.contains(':') => format!("{}:{}", dependency, path),
+        _ => path.to_string(),
+    }
+}
+
+/// The namespace and name of the function a module compiles to. Modules in directories such
+/// as `utils/math` compile to `utils.math`, and the modules of a dependency such as
+/// `math:trig` are in the namespace of the dependency.
+pub fn module_function(module: &str, namespace: &str) -> (String, String) {
+    let (namespace, module) = module.split_once(':').unwrap_or((namespace, module));
+    (namespace.to_string(), module.replace('/', "."))
+}
+
+/// Collects the modules an operation and the operations inside of it import or re-export from.
+fn imports_of(operation: &ASTOperation, imports: &mut Vec<(String, CodeAssociate)>) {
+    match operation {
+        ASTOperation::Import(path, _, associate) | ASTOperation::ReExport(path, _, associate) => {
+            imports.push((path.clone(), associate.clone()));
+        }
+        operation => {
+            for child in operation.children() {
+                imports_of(child, imports);
+            }
+        }
+    }
+}
@@ -0,0 +1,381 @@
+//! The names of the objectives, tags and fake players the compiled code keeps its values in.
+//! They are prefixed with the namespace, so modules and datapacks declaring the same names do
+//! not share their scores.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::ast::operations::ASTOperation;
+
+/// The longest objective name before Minecraft 1.18.
+pub const SHORT_OBJECTIVE_LIMIT: usize = 16;
+
+/// The longest fake player name before Minecraft 1.18.
+pub const SHORT_HOLDER_LIMIT: usize = 40;
+
+/// How the values of variables are laid out in scores, set with `allocation` in `mclang.toml`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Allocation {
+    /// An objective per variable, held by the `$<namespace>` fake player.
+    #[default]
+    Objectives,
+    /// Fake players on a single `<namespace>.vars` objective: `$<scope>.<name>` for variables,
+    /// and `#tmp<n>` for function arguments, loop items and the values of expressions, which
+    /// are reused once the function no longer reads them.
+    Registers,
+}
+
+impl Allocation {
+    pub fn parse(str: &str) -> Option<Allocation> {
+        match str {
+            "objectives" => Some(Allocation::Objectives),
+            "registers" => Some(Allocation::Registers),
+            _ => None,
+        }
+    }
+}
+
+/// Where the value of a variable is kept.
+#[derive(Clone, Debug)]
+struct Score {
+    holder: String,
+    objective: String,
+}
+
+/// Hands out a name for each value the source declares, unique across every module.
+#[derive(Default)]
+pub struct NameAllocator {
+    pub allocation: Allocation,
+    /// The name given to each declaration, by `<namespace> <owner> <name>`.
+    allocated: HashMap<String, String>,
+    /// Every name handed out and the declaration it was given to.
+    names: BTreeMap<String, String>,
+    /// The fake players handed out on shared objectives.
+    registers: HashSet<String>,
+    /// The score of each variable, by the name its scoreboard carries.
+    scores: HashMap<String, Score>,
+}
+
+impl NameAllocator {
+    /// The name of `name` declared in `owner`, a scope or struct, such as `game.code.health`.
+    /// Asking again for the same declaration gives the same name. With `short` names, ones
+    /// longer than an objective may be before 1.18 end in a hash of the declaration instead.
+    pub fn allocate(&mut self, namespace: &str, owner: &str, name: &str, short: bool) -> String {
+        let key = format!("{} {} {}", namespace, owner, name);
+        if let Some(allocated) = self.allocated.get(&key) {
+            return allocated.clone();
+        }
+        let limit = short.then_some(SHORT_OBJECTIVE_LIMIT);
+        let full = format!("{}.{}.{}", namespace, owner, name);
+        let allocated = unique(&full, &key, limit, |name| self.names.contains_key(name));
+        self.allocated.insert(key, allocated.clone());
+        self.names.insert(
+            allocated.clone(),
+            format!("{}:{} {}", namespace, owner, name),
+        );
+        allocated
+    }
+
+    /// The fake player holding the value of a variable, if it was declared.
+    pub fn holder(&self, variable: &str) -> Option<String> {
+        self.scores.get(variable).map(|score| score.holder.clone())
+    }
+
+    /// The objective holding the value of a variable, if it was declared.
+    pub fn objective(&self, variable: &str) -> Option<String> {
+        self.scores
+            .get(variable)
+            .map(|score| score.objective.clone())
+    }
+
+    /// Whether a variable shares its objective with other variables. The objective is then
+    /// created when the datapack loads rather than where the variable is declared.
+    pub fn shared(&self, variable: &str) -> bool {
+        self.objective(variable)
+            .is_some_and(|objective| objective != variable)
+    }
+
+    /// The name of the scoreboard of a variable declared in `owner`, whose score is found with
+    /// [`NameAllocator::holder`] and [`NameAllocator::objective`].
+    pub fn variable(&mut self, namespace: &str, owner: &str, name: &str, short: bool) -> String {
+        let variable = self.allocate(namespace, owner, name, short);
+        if self.scores.contains_key(&variable) {
+            return variable;
+        }
+        let score = match self.allocation {
+            Allocation::Objectives => Score {
+                holder: format!("${}", namespace),
+                objective: variable.clone(),
+            },
+            Allocation::Registers => {
+                let limit = short.then_some(SHORT_HOLDER_LIMIT);
+                let full = format!("${}.{}", owner, name);
+                let holder = unique(&full, &variable, limit, |holder| {
+                    self.registers.contains(holder)
+                });
+                self.registers.insert(holder.clone());
+                Score {
+                    holder,
+                    objective: shared_objective(namespace, short),
+                }
+            }
+        };
+        self.scores.insert(variable.clone(), score);
+        variable
+    }
+
+    /// Like [`NameAllocator::variable`], for a value that only lives while its function runs.
+    /// Registers hold it in the `slot`th temporary, handed out by [`Temporaries`].
+    pub fn temporary(
+        &mut self,
+        namespace: &str,
+        owner: &str,
+        name: &str,
+        slot: usize,
+        short: bool,
+    ) -> String {
+        if self.allocation == Allocation::Objectives {
+            return self.variable(namespace, owner, name, short);
+        }
+        let variable = self.allocate(namespace, owner, name, short);
+        let score = Score {
+            holder: format!("#tmp{}", slot),
+            objective: shared_objective(namespace, short),
+        };
+        self.scores.insert(variable.clone(), score);
+        variable
+    }
+
+    /// Each name and the declaration it belongs to, one per line, to find the values of the
+    /// source in the game while debugging. Registers are listed with their objective.
+    pub fn mapping(&self) -> String {
+        self.names
+            .iter()
+            .map(
+                |(allocated, declaration)| match self.scores.get(allocated) {
+                    Some(score) if self.shared(allocated) => {
+                        format!("{} {} {}\n", score.objective, score.holder, declaration)
+                    }
+                    _ => format!("{} {}\n", allocated, declaration),
+                },
+            )
+            .collect()
+    }
+}
+
+/// What a temporary holds.
+#[derive(Clone, Debug, PartialEq)]
+enum Slot {
+    Free,
+    /// A function argument or loop item, read by its name.
+    Variable(String),
+    /// The value of an expression, only used by the statement computing it.
+    Expression,
+}
+
+/// The `#tmp` registers of a function. The ones below `base` belong to the functions still
+/// running below it. The function's own ones are freed once no later statement reads them, so
+/// the next temporary it needs reuses them.
+#[derive(Clone, Debug, Default)]
+pub struct Temporaries {
+    base: usize,
+    slots: Vec<Slot>,
+}
+
+impl Temporaries {
+    /// The temporaries of a function called at this point, above the ones in use.
+    pub fn above(&self) -> Temporaries {
+        let used = self
+            .slots
+            .iter()
+            .rposition(|slot| *slot != Slot::Free)
+            .map_or(0, |last| last + 1);
+        Temporaries {
+            base: self.base + used,
+            slots: vec![],
+        }
+    }
+
+    /// Takes the first free temporary for the argument or loop item `name`.
+    pub fn variable(&mut self, name: &str) -> usize {
+        self.take(Slot::Variable(name.to_string()))
+    }
+
+    /// Takes the first free temporary for the value of an expression.
+    pub fn expression(&mut self) -> usize {
+        self.take(Slot::Expression)
+    }
+
+    /// Frees a temporary that is no longer used.
+    pub fn free(&mut self, slot: usize) {
+        self.slots[slot - self.base] = Slot::Free;
+    }
+
+    fn take(&mut self, slot: Slot) -> usize {
+        match self.slots.iter().position(|slot| *slot == Slot::Free) {
+            Some(index) => {
+                self.slots[index] = slot;
+                self.base + index
+            }
+            None => {
+                self.slots.push(slot);
+                self.base + self.slots.len() - 1
+            }
+        }
+    }
+
+    /// Frees the values of expressions, and the variables none of the `rest` of the statements
+    /// of the function read.
+    pub fn release(&mut self, rest: &[ASTOperation]) {
+        for slot in &mut self.slots {
+            let live = match slot {
+                Slot::Variable(name) => rest.iter().any(|statement| statement.reads(name)),
+                _ => false,
+            };
+            if !live {
+                *slot = Slot::Free;
+            }
+        }
+    }
+}
+
+/// The objective the registers of a namespace share.
+fn shared_objective(namespace: &str, short: bool) -> String {
+    let limit = short.then_some(SHORT_OBJECTIVE_LIMIT);
+    unique(&format!("{}.vars", namespace), namespace, limit, |_| false)
+}
+
+/// `full`, or when it is longer than `limit` or already `taken`, its start followed by a hash
+/// of `key`.
+fn unique(full: &str, key: &str, limit: Option<usize>, taken: impl Fn(&str) -> bool) -> String {
+    let mut name = full.to_string();
+    let mut attempt = 0;
+    while limit.is_some_and(|limit| name.len() > limit) || taken(&name) {
+        let limit = limit.unwrap_or(full.len()).max(8);
+        let hash = format!("-{:06x}", fnv(&format!("{} {}", key, attempt)) & 0xffffff);
+        let mut cut = full.len().min(limit - 7);
+        while !full.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        name = format!("{}{}", &full[..cut], hash);
+        attempt += 1;
+    }
+    name
+}
+
+/// The 32-bit FNV-1a hash, which stays the same between compilations.
+fn fnv(text: &str) -> u32 {
+    text.bytes().fold(0x811c9dc5, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::lexer::empty_associate;
+
+    #[test]
+    fn gives_each_declaration_one_name() {
+        let mut names = NameAllocator::default();
+        let health = names.allocate("game", "code", "health", false);
+        assert_eq!(health, "game.code.health");
+        assert_eq!(names.allocate("game", "code", "health", false), health);
+        assert_ne!(names.allocate("game", "code.0", "health", false), health);
+    }
+
+    #[test]
+    fn shortens_long_names() {
+        let mut names = NameAllocator::default();
+        let short = names.allocate("game", "code", "remaining_health", true);
+        assert!(short.len() <= SHORT_OBJECTIVE_LIMIT);
+        assert!(short.starts_with("game.code"));
+        let other = names.allocate("game", "code", "remaining_hearts", true);
+        assert_ne!(short, other);
+    }
+
+    #[test]
+    fn cuts_names_between_characters() {
+        let mut names = NameAllocator::default();
+        // the ninth byte is inside the second é
+        let short = names.allocate("café", "cdé", "points", true);
+        assert!(short.len() <= SHORT_OBJECTIVE_LIMIT);
+        assert!(short.starts_with("café.cd-"));
+    }
+
+    #[test]
+    fn keeps_variables_in_their_own_objective() {
+        let mut names = NameAllocator::default();
+        let variable = names.variable("game", "code", "health", false);
+        assert_eq!(names.holder(&variable).unwrap(), "$game");
+        assert_eq!(names.objective(&variable).unwrap(), variable);
+        assert!(!names.shared(&variable));
+    }
+
+    #[test]
+    fn keeps_registers_on_a_shared_objective() {
+        let mut names = NameAllocator {
+            allocation: Allocation::Registers,
+            ..Default::default()
+        };
+        let variable = names.variable("game", "code", "health", false);
+        assert_eq!(names.holder(&variable).unwrap(), "$code.health");
+        assert_eq!(names.objective(&variable).unwrap(), "game.vars");
+        assert!(names.shared(&variable));
+
+        let argument = names.temporary("game", "code.add", "amount", 2, false);
+        assert_eq!(names.holder(&argument).unwrap(), "#tmp2");
+        assert_eq!(
+            names.mapping(),
+            "game.vars #tmp2 game:code.add amount\ngame.vars $code.health game:code health\n"
+        );
+    }
+
+    #[test]
+    fn knows_no_score_for_undeclared_variables() {
+        let names = NameAllocator::default();
+        assert_eq!(names.holder("game.code.health"), None);
+        assert_eq!(names.objective("game.code.health"), None);
+        assert!(!names.shared("game.code.health"));
+    }
+
+    fn access(name: &str) -> ASTOperation {
+        ASTOperation::Access(name.to_string(), empty_associate())
+    }
+
+    #[test]
+    fn reuses_freed_temporaries() {
+        let mut temporaries = Temporaries::default();
+        assert_eq!(temporaries.variable("n"), 0);
+        let expression = temporaries.expression();
+        assert_eq!(expression, 1);
+        assert_eq!(temporaries.expression(), 2);
+        temporaries.free(expression);
+        assert_eq!(temporaries.expression(), 1);
+    }
+
+    #[test]
+    fn frees_temporaries_no_later_statement_reads() {
+        let mut temporaries = Temporaries::default();
+        temporaries.variable("n");
+        temporaries.variable("m");
+        temporaries.expression();
+        temporaries.release(&[access("m")]);
+        // `n` and the expression are dead, `m` is still read
+        assert_eq!(temporaries.expression(), 0);
+        assert_eq!(temporaries.variable("i"), 2);
+        assert_eq!(temporaries.expression(), 3);
+    }
+
+    #[test]
+    fn calls_use_temporaries_above_the_ones_in_use() {
+        let mut temporaries = Temporaries::default();
+        temporaries.variable("n");
+        let expression = temporaries.expression();
+        temporaries.expression();
+        temporaries.free(expression);
+        let mut called = temporaries.above();
+        assert_eq!(called.variable("n"), 3);
+        called.release(&[]);
+        assert_eq!(called.above().expression(), 3);
+    }
+}
@@ -0,0 +1,165 @@
+/// Syntactic validation of SNBT (stringified NBT) written in mclang sources, so that
+/// malformed block entity data, item tags and storage values are caught at compile time
+/// instead of by the game when the datapack loads.
+pub fn validate_snbt(snbt: &str) -> Result<(), String> {
+    let chars: Vec<char> = snbt.chars().collect();
+    let mut parser = SnbtParser { chars, index: 0 };
+    parser.skip_whitespace();
+    parser.value()?;
+    parser.skip_whitespace();
+    if parser.index != parser.chars.len() {
+        return Err(parser.error("Unexpected trailing characters"));
+    }
+    Ok(())
+}
+
+/// Validates that the given SNBT is a compound (`{...}`), as required by block entity data
+/// and `data merge`.
+pub fn validate_snbt_compound(snbt: &str) -> Result<(), String> {
+    if !snbt.trim_start().starts_with('{') {
+        return Err("Expected an NBT compound starting with {".to_string());
+    }
+    validate_snbt(snbt)
+}
+
+/// Quotes a string as an SNBT string literal using the given quote character.
+pub fn quote_snbt_string(value: &str, quote: char) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace(quote, &format!("\\{}", quote));
+    format!("{}{}{}", quote, escaped, quote)
+}
+
+struct SnbtParser {
+    chars: Vec<char>,
+    index: usize,
+}
+
+impl SnbtParser {
+    fn error(&self, message: &str) -> String {
+        format!("{} at character {} of NBT", message, self.index + 1)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.index += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.peek() != Some(expected) {
+            return Err(self.error(&format!("Expected {}", expected)));
+        }
+        self.index += 1;
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.compound(),
+            Some('[') => self.list(),
+            Some('"') | Some('\'') => self.quoted_string(),
+            Some(_) => {
+                if self.unquoted().is_empty() {
+                    return Err(self.error("Expected a value"));
+                }
+                Ok(())
+            }
+            None => Err(self.error("Expected a value")),
+        }
+    }
+
+    fn compound(&mut self) -> Result<(), String> {
+        self.expect('{')?;
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.index += 1;
+            return Ok(());
+        }
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('"') | Some('\'') => self.quoted_string()?,
+                _ => {
+                    if self.unquoted().is_empty() {
+                        return Err(self.error("Expected a compound key"));
+                    }
+                }
+            }
+            self.expect(':')?;
+            self.value()?;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.index += 1,
+                Some('}') => {
+                    self.index += 1;
+                    return Ok(());
+                }
+                _ => return Err(self.error("Expected , or }")),
+            }
+        }
+    }
+
+    fn list(&mut self) -> Result<(), String> {
+        self.expect('[')?;
+        // typed arrays: [B;1b,2b], [I;1,2], [L;1l]
+        if matches!(self.peek(), Some('B') | Some('I') | Some('L'))
+            && self.chars.get(self.index + 1) == Some(&';')
+        {
+            self.index += 2;
+        }
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.index += 1;
+            return Ok(());
+        }
+        loop {
+            self.value()?;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.index += 1,
+                Some(']') => {
+                    self.index += 1;
+                    return Ok(());
+                }
+                _ => return Err(self.error("Expected , or ]")),
+            }
+        }
+    }
+
+    fn quoted_string(&mut self) -> Result<(), String> {
+        let quote = self.peek().unwrap();
+        self.index += 1;
+        while let Some(char) = self.peek() {
+            self.index += 1;
+            if char == '\\' {
+                if self.peek().is_none() {
+                    break;
+                }
+                self.index += 1;
+            } else if char == quote {
+                return Ok(());
+            }
+        }
+        Err(self.error("Unterminated string"))
+    }
+
+    fn unquoted(&mut self) -> String {
+        let mut value = String::new();
+        while let Some(char) = self.peek() {
+            if char.is_ascii_alphanumeric() || "_-.+".contains(char) {
+                value.push(char);
+                self.index += 1;
+            } else {
+                break;
+            }
+        }
+        value
+    }
+}
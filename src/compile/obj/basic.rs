@@ -1,6 +1,8 @@
-use std::{any::Any, collections::HashMap, mem::discriminant, rc::Rc};
+use std::{any::Any, collections::HashMap, mem::discriminant, process::exit, rc::Rc};
 
-use crate::compile::objects::{match_objects, mk_function_map, mk_variable, Object, Objects};
+use crate::compile::objects::{
+    match_objects, mk_function_map, mk_variable, Object, ObjectFunction, Objects,
+};
 
 use super::std::VariableObject;
 
@@ -21,6 +23,11 @@ pub struct NumberObject {
     pub value: i64,
 }
 
+#[derive(Clone, Debug)]
+pub struct DecimalObject {
+    pub value: f64,
+}
+
 #[derive(Clone, Debug)]
 pub struct SetObject {
     pub values: Vec<Rc<dyn Object>>,
@@ -42,12 +49,29 @@ impl Object for NumberObject {
         self
     }
 
-    fn get_functions(
-        &self,
-    ) -> HashMap<
-        String,
-        Box<dyn Fn(Vec<Rc<dyn Object>>, Option<Rc<VariableObject>>) -> Rc<dyn Object>>,
-    > {
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
+        HashMap::new()
+    }
+}
+
+impl Object for DecimalObject {
+    fn get_type(&self) -> Objects {
+        Objects::Decimal(self.value)
+    }
+    fn get_variables(&self) -> HashMap<String, Rc<VariableObject>> {
+        let mut map = HashMap::new();
+        map.insert(
+            "value".to_string(),
+            mk_variable(self.get_type(), Objects::Unknown),
+        );
+        map
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
         HashMap::new()
     }
 }
@@ -73,7 +97,6 @@ impl Object for SetObject {
                         format!("_{}", index),
                         mk_variable(value.get_type(), Objects::Unknown),
                     );
-                    println!("Made {}", index);
                     index += 1;
                 }
             } else {
@@ -82,7 +105,6 @@ impl Object for SetObject {
                         format!("_{}", index),
                         mk_variable(value.get_type(), Objects::Unknown),
                     );
-                    println!("Made {}", index);
                     index += 1;
                 }
             }
@@ -93,20 +115,21 @@ impl Object for SetObject {
         self
     }
 
-    fn get_functions(
-        &self,
-    ) -> HashMap<
-        String,
-        Box<dyn Fn(Vec<Rc<dyn Object>>, Option<Rc<VariableObject>>) -> Rc<dyn Object>>,
-    > {
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
         let mut map = mk_function_map();
         map.insert(
             "get".to_string(),
             Box::new(|params, variable| {
-                let index = params[0].get_type().clone();
-                let index = match index {
+                if params.len() != 1 {
+                    eprintln!("Incorrect number of arguments for function get");
+                    exit(1);
+                }
+                let index = match params[0].get_type() {
                     Objects::Number(num) => num,
-                    _ => panic!("Index must be a number"),
+                    _ => {
+                        eprintln!("The index of an array must be a number, use a storage list to index at runtime");
+                        exit(1);
+                    }
                 };
                 let value = variable.unwrap().value.clone();
                 let value = match *value {
@@ -114,13 +137,23 @@ impl Object for SetObject {
                     _ => panic!("Value must be an array"),
                 };
 
-                let value = value.get(0).unwrap().as_any().downcast_ref::<SetObject>();
-                if value.is_none() {
-                    panic!("Value must be an array (must have at least 2 values)");
+                // an array stored in a variable is wrapped in a set of its own
+                let values = match value.as_slice() {
+                    [inner] => match inner.as_any().downcast_ref::<SetObject>() {
+                        Some(inner) => inner.values.clone(),
+                        None => value.clone(),
+                    },
+                    _ => value.clone(),
+                };
+                if index < 0 || index as usize >= values.len() {
+                    eprintln!(
+                        "Index {} is out of bounds for an array of length {}",
+                        index,
+                        values.len()
+                    );
+                    exit(1);
                 }
-                let value = value.unwrap();
-                println!("{:?}", value.values[index as usize]);
-                value.values[index as usize].clone()
+                values[index as usize].clone()
             }),
         );
         map
@@ -143,12 +176,7 @@ impl Object for StringObject {
         self
     }
 
-    fn get_functions(
-        &self,
-    ) -> HashMap<
-        String,
-        Box<dyn Fn(Vec<Rc<dyn Object>>, Option<Rc<VariableObject>>) -> Rc<dyn Object>>,
-    > {
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
         let map = mk_function_map();
         map
     }
@@ -170,12 +198,7 @@ impl Object for BooleanObject {
     fn as_any(&self) -> &dyn Any {
         self
     }
-    fn get_functions(
-        &self,
-    ) -> HashMap<
-        String,
-        Box<dyn Fn(Vec<Rc<dyn Object>>, Option<Rc<VariableObject>>) -> Rc<dyn Object>>,
-    > {
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
         HashMap::new()
     }
 }
@@ -190,12 +213,7 @@ impl Object for NullObject {
         self
     }
 
-    fn get_functions(
-        &self,
-    ) -> HashMap<
-        String,
-        Box<dyn Fn(Vec<Rc<dyn Object>>, Option<Rc<VariableObject>>) -> Rc<dyn Object>>,
-    > {
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
         HashMap::new()
     }
 }
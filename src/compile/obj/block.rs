@@ -0,0 +1,109 @@
+use std::fmt::Display;
+
+use crate::compile::nbt::validate_snbt_compound;
+
+/// A block argument such as `minecraft:chest[facing=north]{Lock:"key"}`, or a block tag
+/// (`#minecraft:logs`) where predicates allow one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockState {
+    pub id: String,
+    pub tag: bool,
+    pub states: Vec<(String, String)>,
+    pub nbt: Option<String>,
+}
+
+pub fn is_resource_location(str: &str) -> bool {
+    let (namespace, path) = str.split_once(':').unwrap_or(("minecraft", str));
+    !namespace.is_empty()
+        && !path.is_empty()
+        && namespace
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "_-.".contains(c))
+        && path
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "_-./".contains(c))
+}
+
+/// Parses and validates a block argument. The namespace defaults to `minecraft`.
+pub fn parse_block(block: &str, allow_tags: bool) -> Result<BlockState, String> {
+    let block = block.trim();
+    let (tag, block) = match block.strip_prefix('#') {
+        Some(block) if allow_tags => (true, block),
+        Some(_) => return Err("Block tags are not allowed here".to_string()),
+        None => (false, block),
+    };
+
+    let id_end = block.find(['[', '{']).unwrap_or(block.len());
+    let id = &block[..id_end];
+    if !is_resource_location(id) {
+        return Err(format!("Invalid block id {}", id));
+    }
+    let id = if id.contains(':') {
+        id.to_string()
+    } else {
+        format!("minecraft:{}", id)
+    };
+
+    let mut rest = &block[id_end..];
+    let mut states = vec![];
+    if let Some(state_str) = rest.strip_prefix('[') {
+        let state_end = state_str
+            .find(']')
+            .ok_or("Block states opened with [ but not closed with ]")?;
+        for state in state_str[..state_end].split(',') {
+            if state.trim().is_empty() {
+                continue;
+            }
+            let (key, value) = state
+                .split_once('=')
+                .ok_or(format!("Block state {} is missing a value", state.trim()))?;
+            let (key, value) = (key.trim(), value.trim());
+            let valid = |str: &str| {
+                !str.is_empty()
+                    && str
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+            };
+            if !valid(key) || !valid(value) {
+                return Err(format!("Invalid block state {}={}", key, value));
+            }
+            states.push((key.to_string(), value.to_string()));
+        }
+        rest = &state_str[state_end + 1..];
+    }
+
+    let nbt = if rest.trim().is_empty() {
+        None
+    } else {
+        validate_snbt_compound(rest)?;
+        Some(rest.trim().to_string())
+    };
+
+    Ok(BlockState {
+        id,
+        tag,
+        states,
+        nbt,
+    })
+}
+
+impl Display for BlockState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.tag {
+            write!(f, "#")?;
+        }
+        write!(f, "{}", self.id)?;
+        if !self.states.is_empty() {
+            let states = self
+                .states
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect::<Vec<String>>();
+            write!(f, "[{}]", states.join(","))?;
+        }
+        if let Some(nbt) = &self.nbt {
+            write!(f, "{}", nbt)?;
+        }
+        Ok(())
+    }
+}
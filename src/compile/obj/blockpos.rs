@@ -1,25 +1,198 @@
-use std::{any::Any, collections::HashMap, process::exit, rc::Rc};
+use std::{any::Any, collections::HashMap, fmt::Display, process::exit, rc::Rc};
 
-use crate::compile::objects::{match_objects, mk_variable, Object, Objects};
+use crate::compile::{
+    macros::{macro_key, macro_placeholder},
+    objects::{match_objects, mk_variable, Object, ObjectFunction, Objects},
+};
 
-use super::{basic::NumberObject, std::VariableObject};
+use super::{
+    basic::{DecimalObject, NumberObject, StringObject},
+    std::VariableObject,
+};
+
+/// A single component of a position: absolute (`5`), relative (`~5`) or local (`^5`).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Coordinate {
+    Absolute(f64),
+    Relative(f64),
+    Local(f64),
+    /// A runtime value, written as the placeholder of a macro argument.
+    Macro(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct CoordinateObject {
+    pub value: Coordinate,
+}
 
 #[derive(Clone, Debug)]
 pub struct BlockPosObject {
-    pub x: i64,
-    pub y: i64,
-    pub z: i64
+    pub x: Coordinate,
+    pub y: Coordinate,
+    pub z: Coordinate,
+}
+
+pub fn format_decimal(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value as i64)
+    } else {
+        format!("{}", value)
+    }
+}
+
+impl Coordinate {
+    pub fn parse(str: &str) -> Option<Coordinate> {
+        let str = str.trim();
+        let (constructor, value): (fn(f64) -> Coordinate, &str) =
+            if let Some(value) = str.strip_prefix('~') {
+                (Coordinate::Relative, value)
+            } else if let Some(value) = str.strip_prefix('^') {
+                (Coordinate::Local, value)
+            } else {
+                (Coordinate::Absolute, str)
+            };
+        if value.is_empty() {
+            if str.is_empty() {
+                return None;
+            }
+            return Some(constructor(0.0));
+        }
+        value
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .map(constructor)
+    }
+
+    pub fn from_object(object: &Objects) -> Option<Coordinate> {
+        match object {
+            Objects::Number(num) => Some(Coordinate::Absolute(*num as f64)),
+            Objects::Decimal(num) => Some(Coordinate::Absolute(*num)),
+            Objects::Coordinate(coordinate) => Some(coordinate.clone()),
+            Objects::String(str) => Coordinate::parse(str),
+            _ => macro_key(object).map(Coordinate::Macro),
+        }
+    }
+
+    pub fn offset(&self, by: f64) -> Coordinate {
+        match self {
+            Coordinate::Absolute(value) => Coordinate::Absolute(value + by),
+            Coordinate::Relative(value) => Coordinate::Relative(value + by),
+            Coordinate::Local(value) => Coordinate::Local(value + by),
+            Coordinate::Macro(_) => {
+                eprintln!("A position given by a runtime value cannot be offset.");
+                exit(1);
+            }
+        }
+    }
+}
+
+impl Display for Coordinate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Coordinate::Absolute(value) => write!(f, "{}", format_decimal(*value)),
+            Coordinate::Relative(value) if *value == 0.0 => write!(f, "~"),
+            Coordinate::Relative(value) => write!(f, "~{}", format_decimal(*value)),
+            Coordinate::Local(value) if *value == 0.0 => write!(f, "^"),
+            Coordinate::Local(value) => write!(f, "^{}", format_decimal(*value)),
+            Coordinate::Macro(key) => write!(f, "{}", macro_placeholder(key)),
+        }
+    }
+}
+
+impl Display for BlockPosObject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.x, self.y, self.z)
+    }
+}
+
+impl BlockPosObject {
+    /// Builds a position from three coordinate-like objects. Local coordinates cannot be
+    /// mixed with absolute or relative ones.
+    pub fn from_objects(objects: &[Objects]) -> Result<BlockPosObject, &'static str> {
+        if objects.len() != 3 {
+            return Err("Incorrect number of params.");
+        }
+        let mut coordinates = vec![];
+        for object in objects {
+            match Coordinate::from_object(object) {
+                Some(coordinate) => coordinates.push(coordinate),
+                None => return Err("Incorrect type of params."),
+            }
+        }
+        let locals = coordinates
+            .iter()
+            .filter(|coordinate| matches!(coordinate, Coordinate::Local(_)))
+            .count();
+        if locals != 0 && locals != 3 {
+            return Err("Local coordinates (^) cannot be mixed with other coordinates.");
+        }
+        Ok(BlockPosObject {
+            x: coordinates[0].clone(),
+            y: coordinates[1].clone(),
+            z: coordinates[2].clone(),
+        })
+    }
+
+    /// Reads a position from a BlockPos or from coordinates written inline, such as
+    /// `(~ ~1 ~)`.
+    pub fn from_object(object: &Objects) -> Option<BlockPosObject> {
+        match object {
+            Objects::BlockPos(x, y, z) => Some(BlockPosObject {
+                x: x.clone(),
+                y: y.clone(),
+                z: z.clone(),
+            }),
+            Objects::Array(values) if values.len() == 1 => {
+                BlockPosObject::from_object(&values[0].get_type())
+            }
+            Objects::Array(values) => BlockPosObject::from_objects(
+                &values
+                    .iter()
+                    .map(|value| value.get_type())
+                    .collect::<Vec<Objects>>(),
+            )
+            .ok(),
+            _ => None,
+        }
+    }
+}
+
+impl Object for CoordinateObject {
+    fn get_type(&self) -> Objects {
+        Objects::Coordinate(self.value.clone())
+    }
+    fn get_variables(&self) -> HashMap<String, Rc<VariableObject>> {
+        HashMap::new()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
+        HashMap::new()
+    }
 }
 
 impl Object for BlockPosObject {
     fn get_type(&self) -> Objects {
-        Objects::BlockPos(self.x, self.y, self.z)
+        Objects::BlockPos(self.x.clone(), self.y.clone(), self.z.clone())
     }
     fn get_variables(&self) -> HashMap<String, Rc<VariableObject>> {
         let mut map = HashMap::new();
-        map.insert("x".to_string(), mk_variable(Objects::Number(self.x), Objects::Unknown));
-        map.insert("y".to_string(), mk_variable(Objects::Number(self.y), Objects::Unknown));
-        map.insert("z".to_string(), mk_variable(Objects::Number(self.z), Objects::Unknown));
+        map.insert(
+            "x".to_string(),
+            mk_variable(Objects::Coordinate(self.x.clone()), Objects::Unknown),
+        );
+        map.insert(
+            "y".to_string(),
+            mk_variable(Objects::Coordinate(self.y.clone()), Objects::Unknown),
+        );
+        map.insert(
+            "z".to_string(),
+            mk_variable(Objects::Coordinate(self.z.clone()), Objects::Unknown),
+        );
         return map;
     }
 
@@ -27,25 +200,97 @@ impl Object for BlockPosObject {
         self
     }
 
-    fn get_functions(&self) -> HashMap<String, Box<dyn Fn(Vec<Rc<dyn Object>>, Option<Rc<VariableObject>>) -> Rc<dyn Object>>> {
-        let mut map: HashMap<String, Box<dyn Fn(Vec<Rc<dyn Object>>, Option<Rc<VariableObject>>) -> Rc<dyn Object>>> = HashMap::new();
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
+        let mut map: HashMap<String, ObjectFunction> = HashMap::new();
 
-        map.insert("instantiate".to_string(), Box::new(|params, _| {
-            if params.len() != 3 {
-                eprintln!("Incorrect number of params.");
-                exit(1);
-            }
-            let x = params[0].as_any().downcast_ref::<NumberObject>();
-            let y = params[1].as_any().downcast_ref::<NumberObject>();
-            let z = params[2].as_any().downcast_ref::<NumberObject>();
+        map.insert(
+            "instantiate".to_string(),
+            Box::new(|params, _| {
+                // a single string such as "~ ~1 ~" is split into its components
+                let objects = if params.len() == 1
+                    && params[0].as_any().downcast_ref::<StringObject>().is_some()
+                {
+                    let value = &params[0]
+                        .as_any()
+                        .downcast_ref::<StringObject>()
+                        .unwrap()
+                        .value;
+                    value
+                        .split_whitespace()
+                        .map(|part| Objects::String(part.to_string()))
+                        .collect::<Vec<Objects>>()
+                } else {
+                    params
+                        .iter()
+                        .map(|param| param.get_type())
+                        .collect::<Vec<Objects>>()
+                };
 
-            if x.is_none() || y.is_none() || z.is_none() {
-                eprintln!("Incorrect type of params.");
-                exit(1);
-            }
+                match BlockPosObject::from_objects(&objects) {
+                    Ok(position) => Rc::new(position),
+                    Err(message) => {
+                        eprintln!("{}", message);
+                        exit(1);
+                    }
+                }
+            }),
+        );
+
+        map.insert(
+            "offset".to_string(),
+            Box::new(|params, variable| {
+                if params.len() != 3 {
+                    eprintln!("Incorrect number of params.");
+                    exit(1);
+                }
+                let mut offsets = vec![];
+                for param in params.iter() {
+                    if let Some(number) = param.as_any().downcast_ref::<NumberObject>() {
+                        offsets.push(number.value as f64);
+                    } else if let Some(number) = param.as_any().downcast_ref::<DecimalObject>() {
+                        offsets.push(number.value);
+                    } else {
+                        eprintln!("Incorrect type of params.");
+                        exit(1);
+                    }
+                }
 
-            return match_objects(Objects::BlockPos(x.unwrap().value, y.unwrap().value, z.unwrap().value));
-        }));
+                let own = match_objects(*variable.unwrap().value.clone());
+                let own = own.as_any().downcast_ref::<BlockPosObject>().unwrap();
+                match_objects(Objects::BlockPos(
+                    own.x.offset(offsets[0]),
+                    own.y.offset(offsets[1]),
+                    own.z.offset(offsets[2]),
+                ))
+            }),
+        );
         return map;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::compile::compiler::tests::compiled;
+
+    #[test]
+    fn writes_relative_local_and_decimal_coordinates() {
+        let outputs = compiled(
+            "let p = new BlockPos(~, ~1, ~);\nworld.setblock(p.offset(0, 1, 0), \"stone\");\nworld.setblock(new BlockPos(^, ^, ^1), \"dirt\");\npositioned (new BlockPos(1.5, 2, -3)) {\n    /say x\n}\n",
+        );
+        assert_eq!(
+            outputs["code"],
+            "setblock ~ ~2 ~ minecraft:stone\nsetblock ^ ^ ^1 minecraft:dirt\nexecute positioned 1.5 2 -3 run function test:code.0"
+        );
+    }
+
+    #[test]
+    fn passes_changed_variables_through_macros() {
+        let outputs =
+            compiled("let a = 5;\na = a + 1;\nnew Entity(@s).tp(new BlockPos(a, 0, 0));\n");
+        assert_eq!(
+            outputs["code"],
+            "scoreboard objectives add test.code.a dummy\nscoreboard players set $test test.code.a 5\nscoreboard players add $test test.code.a 1\nexecute store result storage test:macro args.var__test_2ecode_2ea int 1 run scoreboard players get $test test.code.a\nfunction test:code.macro0 with storage test:macro args"
+        );
+        assert_eq!(outputs["code.macro0"], "$tp @s $(var__test_2ecode_2ea) 0 0");
+    }
+}
@@ -0,0 +1,121 @@
+use std::{any::Any, collections::HashMap, process::exit, rc::Rc};
+
+use crate::compile::objects::{
+    match_objects, mk_function_map, mk_variable, Object, ObjectFunction, Objects,
+};
+
+use super::{basic::StringObject, std::VariableObject};
+
+pub const OVERWORLD: &str = "minecraft:overworld";
+pub const THE_NETHER: &str = "minecraft:the_nether";
+pub const THE_END: &str = "minecraft:the_end";
+
+#[derive(Clone, Debug)]
+pub struct DimensionObject {
+    pub name: String,
+}
+
+impl DimensionObject {
+    /// Resolves a dimension name into its namespaced id. Vanilla dimensions may be written
+    /// without a namespace, custom dimensions must use `namespace:path`.
+    pub fn resolve(name: &str) -> Option<String> {
+        match name {
+            "overworld" | "minecraft:overworld" => return Some(OVERWORLD.to_string()),
+            "nether" | "the_nether" | "minecraft:the_nether" => {
+                return Some(THE_NETHER.to_string())
+            }
+            "end" | "the_end" | "minecraft:the_end" => return Some(THE_END.to_string()),
+            _ => {}
+        }
+
+        let (namespace, path) = name.split_once(':')?;
+        let valid_namespace = !namespace.is_empty()
+            && namespace
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "_-.".contains(c));
+        let valid_path = !path.is_empty()
+            && path
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "_-./".contains(c));
+        if valid_namespace && valid_path {
+            Some(name.to_string())
+        } else {
+            None
+        }
+    }
+}
+
+impl Object for DimensionObject {
+    fn get_type(&self) -> Objects {
+        Objects::Dimension(self.name.clone())
+    }
+    fn get_variables(&self) -> HashMap<String, Rc<VariableObject>> {
+        let (namespace, path) = self
+            .name
+            .split_once(':')
+            .unwrap_or(("minecraft", &self.name));
+        let mut map = HashMap::new();
+        map.insert(
+            "id".to_string(),
+            mk_variable(Objects::String(self.name.clone()), Objects::Unknown),
+        );
+        map.insert(
+            "namespace".to_string(),
+            mk_variable(Objects::String(namespace.to_string()), Objects::Unknown),
+        );
+        map.insert(
+            "path".to_string(),
+            mk_variable(Objects::String(path.to_string()), Objects::Unknown),
+        );
+        map
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
+        let mut map = mk_function_map();
+        map.insert(
+            "instantiate".to_string(),
+            Box::new(|params, _| {
+                if params.len() != 1 {
+                    eprintln!("Incorrect number of params.");
+                    exit(1);
+                }
+                let name = params[0].as_any().downcast_ref::<StringObject>();
+                if name.is_none() {
+                    eprintln!("Incorrect type of params.");
+                    exit(1);
+                }
+                let name = &name.unwrap().value;
+                let dimension = DimensionObject::resolve(name);
+                if dimension.is_none() {
+                    eprintln!(
+                        "Unknown dimension {}. Custom dimensions must be written as namespace:path.",
+                        name
+                    );
+                    exit(1);
+                }
+                match_objects(Objects::Dimension(dimension.unwrap()))
+            }),
+        );
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compile::compiler::tests::compiled;
+
+    #[test]
+    fn runs_code_and_teleports_in_a_dimension() {
+        let outputs = compiled(
+            "let d = new Dimension(\"the_nether\");\nin (d) {\n    /say nether\n}\nlet e = new Entity(@s);\ne.tp(d, new BlockPos(~, ~2, ~));\n",
+        );
+        assert_eq!(
+            outputs["code"],
+            "execute in minecraft:the_nether run function test:code.0\nexecute in minecraft:the_nether run tp @s ~ ~2 ~"
+        );
+        assert_eq!(outputs["code.0"], "say nether");
+    }
+}
@@ -2,10 +2,16 @@ use std::{any::Any, collections::HashMap, process::exit, rc::Rc};
 
 use crate::compile::{
     mcstatements::{MinecraftStatementObject, Statements},
-    objects::{match_objects, Object, Objects},
+    objects::{match_objects, Object, ObjectFunction, Objects},
 };
 
-use super::{basic::StringObject, blockpos::BlockPosObject, std::VariableObject};
+use super::{
+    basic::{NumberObject, StringObject},
+    blockpos::BlockPosObject,
+    dimension::DimensionObject,
+    item::{count_suffix, is_slot, ItemObject, ItemSyntax},
+    std::VariableObject,
+};
 
 #[derive(Debug, Clone)]
 pub struct EntityObject {
@@ -23,20 +29,15 @@ impl Object for EntityObject {
     fn get_variables(&self) -> HashMap<String, Rc<VariableObject>> {
         HashMap::new()
     }
-    fn get_functions(
-        &self,
-    ) -> HashMap<
-        String,
-        Box<dyn Fn(Vec<Rc<dyn Object>>, Option<Rc<VariableObject>>) -> Rc<dyn Object>>,
-    > {
-        let mut map: HashMap<
-            String,
-            Box<dyn Fn(Vec<Rc<dyn Object>>, Option<Rc<VariableObject>>) -> Rc<dyn Object>>,
-        > = HashMap::new();
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
+        let mut map: HashMap<String, ObjectFunction> = HashMap::new();
 
         map.insert(
             "instantiate".to_string(),
             Box::new(|params, _| {
+                if let Some(entity) = params[0].as_any().downcast_ref::<EntityObject>() {
+                    return Rc::new(entity.clone());
+                }
                 let selector = params[0].as_any().downcast_ref::<StringObject>();
                 if selector.is_none() {
                     eprintln!("Incorrect Type");
@@ -86,24 +87,38 @@ impl Object for EntityObject {
         map.insert(
             "tp".to_string(),
             Box::new(|params, variable| {
-                if params.len() != 1 {
+                if params.len() != 1 && params.len() != 2 {
                     eprintln!("Incorrect number of arguments for function tp");
                     exit(1);
                 }
-                let params = params[0].as_any();
+                // tp(dimension, pos) teleports across dimensions with `execute in`
+                let prefix = if params.len() == 2 {
+                    let dimension = params[0].as_any().downcast_ref::<DimensionObject>();
+                    if dimension.is_none() {
+                        eprintln!("Incorrect argument type for function tp");
+                        exit(1);
+                    }
+                    format!("execute in {} run ", dimension.unwrap().name)
+                } else {
+                    String::new()
+                };
+                let params = params[params.len() - 1].as_any();
 
                 let own = match_objects(*variable.unwrap().value.clone());
                 let own = own.as_any().downcast_ref::<EntityObject>().unwrap();
 
                 if let Some(params) = params.downcast_ref::<EntityObject>() {
                     Rc::new(MinecraftStatementObject {
-                        value: Statements::Raw(format!("tp {} {}", own.selector, params.selector)),
+                        value: Statements::Raw(format!(
+                            "{}tp {} {}",
+                            prefix, own.selector, params.selector
+                        )),
                     })
                 } else if let Some(params) = params.downcast_ref::<BlockPosObject>() {
                     Rc::new(MinecraftStatementObject {
                         value: Statements::Raw(format!(
-                            "tp {} {} {} {}",
-                            own.selector, params.x, params.y, params.z
+                            "{}tp {} {} {} {}",
+                            prefix, own.selector, params.x, params.y, params.z
                         )),
                     })
                 } else {
@@ -113,6 +128,110 @@ impl Object for EntityObject {
             }),
         );
 
+        map.insert(
+            "give".to_string(),
+            Box::new(|params, variable| {
+                if params.len() != 1 {
+                    eprintln!("Incorrect number of arguments for function give");
+                    exit(1);
+                }
+                let item = params[0].as_any().downcast_ref::<ItemObject>();
+                if item.is_none() {
+                    eprintln!("Incorrect argument type for function give, expected Item");
+                    exit(1);
+                }
+                let item = item.unwrap();
+
+                let own = match_objects(*variable.unwrap().value.clone());
+                let own = own.as_any().downcast_ref::<EntityObject>().unwrap();
+
+                Rc::new(MinecraftStatementObject {
+                    value: Statements::Item(
+                        format!("give {} ", own.selector),
+                        item.clone(),
+                        ItemSyntax::Stack,
+                        count_suffix(item.count),
+                    ),
+                })
+            }),
+        );
+
+        map.insert(
+            "clear".to_string(),
+            Box::new(|params, variable| {
+                if params.len() > 2 {
+                    eprintln!("Incorrect number of arguments for function clear");
+                    exit(1);
+                }
+
+                let own = match_objects(*variable.unwrap().value.clone());
+                let own = own.as_any().downcast_ref::<EntityObject>().unwrap();
+
+                // clear() empties the whole inventory
+                if params.is_empty() {
+                    return Rc::new(MinecraftStatementObject {
+                        value: Statements::Raw(format!("clear {}", own.selector)),
+                    });
+                }
+                let item = params[0].as_any().downcast_ref::<ItemObject>();
+                if item.is_none() {
+                    eprintln!("Incorrect argument type for function clear, expected Item");
+                    exit(1);
+                }
+                let mut suffix = String::new();
+                if params.len() == 2 {
+                    let max_count = params[1].as_any().downcast_ref::<NumberObject>();
+                    if max_count.is_none() || max_count.unwrap().value < 0 {
+                        eprintln!("Incorrect argument type for function clear, expected a count");
+                        exit(1);
+                    }
+                    suffix = format!(" {}", max_count.unwrap().value);
+                }
+
+                Rc::new(MinecraftStatementObject {
+                    value: Statements::Item(
+                        format!("clear {} ", own.selector),
+                        item.unwrap().clone(),
+                        ItemSyntax::Stack,
+                        suffix,
+                    ),
+                })
+            }),
+        );
+
+        map.insert(
+            "replace_item".to_string(),
+            Box::new(|params, variable| {
+                if params.len() != 2 {
+                    eprintln!("Incorrect number of arguments for function replace_item");
+                    exit(1);
+                }
+                let slot = params[0].as_any().downcast_ref::<StringObject>();
+                let item = params[1].as_any().downcast_ref::<ItemObject>();
+                if slot.is_none() || item.is_none() || !is_slot(&slot.unwrap().value) {
+                    eprintln!("Incorrect argument type for function replace_item");
+                    exit(1);
+                }
+                let item = item.unwrap();
+
+                let own = match_objects(*variable.unwrap().value.clone());
+                let own = own.as_any().downcast_ref::<EntityObject>().unwrap();
+
+                Rc::new(MinecraftStatementObject {
+                    value: Statements::Item(
+                        format!(
+                            "item replace entity {} {} with ",
+                            own.selector,
+                            slot.unwrap().value
+                        ),
+                        item.clone(),
+                        ItemSyntax::Stack,
+                        count_suffix(item.count),
+                    ),
+                })
+            }),
+        );
+
         return map;
     }
 }
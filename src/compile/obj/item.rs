@@ -0,0 +1,353 @@
+use std::{any::Any, collections::HashMap, process::exit, rc::Rc};
+
+use crate::compile::{
+    nbt::quote_snbt_string,
+    objects::{check_params, mk_function_map, own, string_param, Object, ObjectFunction, Objects},
+    version::Feature,
+};
+
+use super::{
+    basic::{NumberObject, SetObject, StringObject},
+    block::is_resource_location,
+    std::VariableObject,
+    text::json_string,
+};
+
+/// An item stack such as `new Item("diamond_sword").count(1).name("Excalibur")`. Items are
+/// only turned into text when compiled, as the syntax depends on the target pack format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ItemObject {
+    pub id: String,
+    pub count: i64,
+    pub name: Option<String>,
+    pub lore: Vec<String>,
+    pub enchantments: Vec<(String, i64)>,
+    pub unbreakable: bool,
+}
+
+/// How an item is written inside a command.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ItemSyntax {
+    /// The item argument of `give`, `clear` and `item replace`.
+    Stack,
+    /// The NBT of an item entity, used by `summon item`.
+    Entity,
+}
+
+fn namespaced(id: &str) -> String {
+    if id.contains(':') {
+        id.to_string()
+    } else {
+        format!("minecraft:{}", id)
+    }
+}
+
+impl ItemObject {
+    /// A text component holding plain text, in the form items of the pack format expect.
+    fn text(value: &str, pack_format: u32) -> String {
+        if Feature::SnbtText.supported_by(pack_format) {
+            quote_snbt_string(value, '"')
+        } else {
+            quote_snbt_string(&json_string(value), '\'')
+        }
+    }
+
+    /// The NBT tag of the item for pack formats before data components.
+    fn tag(&self, pack_format: u32) -> Option<String> {
+        let mut tag = vec![];
+        let mut display = vec![];
+        if let Some(name) = &self.name {
+            display.push(format!("Name:{}", ItemObject::text(name, pack_format)));
+        }
+        if !self.lore.is_empty() {
+            let lore = self
+                .lore
+                .iter()
+                .map(|line| ItemObject::text(line, pack_format))
+                .collect::<Vec<String>>();
+            display.push(format!("Lore:[{}]", lore.join(",")));
+        }
+        if !display.is_empty() {
+            tag.push(format!("display:{{{}}}", display.join(",")));
+        }
+        if !self.enchantments.is_empty() {
+            let enchantments = self
+                .enchantments
+                .iter()
+                .map(|(id, level)| format!("{{id:\"{}\",lvl:{}s}}", id, level))
+                .collect::<Vec<String>>();
+            tag.push(format!("Enchantments:[{}]", enchantments.join(",")));
+        }
+        if self.unbreakable {
+            tag.push("Unbreakable:1b".to_string());
+        }
+        if tag.is_empty() {
+            None
+        } else {
+            Some(format!("{{{}}}", tag.join(",")))
+        }
+    }
+
+    /// The data components of the item as `(name, value)` pairs.
+    fn components(&self, pack_format: u32) -> Vec<(String, String)> {
+        let mut components = vec![];
+        if let Some(name) = &self.name {
+            components.push((
+                "minecraft:custom_name".to_string(),
+                ItemObject::text(name, pack_format),
+            ));
+        }
+        if !self.lore.is_empty() {
+            let lore = self
+                .lore
+                .iter()
+                .map(|line| ItemObject::text(line, pack_format))
+                .collect::<Vec<String>>();
+            components.push((
+                "minecraft:lore".to_string(),
+                format!("[{}]", lore.join(",")),
+            ));
+        }
+        if !self.enchantments.is_empty() {
+            let levels = self
+                .enchantments
+                .iter()
+                .map(|(id, level)| format!("\"{}\":{}", id, level))
+                .collect::<Vec<String>>()
+                .join(",");
+            let enchantments = if Feature::SnbtText.supported_by(pack_format) {
+                format!("{{{}}}", levels)
+            } else {
+                format!("{{levels:{{{}}}}}", levels)
+            };
+            components.push(("minecraft:enchantments".to_string(), enchantments));
+        }
+        if self.unbreakable {
+            components.push(("minecraft:unbreakable".to_string(), "{}".to_string()));
+        }
+        components
+    }
+
+    /// Renders the item for the given pack format, either as a command argument or as the
+    /// NBT of an item entity.
+    pub fn render(&self, syntax: &ItemSyntax, pack_format: u32) -> String {
+        match syntax {
+            ItemSyntax::Stack if Feature::ItemComponents.supported_by(pack_format) => {
+                let components = self.components(pack_format);
+                if components.is_empty() {
+                    return self.id.clone();
+                }
+                let components = components
+                    .iter()
+                    .map(|(name, value)| format!("{}={}", name, value))
+                    .collect::<Vec<String>>();
+                format!("{}[{}]", self.id, components.join(","))
+            }
+            ItemSyntax::Stack => {
+                format!("{}{}", self.id, self.tag(pack_format).unwrap_or_default())
+            }
+            ItemSyntax::Entity if Feature::ItemComponents.supported_by(pack_format) => {
+                let mut item = vec![
+                    format!("id:\"{}\"", self.id),
+                    format!("count:{}", self.count),
+                ];
+                let components = self.components(pack_format);
+                if !components.is_empty() {
+                    let components = components
+                        .iter()
+                        .map(|(name, value)| format!("\"{}\":{}", name, value))
+                        .collect::<Vec<String>>();
+                    item.push(format!("components:{{{}}}", components.join(",")));
+                }
+                format!("{{Item:{{{}}}}}", item.join(","))
+            }
+            ItemSyntax::Entity => {
+                let mut item = vec![
+                    format!("id:\"{}\"", self.id),
+                    format!("Count:{}b", self.count),
+                ];
+                if let Some(tag) = self.tag(pack_format) {
+                    item.push(format!("tag:{}", tag));
+                }
+                format!("{{Item:{{{}}}}}", item.join(","))
+            }
+        }
+    }
+}
+
+/// Checks a slot name such as `weapon.mainhand` or `container.5`.
+pub fn is_slot(slot: &str) -> bool {
+    !slot.is_empty()
+        && slot
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '.' || c == '_')
+}
+
+/// The optional count written after an item argument.
+pub fn count_suffix(count: i64) -> String {
+    if count == 1 {
+        String::new()
+    } else {
+        format!(" {}", count)
+    }
+}
+
+fn number_param(params: &[Rc<dyn Object>], index: usize, function: &str) -> i64 {
+    let value = params[index].as_any().downcast_ref::<NumberObject>();
+    if value.is_none() {
+        eprintln!(
+            "Incorrect argument type for function {}, expected number",
+            function
+        );
+        exit(1);
+    }
+    value.unwrap().value
+}
+
+impl Object for ItemObject {
+    fn get_type(&self) -> Objects {
+        Objects::Item(self.clone())
+    }
+    fn get_variables(&self) -> HashMap<String, Rc<VariableObject>> {
+        HashMap::new()
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
+        let mut map = mk_function_map();
+
+        map.insert(
+            "instantiate".to_string(),
+            Box::new(|params, _| {
+                check_params(&params, 1, 1, "Item");
+                let id = string_param(&params, 0, "Item");
+                if !is_resource_location(&id) {
+                    eprintln!("Invalid item id {}", id);
+                    exit(1);
+                }
+                Rc::new(ItemObject {
+                    id: namespaced(&id),
+                    count: 1,
+                    name: None,
+                    lore: vec![],
+                    enchantments: vec![],
+                    unbreakable: false,
+                })
+            }),
+        );
+
+        map.insert(
+            "count".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 1, 1, "count");
+                let count = number_param(&params, 0, "count");
+                if !(1..=99).contains(&count) {
+                    eprintln!("Item count must be between 1 and 99, got {}", count);
+                    exit(1);
+                }
+                let mut item: ItemObject = own(variable);
+                item.count = count;
+                Rc::new(item)
+            }),
+        );
+
+        map.insert(
+            "name".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 1, 1, "name");
+                let mut item: ItemObject = own(variable);
+                item.name = Some(string_param(&params, 0, "name"));
+                Rc::new(item)
+            }),
+        );
+
+        map.insert(
+            "lore".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 1, 1, "lore");
+                let lines = params[0].as_any().downcast_ref::<SetObject>();
+                if lines.is_none() {
+                    eprintln!("Incorrect argument type for function lore, expected a list of strings");
+                    exit(1);
+                }
+                let mut lore = vec![];
+                for line in lines.unwrap().values.iter() {
+                    match line.as_any().downcast_ref::<StringObject>() {
+                        Some(line) => lore.push(line.value.clone()),
+                        None => {
+                            eprintln!("Incorrect argument type for function lore, expected a list of strings");
+                            exit(1);
+                        }
+                    }
+                }
+                let mut item: ItemObject = own(variable);
+                item.lore = lore;
+                Rc::new(item)
+            }),
+        );
+
+        map.insert(
+            "enchant".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 2, 2, "enchant");
+                let enchantment = string_param(&params, 0, "enchant");
+                if !is_resource_location(&enchantment) {
+                    eprintln!("Invalid enchantment {}", enchantment);
+                    exit(1);
+                }
+                let level = number_param(&params, 1, "enchant");
+                if !(1..=255).contains(&level) {
+                    eprintln!("Enchantment level must be between 1 and 255, got {}", level);
+                    exit(1);
+                }
+                let enchantment = namespaced(&enchantment);
+                let mut item: ItemObject = own(variable);
+                // enchanting twice with the same enchantment replaces its level
+                item.enchantments.retain(|(id, _)| *id != enchantment);
+                item.enchantments.push((enchantment, level));
+                Rc::new(item)
+            }),
+        );
+
+        map.insert(
+            "unbreakable".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 0, 0, "unbreakable");
+                let mut item: ItemObject = own(variable);
+                item.unbreakable = true;
+                Rc::new(item)
+            }),
+        );
+
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compile::compiler::tests::compiled_for;
+
+    const GIVE: &str = "let sword = new Item(\"diamond_sword\").count(2).name(\"Excalibur\").enchant(\"sharpness\", 5);\nnew Entity(@a).give(sword);\n";
+
+    #[test]
+    fn writes_items_as_nbt_before_data_components() {
+        assert_eq!(
+            compiled_for("1.20.4", GIVE)["code"],
+            "give @a minecraft:diamond_sword{display:{Name:'\"Excalibur\"'},Enchantments:[{id:\"minecraft:sharpness\",lvl:5s}]} 2"
+        );
+    }
+
+    #[test]
+    fn writes_items_as_data_components() {
+        assert_eq!(
+            compiled_for("1.20.5", GIVE)["code"],
+            "give @a minecraft:diamond_sword[minecraft:custom_name='\"Excalibur\"',minecraft:enchantments={levels:{\"minecraft:sharpness\":5}}] 2"
+        );
+        assert_eq!(
+            compiled_for("1.21.5", GIVE)["code"],
+            "give @a minecraft:diamond_sword[minecraft:custom_name=\"Excalibur\",minecraft:enchantments={\"minecraft:sharpness\":5}] 2"
+        );
+    }
+}
@@ -0,0 +1,325 @@
+use std::{any::Any, collections::HashMap, process::exit, rc::Rc};
+
+use crate::compile::{
+    mcstatements::{MinecraftStatementObject, Statements},
+    nbt::quote_snbt_string,
+    objects::{
+        check_params, match_objects, mk_function_map, mk_variable, own, statement, Object,
+        ObjectFunction, Objects,
+    },
+};
+
+use super::{
+    basic::NumberObject,
+    std::VariableObject,
+    storage::{score_source, snbt_value, NbtType, StoragePathObject},
+};
+
+/// Copies the element at `$(index)` of the list at `$(path)` into `__args.value`.
+const LIST_GET_HELPER: &str = "__list_get";
+const LIST_GET_BODY: &str =
+    "$data modify storage $(storage) __args.value set from storage $(storage) $(path)[$(index)]";
+/// Copies `__args.value` into the element at `$(index)` of the list at `$(path)`.
+const LIST_SET_HELPER: &str = "__list_set";
+const LIST_SET_BODY: &str =
+    "$data modify storage $(storage) $(path)[$(index)] set from storage $(storage) __args.value";
+
+/// A list that lives in data storage at runtime, such as `storage.list("queue", "int")`.
+/// Unlike arrays, its length and contents are only known while the datapack runs.
+#[derive(Clone, Debug)]
+pub struct ListObject {
+    pub storage: String,
+    pub path: String,
+    pub element: NbtType,
+}
+
+/// A list index known at compile time. Negative indexes count from the end of the list.
+fn constant_index(object: &Rc<dyn Object>) -> Option<i64> {
+    object
+        .as_any()
+        .downcast_ref::<NumberObject>()
+        .map(|number| number.value)
+}
+
+impl ListObject {
+    fn target(&self) -> String {
+        format!("storage {} {}", self.storage, self.path)
+    }
+
+    /// The element at a constant index.
+    pub fn element(&self, index: i64) -> StoragePathObject {
+        StoragePathObject {
+            storage: self.storage.clone(),
+            path: format!("{}[{}]", self.path, index),
+            nbt_type: self.element.clone(),
+        }
+    }
+
+    /// The scratch path the list helpers pass values through.
+    fn value_path(&self) -> StoragePathObject {
+        StoragePathObject {
+            storage: self.storage.clone(),
+            path: "__args.value".to_string(),
+            nbt_type: self.element.clone(),
+        }
+    }
+
+    /// The commands that write a value (a literal, another path or a score) into `path`.
+    fn write_into(path: &StoragePathObject, value: &Rc<dyn Object>) -> Rc<dyn Object> {
+        path.call("set", vec![value.clone()])
+    }
+
+    /// The commands that copy `path` into a target, either a storage path or a score.
+    fn read_into(
+        path: &StoragePathObject,
+        target: &Rc<dyn Object>,
+        function: &str,
+    ) -> Rc<dyn Object> {
+        if let Some(target) = target.as_any().downcast_ref::<StoragePathObject>() {
+            return target.call("set", vec![Rc::new(path.clone())]);
+        }
+        match score_source(target) {
+            Some((holder, objective)) => statement(path.to_score(&holder, &objective, "1")),
+            None => {
+                eprintln!(
+                    "Incorrect argument type for function {}, expected a storage path or a score",
+                    function
+                );
+                exit(1);
+            }
+        }
+    }
+
+    /// Calls a list helper with the index given by a score, once the arguments are set up.
+    fn helper(
+        &self,
+        helper: &str,
+        body: &str,
+        index: &Rc<dyn Object>,
+        function: &str,
+    ) -> Vec<Rc<dyn Object>> {
+        let score = score_source(index);
+        if score.is_none() {
+            eprintln!(
+                "Incorrect argument type for function {}, expected a number or a score as index",
+                function
+            );
+            exit(1);
+        }
+        let (holder, objective) = score.unwrap();
+        vec![
+            statement(format!(
+                "data modify storage {} __args merge value {{storage:{},path:{}}}",
+                self.storage,
+                quote_snbt_string(&self.storage, '"'),
+                quote_snbt_string(&self.path, '"')
+            )),
+            statement(format!(
+                "execute store result storage {} __args.index int 1 run scoreboard players get {} {}",
+                self.storage, holder, objective
+            )),
+            Rc::new(MinecraftStatementObject {
+                value: Statements::Helper(
+                    helper.to_string(),
+                    body.to_string(),
+                    format!("storage {} __args", self.storage),
+                ),
+            }),
+        ]
+    }
+}
+
+impl Object for ListObject {
+    fn get_type(&self) -> Objects {
+        Objects::List(
+            self.storage.clone(),
+            self.path.clone(),
+            self.element.clone(),
+        )
+    }
+    fn get_variables(&self) -> HashMap<String, Rc<VariableObject>> {
+        let mut map = HashMap::new();
+        map.insert(
+            "storage".to_string(),
+            mk_variable(Objects::Storage(self.storage.clone()), Objects::Unknown),
+        );
+        map.insert(
+            "path".to_string(),
+            mk_variable(Objects::String(self.path.clone()), Objects::Unknown),
+        );
+        map
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
+        let mut map = mk_function_map();
+
+        map.insert(
+            "push".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 1, 1, "push");
+                let own: ListObject = own(variable);
+                if let Some(path) = params[0].as_any().downcast_ref::<StoragePathObject>() {
+                    return statement(format!(
+                        "data modify {} append from storage {} {}",
+                        own.target(),
+                        path.storage,
+                        path.path
+                    ));
+                }
+                if score_source(&params[0]).is_none() {
+                    return statement(format!(
+                        "data modify {} append value {}",
+                        own.target(),
+                        snbt_value(&params[0], &own.element, "push")
+                    ));
+                }
+                // scores are converted through the scratch path first
+                let value = own.value_path();
+                match_objects(Objects::Array(vec![
+                    ListObject::write_into(&value, &params[0]),
+                    statement(format!(
+                        "data modify {} append from storage {} {}",
+                        own.target(),
+                        value.storage,
+                        value.path
+                    )),
+                ]))
+            }),
+        );
+
+        map.insert(
+            "pop".to_string(),
+            Box::new(|params, variable| {
+                // pop(target) keeps the removed element
+                check_params(&params, 0, 1, "pop");
+                let own: ListObject = own(variable);
+                let remove = statement(format!("data remove {}[-1]", own.target()));
+                if params.is_empty() {
+                    return remove;
+                }
+                match_objects(Objects::Array(vec![
+                    ListObject::read_into(&own.element(-1), &params[0], "pop"),
+                    remove,
+                ]))
+            }),
+        );
+
+        map.insert(
+            "len".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 0, 1, "len");
+                let own: ListObject = own(variable);
+                if params.is_empty() {
+                    return Rc::new(MinecraftStatementObject {
+                        value: Statements::Query(format!("data get {}", own.target())),
+                    });
+                }
+                match score_source(&params[0]) {
+                    Some((holder, objective)) => statement(format!(
+                        "execute store result score {} {} run data get {}",
+                        holder,
+                        objective,
+                        own.target()
+                    )),
+                    None => {
+                        eprintln!("Incorrect argument type for function len, expected a score");
+                        exit(1);
+                    }
+                }
+            }),
+        );
+
+        map.insert(
+            "clear".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 0, 0, "clear");
+                let own: ListObject = own(variable);
+                statement(format!("data modify {} set value []", own.target()))
+            }),
+        );
+
+        map.insert(
+            "get".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 1, 1, "get");
+                let own: ListObject = own(variable);
+                match constant_index(&params[0]) {
+                    Some(index) => Rc::new(own.element(index)),
+                    None => {
+                        eprintln!("The index of function get must be a number, use read(index, target) for scores");
+                        exit(1);
+                    }
+                }
+            }),
+        );
+
+        map.insert(
+            "read".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 2, 2, "read");
+                let own: ListObject = own(variable);
+                if let Some(index) = constant_index(&params[0]) {
+                    return ListObject::read_into(&own.element(index), &params[1], "read");
+                }
+                let mut statements = own.helper(LIST_GET_HELPER, LIST_GET_BODY, &params[0], "read");
+                statements.push(ListObject::read_into(&own.value_path(), &params[1], "read"));
+                match_objects(Objects::Array(statements))
+            }),
+        );
+
+        map.insert(
+            "write".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 2, 2, "write");
+                let own: ListObject = own(variable);
+                if let Some(index) = constant_index(&params[0]) {
+                    return ListObject::write_into(&own.element(index), &params[1]);
+                }
+                let mut statements = vec![ListObject::write_into(&own.value_path(), &params[1])];
+                statements.extend(own.helper(LIST_SET_HELPER, LIST_SET_BODY, &params[0], "write"));
+                match_objects(Objects::Array(statements))
+            }),
+        );
+
+        map
+    }
+}
+
+/// Reads the name of a list's storage and its path from a `for` loop iterator.
+pub fn runtime_list(object: &Rc<dyn Object>) -> Option<ListObject> {
+    match object.get_type() {
+        Objects::List(storage, path, element) => Some(ListObject {
+            storage,
+            path,
+            element,
+        }),
+        Objects::Variable(value, _) => runtime_list(&match_objects(*value)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compile::compiler::tests::compiled;
+
+    #[test]
+    fn stores_the_length_in_a_score() {
+        let outputs = compiled(
+            "let st = new Storage(\"mypack:state\");\nlet items = st.list(\"items\", \"int\");\nitems.push(3);\nlet n = items.len();\ntellraw(@a, \"{n}\");\n",
+        );
+        assert_eq!(
+            outputs["code"].lines().collect::<Vec<&str>>(),
+            [
+                "data modify storage mypack:state items append value 3",
+                "scoreboard objectives add test.code.tmp-0 dummy",
+                "execute store result score $test test.code.tmp-0 run data get storage mypack:state items",
+                "scoreboard objectives add test.code.n dummy",
+                "scoreboard players operation $test test.code.n = $test test.code.tmp-0",
+                "tellraw @a {\"score\":{\"name\":\"$test\",\"objective\":\"test.code.n\"}}",
+            ]
+        );
+    }
+}
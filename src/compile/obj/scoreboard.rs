@@ -1,11 +1,122 @@
-use std::{any::Any, collections::HashMap, rc::Rc};
+use std::{any::Any, collections::HashMap, process::exit, rc::Rc};
 
 use crate::{
     ast::operations::Operator,
-    compile::objects::{match_objects, mk_function_map, mk_variable, Object, Objects},
+    compile::{
+        mcstatements::{MinecraftStatementObject, Statements},
+        objects::{
+            check_params, match_objects, mk_function_map, mk_variable, own, statement,
+            string_param, Object, ObjectFunction, Objects,
+        },
+    },
 };
 
-use super::std::VariableObject;
+use super::{
+    basic::StringObject, entity::EntityObject, std::VariableObject, storage::StoragePathObject,
+    text::TextObject,
+};
+
+const TEAM_COLORS: [&str; 16] = [
+    "black",
+    "dark_blue",
+    "dark_green",
+    "dark_aqua",
+    "dark_red",
+    "dark_purple",
+    "gold",
+    "gray",
+    "dark_gray",
+    "blue",
+    "green",
+    "aqua",
+    "red",
+    "light_purple",
+    "yellow",
+    "white",
+];
+
+const SIMPLE_CRITERIA: [&str; 11] = [
+    "dummy",
+    "trigger",
+    "deathCount",
+    "playerKillCount",
+    "totalKillCount",
+    "health",
+    "xp",
+    "level",
+    "food",
+    "air",
+    "armor",
+];
+
+const STATISTIC_TYPES: [&str; 9] = [
+    "mined",
+    "crafted",
+    "used",
+    "broken",
+    "picked_up",
+    "dropped",
+    "killed",
+    "killed_by",
+    "custom",
+];
+
+/// Checks an objective criterion: a simple one such as `dummy`, a team one such as
+/// `teamkill.red`, or a statistic such as `minecraft.mined:minecraft.stone`.
+pub fn is_criteria(criteria: &str) -> bool {
+    if SIMPLE_CRITERIA.contains(&criteria) {
+        return true;
+    }
+    if let Some((kind, color)) = criteria.split_once('.') {
+        if (kind == "teamkill" || kind == "killedByTeam") && TEAM_COLORS.contains(&color) {
+            return true;
+        }
+    }
+    match criteria.split_once(':') {
+        Some((kind, value)) => {
+            let kind = kind.strip_prefix("minecraft.").unwrap_or(kind);
+            STATISTIC_TYPES.contains(&kind)
+                && !value.is_empty()
+                && value
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "_-.".contains(c))
+        }
+        None => false,
+    }
+}
+
+/// Checks an objective name, which may only use letters, digits and `_ - . +`.
+pub fn is_objective_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-.+".contains(c))
+}
+
+/// Checks a display slot such as `sidebar` or `sidebar.team.red`.
+pub fn is_display_slot(slot: &str) -> bool {
+    match slot {
+        "list" | "sidebar" | "below_name" | "belowName" => true,
+        _ => slot
+            .strip_prefix("sidebar.team.")
+            .is_some_and(|color| TEAM_COLORS.contains(&color)),
+    }
+}
+
+/// Reads a score holder, given either as an entity or as a name such as `"#global"`.
+fn holder_param(params: &[Rc<dyn Object>], index: usize, function: &str) -> String {
+    if let Some(entity) = params[index].as_any().downcast_ref::<EntityObject>() {
+        return entity.selector.clone();
+    }
+    if let Some(name) = params[index].as_any().downcast_ref::<StringObject>() {
+        return name.value.clone();
+    }
+    eprintln!(
+        "Incorrect argument type for function {}, expected an entity or a name",
+        function
+    );
+    exit(1);
+}
 
 #[derive(Clone, Debug)]
 pub struct ScoreboardObject {
@@ -51,16 +162,8 @@ impl Object for ScoreboardPlayerPairObject {
     fn as_any(&self) -> &dyn Any {
         self
     }
-    fn get_functions(
-        &self,
-    ) -> HashMap<
-        String,
-        Box<dyn Fn(Vec<Rc<dyn Object>>, Option<Rc<VariableObject>>) -> Rc<dyn Object>>,
-    > {
-        fn create_operator_func(
-            operator: Operator,
-        ) -> Box<dyn Fn(Vec<Rc<dyn Object>>, Option<Rc<VariableObject>>) -> Rc<dyn Object>>
-        {
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
+        fn create_operator_func(operator: Operator) -> ObjectFunction {
             return Box::new(move |args, var| {
                 let value = args[0].get_type();
                 if var.is_none() {
@@ -112,6 +215,17 @@ impl Object for ScoreboardPlayerPairObject {
                                 Box::new(second_scoreboard_pair.get_type()),
                             ))
                         }
+                        // reading a storage path converts it into a score
+                        Objects::StoragePath(storage, path, nbt_type)
+                            if matches!(operator, Operator::Assignment) =>
+                        {
+                            let path = StoragePathObject {
+                                storage,
+                                path,
+                                nbt_type,
+                            };
+                            statement(path.to_score(&player_name, &objective_name, "1"))
+                        }
                         // TODO: Add Variable support
                         _ => panic!("Invalid arguments"),
                     }
@@ -138,9 +252,56 @@ impl Object for ScoreboardPlayerPairObject {
             create_operator_func(Operator::Assignment),
         );
 
+        map.insert(
+            "reset".to_string(),
+            Box::new(|params, var| {
+                check_params(&params, 0, 0, "reset");
+                let pair: ScoreboardPlayerPairObject = own(var);
+                statement(format!(
+                    "scoreboard players reset {} {}",
+                    pair.player_name, pair.objective_name
+                ))
+            }),
+        );
+        map.insert(
+            "enable".to_string(),
+            Box::new(|params, var| {
+                check_params(&params, 0, 0, "enable");
+                let pair: ScoreboardPlayerPairObject = own(var);
+                statement(format!(
+                    "scoreboard players enable {} {}",
+                    pair.player_name, pair.objective_name
+                ))
+            }),
+        );
+        map.insert(
+            "list".to_string(),
+            Box::new(|params, var| {
+                check_params(&params, 0, 0, "list");
+                let pair: ScoreboardPlayerPairObject = own(var);
+                statement(format!("scoreboard players list {}", pair.player_name))
+            }),
+        );
+
         map
     }
 }
+impl ScoreboardObject {
+    /// Calls one of the objective's functions, such as `enable`, and returns the command it
+    /// compiles to.
+    pub fn command(&self, function: &str, params: Vec<Rc<dyn Object>>) -> String {
+        let functions = self.get_functions();
+        let function = functions
+            .get(function)
+            .expect("Unknown scoreboard function");
+        let statement = function(params, Some(mk_variable(self.get_type(), Objects::Unknown)));
+        match statement.get_type() {
+            Objects::MCStatement(Statements::Raw(command)) => command,
+            _ => panic!("Scoreboard function did not produce a command"),
+        }
+    }
+}
+
 impl Object for ScoreboardObject {
     fn get_type(&self) -> Objects {
         Objects::Scoreboard(
@@ -156,12 +317,7 @@ impl Object for ScoreboardObject {
         self
     }
 
-    fn get_functions(
-        &self,
-    ) -> HashMap<
-        String,
-        Box<dyn Fn(Vec<Rc<dyn Object>>, Option<Rc<VariableObject>>) -> Rc<dyn Object>>,
-    > {
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
         let mut map = mk_function_map();
         map.insert(
             "get_player".to_string(),
@@ -171,15 +327,13 @@ impl Object for ScoreboardObject {
                 }
                 let var = var.unwrap();
                 if let Objects::Scoreboard(sb_name, _, sb_type) = *var.value.clone() {
-                    let name = args[0].get_type();
-                    match name {
-                        Objects::String(name) => match_objects(Objects::ScoreboardPlayerPair(
-                            sb_name.clone(),
-                            name,
-                            sb_type,
-                        )),
-                        _ => panic!("Invalid arguments"),
-                    }
+                    check_params(&args, 1, 1, "get_player");
+                    let name = holder_param(&args, 0, "get_player");
+                    match_objects(Objects::ScoreboardPlayerPair(
+                        sb_name.clone(),
+                        name,
+                        sb_type,
+                    ))
                 } else {
                     panic!("Invalid arguments")
                 }
@@ -189,16 +343,161 @@ impl Object for ScoreboardObject {
         map.insert(
             "instantiate".to_string(),
             Box::new(|args, _| {
+                check_params(&args, 2, 2, "Scoreboard");
                 let name = args[0].get_type();
                 let objective = args[1].get_type();
                 match (name, objective) {
-                    (Objects::String(name), Objects::String(objective)) => match_objects(
-                        Objects::Scoreboard(name, objective, Box::new(Objects::Number(0))),
-                    ),
+                    (Objects::String(name), Objects::String(objective)) => {
+                        if !is_objective_name(&name) {
+                            eprintln!("Invalid objective name {}", name);
+                            exit(1);
+                        }
+                        if !is_criteria(&objective) {
+                            eprintln!("Invalid objective criteria {}", objective);
+                            exit(1);
+                        }
+                        match_objects(Objects::Scoreboard(
+                            name,
+                            objective,
+                            Box::new(Objects::Number(0)),
+                        ))
+                    }
                     _ => panic!("Invalid arguments"),
                 }
             }),
         );
+
+        map.insert(
+            "display".to_string(),
+            Box::new(|params, var| {
+                check_params(&params, 1, 1, "display");
+                let slot = string_param(&params, 0, "display");
+                if !is_display_slot(&slot) {
+                    eprintln!("Invalid display slot {}", slot);
+                    exit(1);
+                }
+                let name = own::<ScoreboardObject>(var).name;
+                statement(format!(
+                    "scoreboard objectives setdisplay {} {}",
+                    slot, name
+                ))
+            }),
+        );
+
+        map.insert(
+            "set_display_name".to_string(),
+            Box::new(|params, var| {
+                check_params(&params, 1, 1, "set_display_name");
+                let display_name = TextObject::from_object(&params[0]);
+                if display_name.is_none() {
+                    eprintln!(
+                        "Incorrect argument type for function set_display_name, expected text"
+                    );
+                    exit(1);
+                }
+                let name = own::<ScoreboardObject>(var).name;
+                Rc::new(MinecraftStatementObject {
+                    value: Statements::Text(
+                        format!("scoreboard objectives modify {} displayname ", name),
+                        display_name.unwrap(),
+                        String::new(),
+                    ),
+                })
+            }),
+        );
+
+        map.insert(
+            "render_type".to_string(),
+            Box::new(|params, var| {
+                check_params(&params, 1, 1, "render_type");
+                let render_type = string_param(&params, 0, "render_type");
+                if render_type != "integer" && render_type != "hearts" {
+                    eprintln!(
+                        "Invalid render type {}, expected integer or hearts",
+                        render_type
+                    );
+                    exit(1);
+                }
+                let name = own::<ScoreboardObject>(var).name;
+                statement(format!(
+                    "scoreboard objectives modify {} rendertype {}",
+                    name, render_type
+                ))
+            }),
+        );
+
+        map.insert(
+            "reset".to_string(),
+            Box::new(|params, var| {
+                // reset() clears the objective for every score holder
+                check_params(&params, 0, 1, "reset");
+                let holder = if params.is_empty() {
+                    "*".to_string()
+                } else {
+                    holder_param(&params, 0, "reset")
+                };
+                let name = own::<ScoreboardObject>(var).name;
+                statement(format!("scoreboard players reset {} {}", holder, name))
+            }),
+        );
+
+        map.insert(
+            "enable".to_string(),
+            Box::new(|params, var| {
+                check_params(&params, 1, 1, "enable");
+                let holder = holder_param(&params, 0, "enable");
+                let scoreboard: ScoreboardObject = own(var);
+                let (name, criteria) = (scoreboard.name, scoreboard.objective);
+                if criteria != "trigger" {
+                    eprintln!(
+                        "Only trigger objectives can be enabled, {} is {}",
+                        name, criteria
+                    );
+                    exit(1);
+                }
+                statement(format!("scoreboard players enable {} {}", holder, name))
+            }),
+        );
+
+        map.insert(
+            "remove".to_string(),
+            Box::new(|params, var| {
+                check_params(&params, 0, 0, "remove");
+                let name = own::<ScoreboardObject>(var).name;
+                statement(format!("scoreboard objectives remove {}", name))
+            }),
+        );
+
+        map.insert(
+            "list".to_string(),
+            Box::new(|params, _| {
+                check_params(&params, 0, 0, "list");
+                statement("scoreboard objectives list".to_string())
+            }),
+        );
         map
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::compile::compiler::tests::compiled;
+
+    #[test]
+    fn displays_and_resets_objectives() {
+        let outputs = compiled(
+            "let kills = new Scoreboard(\"kills\", \"minecraft.killed:minecraft.zombie\");\nkills.display(\"sidebar\");\nkills.render_type(\"hearts\");\nkills.set_display_name(text(\"Kills\").color(\"red\"));\nkills.get_player(\"@s\").set(0);\nkills.reset();\n",
+        );
+        assert_eq!(
+            outputs["code"].lines().collect::<Vec<&str>>(),
+            [
+                "scoreboard objectives add kills minecraft.killed:minecraft.zombie",
+                "scoreboard objectives setdisplay sidebar kills",
+                "scoreboard objectives modify kills rendertype hearts",
+                "scoreboard objectives modify kills displayname {\"text\":\"Kills\",\"color\":\"red\"}",
+                "scoreboard players set @s kills 0",
+                "scoreboard players reset * kills",
+            ]
+        );
+    }
+}
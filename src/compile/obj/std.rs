@@ -4,8 +4,8 @@ use crate::{
     ast::operations::{ASTOperation, Operator},
     compile::{
         compiler::{Compiler, Scope, Variable},
-        mcstatements::{execute_step_str, MinecraftStatementObject, Statements},
-        objects::{match_objects, Object, Objects},
+        mcstatements::{execute_step_str, ExecuteSteps, MinecraftStatementObject, Statements},
+        objects::{match_objects, Object, ObjectFunction, Objects},
     },
 };
 
@@ -35,6 +35,12 @@ pub struct IfStatementObject {
     pub code_block: Box<ASTOperation>,
 }
 
+#[derive(Clone, Debug)]
+pub struct ExecuteBlockObject {
+    pub steps: Vec<ExecuteSteps>,
+    pub code_block: Box<ASTOperation>,
+}
+
 #[derive(Clone, Debug)]
 pub struct WhileObject {
     pub name: String,
@@ -53,12 +59,7 @@ impl Object for VariableObject {
         self
     }
 
-    fn get_functions(
-        &self,
-    ) -> HashMap<
-        String,
-        Box<dyn Fn(Vec<Rc<dyn Object>>, Option<Rc<VariableObject>>) -> Rc<dyn Object>>,
-    > {
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
         HashMap::new()
     }
 }
@@ -74,12 +75,23 @@ impl Object for IfStatementObject {
         self
     }
 
-    fn get_functions(
-        &self,
-    ) -> HashMap<
-        String,
-        Box<dyn Fn(Vec<Rc<dyn Object>>, Option<Rc<VariableObject>>) -> Rc<dyn Object>>,
-    > {
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
+        HashMap::new()
+    }
+}
+
+impl Object for ExecuteBlockObject {
+    fn get_type(&self) -> Objects {
+        Objects::ExecuteBlock(self.steps.clone(), self.code_block.clone())
+    }
+    fn get_variables(&self) -> HashMap<String, Rc<VariableObject>> {
+        HashMap::new()
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
         HashMap::new()
     }
 }
@@ -95,12 +107,7 @@ impl Object for CreatedFunctionObject {
         self
     }
 
-    fn get_functions(
-        &self,
-    ) -> HashMap<
-        String,
-        Box<dyn Fn(Vec<Rc<dyn Object>>, Option<Rc<VariableObject>>) -> Rc<dyn Object>>,
-    > {
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
         HashMap::new()
     }
 }
@@ -120,12 +127,7 @@ impl Object for WhileObject {
         self
     }
 
-    fn get_functions(
-        &self,
-    ) -> HashMap<
-        String,
-        Box<dyn Fn(Vec<Rc<dyn Object>>, Option<Rc<VariableObject>>) -> Rc<dyn Object>>,
-    > {
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
         HashMap::new()
     }
 }
@@ -147,39 +149,50 @@ impl Object for MutationVariableObject {
         self
     }
 
-    fn get_functions(
-        &self,
-    ) -> HashMap<
-        String,
-        Box<dyn Fn(Vec<Rc<dyn Object>>, Option<Rc<VariableObject>>) -> Rc<dyn Object>>,
-    > {
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
         HashMap::new()
     }
 }
 
-pub fn compile_into_variable(var: Objects, scoreboard: Objects) -> (String, Option<Scope>) {
+pub fn compile_into_variable(
+    var: Objects,
+    scoreboard: Objects,
+    compiler: &Compiler,
+) -> (String, Option<Scope>) {
     if let Objects::Number(num) = var {
-        if let Objects::Scoreboard(name, objective, _) = scoreboard {
+        if let Objects::Scoreboard(name, criteria, _) = scoreboard {
             let mut built_str = String::new();
+            // registers share an objective created when the datapack loads
+            if !compiler.names.shared(&name) {
+                built_str.push_str(&format!(
+                    "scoreboard objectives add {} {}\n",
+                    name, criteria
+                ));
+            }
+            let (holder, objective) = compiler.score(&name);
             built_str.push_str(&format!(
-                "scoreboard objective add {} {}\n",
-                name, objective
+                "scoreboard players set {} {} {}\n",
+                holder, objective, num
             ));
-            built_str.push_str(&format!("scoreboard players set value {} {}\n", name, num));
             return (built_str, None);
         } else {
             eprintln!("Invalid scoreboard");
             exit(1);
         }
     } else if let Objects::Boolean(bool) = var {
-        if let Objects::Scoreboard(name, objective, _) = scoreboard {
+        if let Objects::Scoreboard(name, criteria, _) = scoreboard {
             let mut built_str = String::new();
+            // registers share an objective created when the datapack loads
+            if !compiler.names.shared(&name) {
+                built_str.push_str(&format!(
+                    "scoreboard objectives add {} {}\n",
+                    name, criteria
+                ));
+            }
+            let (holder, objective) = compiler.score(&name);
             built_str.push_str(&format!(
-                "scoreboard objective add {} {}\n",
-                name, objective
-            ));
-            built_str.push_str(&format!(
-                "scoreboard players set value {} {}\n",
+                "scoreboard players set {} {} {}\n",
+                holder,
                 objective,
                 if bool { 1 } else { 0 }
             ));
@@ -190,7 +203,7 @@ pub fn compile_into_variable(var: Objects, scoreboard: Objects) -> (String, Opti
         }
     } else if let Objects::Scoreboard(name, objective, _) = var {
         return (
-            format!("scoreboard objective add {} {}\n", name, objective),
+            format!("scoreboard objectives add {} {}\n", name, objective),
             None,
         );
     } else {
@@ -404,44 +417,39 @@ pub fn compile_into_while_loop(
             }
         }
 
-        if let Objects::Array(set) = set[0].get_type() {
-            for item in set {
-                let mut inline_scope = Scope::new(
-                    format!("{}.{}", scope.name, scope.scopes.len()),
-                    compiler.namespace.clone(),
-                    codes.clone(),
-                    scope.functions.clone(),
-                );
-                // add scoped variables
-                inline_scope.variables = scope.variables.clone();
-                println!("{:?}", item);
-                let variable = Objects::Variable(
+        for item in set {
+            let mut inline_scope = Scope::new(
+                format!("{}.{}", scope.name, scope.scopes.len()),
+                compiler.namespace.clone(),
+                codes.clone(),
+                scope.functions.clone(),
+            );
+            inline_scope.variables = scope.variables.block(&inline_scope.name);
+            inline_scope.temporaries = scope.temporaries.above();
+            println!("{:?}", item);
+            let variable = Objects::Variable(
+                Box::new(item.get_type()),
+                Box::new(Objects::Scoreboard(
+                    compiler.temporary(&mut inline_scope, &name, &item.get_type(), &associate),
+                    "dummy".to_string(),
                     Box::new(item.get_type()),
-                    Box::new(Objects::Scoreboard(
-                        format!("v_{}_{}", inline_scope.name, inline_scope.variables.len()),
-                        "dummy".to_string(),
-                        Box::new(item.get_type()),
-                    )),
-                );
-                let variable = match_objects(variable);
-                inline_scope.variables.insert(
-                    name.clone(),
-                    Variable {
-                        name: name.clone(),
-                        value: variable.clone(),
-                        static_variable: false,
-                    },
-                );
-                compiler.compile(&mut inline_scope);
-                scope.scopes.push(inline_scope.clone());
-                built_str.push_str(&format!(
-                    "\nfunction {}:{}",
-                    compiler.namespace, inline_scope.name
-                ));
-            }
-        } else {
-            eprintln!("Not an array");
-            exit(1);
+                )),
+            );
+            let variable = match_objects(variable);
+            inline_scope.variables.insert(
+                name.clone(),
+                Variable {
+                    name: name.clone(),
+                    value: variable.clone(),
+                    static_variable: false,
+                },
+            );
+            compiler.compile(&mut inline_scope);
+            scope.scopes.push(inline_scope.clone());
+            built_str.push_str(&format!(
+                "\nfunction {}:{}",
+                compiler.namespace, inline_scope.name
+            ));
         }
     }
 
@@ -492,8 +500,8 @@ pub fn compile_into_if_statement(
                 codes,
                 scope.functions.clone(),
             );
-            // add scoped variables
-            inline_scope.variables = scope.variables.clone();
+            inline_scope.variables = scope.variables.block(&inline_scope.name);
+            inline_scope.temporaries = scope.temporaries.above();
 
             let mut full_statement = String::new();
             for or_part in execute_statements.split("[OR]").into_iter() {
@@ -513,3 +521,64 @@ pub fn compile_into_if_statement(
 
     return (String::new(), None);
 }
+
+pub fn compile_into_execute_block(
+    steps: Vec<ExecuteSteps>,
+    code_block: ASTOperation,
+    scope: &Scope,
+    compiler: &Compiler,
+) -> (String, Option<Scope>) {
+    let subcommands = steps
+        .into_iter()
+        .map(execute_step_str)
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    if let ASTOperation::CodeBlock(code, _) = code_block {
+        let mut codes: Vec<ASTOperation> = code.clone();
+        if codes.is_empty() {
+            eprintln!("Empty code block.");
+            exit(1);
+        }
+        if let ASTOperation::Set(mult, _) = code[0].clone() {
+            codes = mult;
+        }
+        let mut inline_scope = Scope::new(
+            format!("{}.{}", scope.name, scope.scopes.len()),
+            compiler.namespace.clone(),
+            codes,
+            scope.functions.clone(),
+        );
+        // add scoped variables
+        inline_scope.variables = scope.variables.block(&inline_scope.name);
+        inline_scope.temporaries = scope.temporaries.above();
+
+        return (
+            format!(
+                "execute {} run function {}:{}\n",
+                subcommands, compiler.namespace, inline_scope.name
+            ),
+            Some(inline_scope),
+        );
+    }
+
+    (String::new(), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compile::compiler::tests::compiled;
+
+    #[test]
+    fn runs_execute_blocks_in_a_function() {
+        let outputs = compiled(
+            "as (@a[tag=foo]) at (@s) positioned (~ ~1 ~) {\n    /say hi\n}\nanchored (eyes) facing (@p) {\n    /say there\n}\n",
+        );
+        assert_eq!(
+            outputs["code"],
+            "execute as @a[tag=foo] at @s positioned ~ ~1 ~ run function test:code.0\nexecute anchored eyes facing entity @p eyes run function test:code.1"
+        );
+        assert_eq!(outputs["code.0"], "say hi");
+        assert_eq!(outputs["code.1"], "say there");
+    }
+}
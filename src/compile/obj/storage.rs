@@ -0,0 +1,611 @@
+use std::{any::Any, collections::HashMap, fmt::Display, process::exit, rc::Rc};
+
+use crate::compile::{
+    mcstatements::Statements,
+    nbt::{quote_snbt_string, validate_snbt, validate_snbt_compound},
+    objects::{
+        check_params, mk_function_map, mk_variable, own, statement, string_param, Object,
+        ObjectFunction, Objects,
+    },
+};
+
+use super::{
+    basic::{BooleanObject, DecimalObject, NumberObject, SetObject, StringObject},
+    block::is_resource_location,
+    blockpos::format_decimal,
+    list::ListObject,
+    std::VariableObject,
+};
+
+/// The type of the value stored at a storage path.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NbtType {
+    Byte,
+    Short,
+    Int,
+    Long,
+    Float,
+    Double,
+    String,
+    List,
+    Compound,
+}
+
+impl NbtType {
+    pub fn parse(str: &str) -> Option<NbtType> {
+        match str {
+            "byte" | "bool" => Some(NbtType::Byte),
+            "short" => Some(NbtType::Short),
+            "int" => Some(NbtType::Int),
+            "long" => Some(NbtType::Long),
+            "float" => Some(NbtType::Float),
+            "double" => Some(NbtType::Double),
+            "string" => Some(NbtType::String),
+            "list" => Some(NbtType::List),
+            "compound" => Some(NbtType::Compound),
+            _ => None,
+        }
+    }
+
+    /// Whether the result of a command can be stored into the type with
+    /// `execute store result storage`.
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            NbtType::Byte
+                | NbtType::Short
+                | NbtType::Int
+                | NbtType::Long
+                | NbtType::Float
+                | NbtType::Double
+        )
+    }
+
+    /// The suffix of a number literal of the type, such as `b` in `1b`.
+    fn suffix(&self) -> &'static str {
+        match self {
+            NbtType::Byte => "b",
+            NbtType::Short => "s",
+            NbtType::Long => "L",
+            NbtType::Float => "f",
+            NbtType::Double => "d",
+            _ => "",
+        }
+    }
+}
+
+impl Display for NbtType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            NbtType::Byte => "byte",
+            NbtType::Short => "short",
+            NbtType::Int => "int",
+            NbtType::Long => "long",
+            NbtType::Float => "float",
+            NbtType::Double => "double",
+            NbtType::String => "string",
+            NbtType::List => "list",
+            NbtType::Compound => "compound",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A command storage such as `new Storage("mypack:state")`, which can hold any NBT at
+/// runtime, unlike scoreboards which only hold integers.
+#[derive(Clone, Debug)]
+pub struct StorageObject {
+    pub name: String,
+}
+
+/// A typed path inside a storage, such as `storage.path("players[0].name", "string")`.
+#[derive(Clone, Debug)]
+pub struct StoragePathObject {
+    pub storage: String,
+    pub path: String,
+    pub nbt_type: NbtType,
+}
+
+/// Checks an NBT path such as `players[0].name` or `items[{id:"minecraft:stone"}]`. Only
+/// the overall shape is checked: no whitespace outside of quotes and balanced brackets.
+pub fn is_nbt_path(path: &str) -> bool {
+    let mut depth: Vec<char> = vec![];
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for char in path.chars() {
+        if let Some(open) = quote {
+            if escaped {
+                escaped = false;
+            } else if char == '\\' {
+                escaped = true;
+            } else if char == open {
+                quote = None;
+            }
+            continue;
+        }
+        match char {
+            '"' | '\'' => quote = Some(char),
+            '[' | '{' => depth.push(char),
+            ']' if depth.pop() == Some('[') => {}
+            '}' if depth.pop() == Some('{') => {}
+            ']' | '}' => return false,
+            c if c.is_whitespace() && depth.is_empty() => return false,
+            _ => {}
+        }
+    }
+    !path.is_empty() && depth.is_empty() && quote.is_none()
+}
+
+/// Reads the scoreboard value an object refers to, as a `(holder, objective)` pair. Variables
+/// are resolved to their scores before objects are given them.
+pub fn score_source(object: &Rc<dyn Object>) -> Option<(String, String)> {
+    match object.get_type() {
+        Objects::ScoreboardPlayerPair(objective, player, _) => Some((player, objective)),
+        _ => None,
+    }
+}
+
+/// Reads the optional scale of a conversion, which defaults to 1.
+fn scale_param(params: &[Rc<dyn Object>], index: usize, function: &str) -> String {
+    if params.len() <= index {
+        return "1".to_string();
+    }
+    if let Some(number) = params[index].as_any().downcast_ref::<NumberObject>() {
+        return number.value.to_string();
+    }
+    if let Some(number) = params[index].as_any().downcast_ref::<DecimalObject>() {
+        return format_decimal(number.value);
+    }
+    eprintln!(
+        "Incorrect argument type for function {}, expected a number as scale",
+        function
+    );
+    exit(1);
+}
+
+fn path_param(params: &[Rc<dyn Object>], index: usize, function: &str) -> String {
+    let path = string_param(params, index, function);
+    if !is_nbt_path(&path) {
+        eprintln!("Invalid NBT path {}", path);
+        exit(1);
+    }
+    path
+}
+
+fn type_param(params: &[Rc<dyn Object>], index: usize, function: &str) -> NbtType {
+    let nbt_type = string_param(params, index, function);
+    match NbtType::parse(&nbt_type) {
+        Some(nbt_type) => nbt_type,
+        None => {
+            eprintln!("Invalid NBT type {}", nbt_type);
+            exit(1);
+        }
+    }
+}
+
+fn type_error(function: &str, nbt_type: &NbtType) -> ! {
+    eprintln!(
+        "Incorrect argument type for function {}, expected a value of type {}",
+        function, nbt_type
+    );
+    exit(1);
+}
+
+/// Writes a compile time value as an SNBT literal of the given type.
+pub fn snbt_value(value: &Rc<dyn Object>, nbt_type: &NbtType, function: &str) -> String {
+    let any = value.as_any();
+    if let Some(number) = any.downcast_ref::<NumberObject>() {
+        if !nbt_type.is_numeric() {
+            type_error(function, nbt_type);
+        }
+        return format!("{}{}", number.value, nbt_type.suffix());
+    }
+    if let Some(number) = any.downcast_ref::<DecimalObject>() {
+        if *nbt_type != NbtType::Float && *nbt_type != NbtType::Double {
+            type_error(function, nbt_type);
+        }
+        return format!("{}{}", format_decimal(number.value), nbt_type.suffix());
+    }
+    if let Some(bool) = any.downcast_ref::<BooleanObject>() {
+        if *nbt_type != NbtType::Byte {
+            type_error(function, nbt_type);
+        }
+        return format!("{}b", if bool.value { 1 } else { 0 });
+    }
+    if let Some(str) = any.downcast_ref::<StringObject>() {
+        // lists and compounds are given as SNBT, such as "[1, 2]" or "{a: 1}"
+        let valid = match nbt_type {
+            NbtType::String => return quote_snbt_string(&str.value, '"'),
+            NbtType::List => {
+                str.value.trim_start().starts_with('[') && validate_snbt(&str.value).is_ok()
+            }
+            NbtType::Compound => validate_snbt_compound(&str.value).is_ok(),
+            _ => false,
+        };
+        if !valid {
+            eprintln!(
+                "Invalid {} {} in function {}",
+                nbt_type, str.value, function
+            );
+            exit(1);
+        }
+        return str.value.clone();
+    }
+    if let Some(set) = any.downcast_ref::<SetObject>() {
+        if *nbt_type != NbtType::List {
+            type_error(function, nbt_type);
+        }
+        let values = set
+            .values
+            .iter()
+            .map(|value| snbt_element(value, function))
+            .collect::<Vec<String>>();
+        return format!("[{}]", values.join(","));
+    }
+    type_error(function, nbt_type);
+}
+
+/// Writes an element of a list, whose type is taken from the value itself.
+fn snbt_element(value: &Rc<dyn Object>, function: &str) -> String {
+    let nbt_type = match value.get_type() {
+        Objects::Number(_) => NbtType::Int,
+        Objects::Decimal(_) => NbtType::Double,
+        Objects::Boolean(_) => NbtType::Byte,
+        Objects::String(_) => NbtType::String,
+        Objects::Array(_) => NbtType::List,
+        _ => {
+            eprintln!(
+                "Incorrect argument type for function {}, lists may only hold numbers, strings and lists",
+                function
+            );
+            exit(1);
+        }
+    };
+    snbt_value(value, &nbt_type, function)
+}
+
+impl StoragePathObject {
+    /// Calls one of the path's functions, such as `set`, as if it was called in the source.
+    pub fn call(&self, function: &str, params: Vec<Rc<dyn Object>>) -> Rc<dyn Object> {
+        let functions = self.get_functions();
+        let function = functions.get(function).expect("Unknown storage function");
+        function(params, Some(mk_variable(self.get_type(), Objects::Unknown)))
+    }
+
+    fn target(&self) -> String {
+        format!("storage {} {}", self.storage, self.path)
+    }
+
+    /// The source of a `data modify` command: another storage path or a literal value.
+    fn source(&self, value: &Rc<dyn Object>, function: &str) -> String {
+        if let Some(other) = value.as_any().downcast_ref::<StoragePathObject>() {
+            return format!("from {}", other.target());
+        }
+        format!("value {}", snbt_value(value, &self.nbt_type, function))
+    }
+
+    /// Stores the result of a command at the path, converting it to the path's type.
+    fn store(&self, command: &str, scale: &str, function: &str) -> String {
+        if !self.nbt_type.is_numeric() {
+            eprintln!(
+                "Function {} needs a numeric storage path, {} is a {}",
+                function, self.path, self.nbt_type
+            );
+            exit(1);
+        }
+        format!(
+            "execute store result {} {} {} run {}",
+            self.target(),
+            self.nbt_type,
+            scale,
+            command
+        )
+    }
+
+    /// The command that copies the path into a score.
+    pub fn to_score(&self, holder: &str, objective: &str, scale: &str) -> String {
+        let scale = if scale == "1" {
+            String::new()
+        } else {
+            format!(" {}", scale)
+        };
+        format!(
+            "execute store result score {} {} run data get {}{}",
+            holder,
+            objective,
+            self.target(),
+            scale
+        )
+    }
+}
+
+impl Object for StorageObject {
+    fn get_type(&self) -> Objects {
+        Objects::Storage(self.name.clone())
+    }
+    fn get_variables(&self) -> HashMap<String, Rc<VariableObject>> {
+        let mut map = HashMap::new();
+        map.insert(
+            "name".to_string(),
+            mk_variable(Objects::String(self.name.clone()), Objects::Unknown),
+        );
+        map
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
+        let mut map = mk_function_map();
+
+        map.insert(
+            "instantiate".to_string(),
+            Box::new(|params, _| {
+                check_params(&params, 1, 1, "Storage");
+                let name = string_param(&params, 0, "Storage");
+                if !is_resource_location(&name) {
+                    eprintln!("Invalid storage {}", name);
+                    exit(1);
+                }
+                let name = if name.contains(':') {
+                    name
+                } else {
+                    format!("minecraft:{}", name)
+                };
+                Rc::new(StorageObject { name })
+            }),
+        );
+
+        map.insert(
+            "path".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 2, 2, "path");
+                let path = path_param(&params, 0, "path");
+                let nbt_type = type_param(&params, 1, "path");
+                let storage: StorageObject = own(variable);
+                Rc::new(StoragePathObject {
+                    storage: storage.name,
+                    path,
+                    nbt_type,
+                })
+            }),
+        );
+
+        map.insert(
+            "list".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 2, 2, "list");
+                let path = path_param(&params, 0, "list");
+                let element = type_param(&params, 1, "list");
+                let storage: StorageObject = own(variable);
+                Rc::new(ListObject {
+                    storage: storage.name,
+                    path,
+                    element,
+                })
+            }),
+        );
+
+        map.insert(
+            "merge".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 1, 1, "merge");
+                let nbt = string_param(&params, 0, "merge");
+                if let Err(message) = validate_snbt_compound(&nbt) {
+                    eprintln!("{}", message);
+                    exit(1);
+                }
+                let storage: StorageObject = own(variable);
+                statement(format!("data merge storage {} {}", storage.name, nbt))
+            }),
+        );
+
+        map.insert(
+            "remove".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 1, 1, "remove");
+                let path = path_param(&params, 0, "remove");
+                let storage: StorageObject = own(variable);
+                statement(format!("data remove storage {} {}", storage.name, path))
+            }),
+        );
+
+        map
+    }
+}
+
+impl Object for StoragePathObject {
+    fn get_type(&self) -> Objects {
+        Objects::StoragePath(
+            self.storage.clone(),
+            self.path.clone(),
+            self.nbt_type.clone(),
+        )
+    }
+    fn get_variables(&self) -> HashMap<String, Rc<VariableObject>> {
+        let mut map = HashMap::new();
+        map.insert(
+            "storage".to_string(),
+            mk_variable(Objects::Storage(self.storage.clone()), Objects::Unknown),
+        );
+        map.insert(
+            "path".to_string(),
+            mk_variable(Objects::String(self.path.clone()), Objects::Unknown),
+        );
+        map
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
+        let mut map = mk_function_map();
+
+        map.insert(
+            "at".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 2, 2, "at");
+                let child = path_param(&params, 0, "at");
+                let nbt_type = type_param(&params, 1, "at");
+                let own: StoragePathObject = own(variable);
+                let path = if child.starts_with('[') || child.starts_with('{') {
+                    format!("{}{}", own.path, child)
+                } else {
+                    format!("{}.{}", own.path, child)
+                };
+                Rc::new(StoragePathObject {
+                    storage: own.storage,
+                    path,
+                    nbt_type,
+                })
+            }),
+        );
+
+        map.insert(
+            "set".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 1, 1, "set");
+                let own: StoragePathObject = own(variable);
+                // scores are converted to the type of the path
+                if let Some((holder, objective)) = score_source(&params[0]) {
+                    return statement(own.store(
+                        &format!("scoreboard players get {} {}", holder, objective),
+                        "1",
+                        "set",
+                    ));
+                }
+                statement(format!(
+                    "data modify {} set {}",
+                    own.target(),
+                    own.source(&params[0], "set")
+                ))
+            }),
+        );
+
+        for (function, mode) in [("append", "append"), ("prepend", "prepend")] {
+            map.insert(
+                function.to_string(),
+                Box::new(move |params, variable| {
+                    check_params(&params, 1, 1, function);
+                    let own: StoragePathObject = own(variable);
+                    if own.nbt_type != NbtType::List {
+                        eprintln!(
+                            "Function {} needs a list storage path, {} is a {}",
+                            function, own.path, own.nbt_type
+                        );
+                        exit(1);
+                    }
+                    let source = if let Some(other) =
+                        params[0].as_any().downcast_ref::<StoragePathObject>()
+                    {
+                        format!("from {}", other.target())
+                    } else {
+                        format!("value {}", snbt_element(&params[0], function))
+                    };
+                    statement(format!("data modify {} {} {}", own.target(), mode, source))
+                }),
+            );
+        }
+
+        map.insert(
+            "merge".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 1, 1, "merge");
+                let own: StoragePathObject = own(variable);
+                if own.nbt_type != NbtType::Compound {
+                    eprintln!(
+                        "Function merge needs a compound storage path, {} is a {}",
+                        own.path, own.nbt_type
+                    );
+                    exit(1);
+                }
+                statement(format!(
+                    "data modify {} merge {}",
+                    own.target(),
+                    own.source(&params[0], "merge")
+                ))
+            }),
+        );
+
+        map.insert(
+            "remove".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 0, 0, "remove");
+                let own: StoragePathObject = own(variable);
+                statement(format!("data remove {}", own.target()))
+            }),
+        );
+
+        map.insert(
+            "get".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 0, 1, "get");
+                let scale = scale_param(&params, 0, "get");
+                let own: StoragePathObject = own(variable);
+                if scale == "1" {
+                    statement(format!("data get {}", own.target()))
+                } else {
+                    statement(format!("data get {} {}", own.target(), scale))
+                }
+            }),
+        );
+
+        map.insert(
+            "to_score".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 1, 2, "to_score");
+                let score = score_source(&params[0]);
+                if score.is_none() {
+                    eprintln!("Incorrect argument type for function to_score, expected a score");
+                    exit(1);
+                }
+                let (holder, objective) = score.unwrap();
+                let scale = scale_param(&params, 1, "to_score");
+                statement(own::<StoragePathObject>(variable).to_score(&holder, &objective, &scale))
+            }),
+        );
+
+        map.insert(
+            "store".to_string(),
+            Box::new(|params, variable| {
+                // stores the result of a command, such as the get() of another path
+                check_params(&params, 1, 2, "store");
+                let command = match params[0].get_type() {
+                    Objects::MCStatement(Statements::Raw(command) | Statements::Query(command)) => {
+                        command
+                    }
+                    _ => {
+                        eprintln!("Incorrect argument type for function store, expected a command");
+                        exit(1);
+                    }
+                };
+                let scale = scale_param(&params, 1, "store");
+                statement(own::<StoragePathObject>(variable).store(command.trim(), &scale, "store"))
+            }),
+        );
+
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compile::compiler::tests::compiled;
+
+    #[test]
+    fn keeps_values_in_storage() {
+        let outputs = compiled(
+            "let st = new Storage(\"mypack:state\");\nlet name = st.path(\"player.name\", \"string\");\nname.set(\"Steve\");\nlet hp = st.path(\"player.hp\", \"int\");\nhp.set(20);\nlet sc = new Scoreboard(\"points\", \"dummy\").get_player(\"@s\");\nhp.set(sc);\nhp.to_score(sc);\nst.path(\"copy\", \"int\").store(hp.get());\n",
+        );
+        assert_eq!(
+            outputs["code"].lines().collect::<Vec<&str>>(),
+            [
+                "data modify storage mypack:state player.name set value \"Steve\"",
+                "data modify storage mypack:state player.hp set value 20",
+                "execute store result storage mypack:state player.hp int 1 run scoreboard players get @s points",
+                "execute store result score @s points run data get storage mypack:state player.hp",
+                "execute store result storage mypack:state copy int 1 run data get storage mypack:state player.hp",
+            ]
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use std::{any::Any, collections::HashMap, process::exit, rc::Rc};
+
+use crate::{
+    compile::{
+        checker::argument_count,
+        compiler::Function,
+        objects::{mk_function_map, mk_variable, Object, ObjectFunction, Objects},
+    },
+    errors::{
+        associate::CodeAssociate,
+        error::{compile_error, CompileErrors},
+    },
+};
+
+use super::{std::VariableObject, storage::NbtType};
+
+/// Where the values of a struct field live.
+#[derive(Clone, Debug)]
+pub enum FieldKind {
+    /// `int` and `bool` fields, one objective per field.
+    Score,
+    /// Any other NBT type, in the `<namespace>:structs` storage.
+    Storage(NbtType),
+}
+
+impl FieldKind {
+    pub fn parse(str: &str) -> Option<FieldKind> {
+        match str {
+            "int" | "bool" => Some(FieldKind::Score),
+            _ => NbtType::parse(str).map(FieldKind::Storage),
+        }
+    }
+}
+
+/// A struct declared in the source, such as `struct Player { health: int }`.
+#[derive(Clone, Debug)]
+pub struct StructDefinition {
+    pub name: String,
+    pub fields: Vec<(String, FieldKind)>,
+    pub methods: HashMap<String, Function>,
+    /// The objective holding each score field, e.g. `game.Player.health`.
+    pub objectives: HashMap<String, String>,
+}
+
+impl StructDefinition {
+    /// The objective holding a score field.
+    pub fn objective(&self, field: &str) -> String {
+        self.objectives[field].clone()
+    }
+}
+
+/// An instance of a struct. Created with an entity (`new Player(@s)`), its score fields are
+/// that entity's scores; otherwise they belong to a fake player named after the instance.
+/// Storage fields are always kept per instance.
+#[derive(Clone, Debug)]
+pub struct StructObject {
+    pub definition: StructDefinition,
+    /// The name the instance's values are kept under, e.g. `player0`.
+    pub instance: String,
+    /// The selector of the entity the instance belongs to.
+    pub owner: Option<String>,
+    pub namespace: String,
+}
+
+impl StructObject {
+    fn holder(&self) -> String {
+        match &self.owner {
+            Some(owner) => owner.clone(),
+            None => format!("#{}", self.instance),
+        }
+    }
+
+    /// The score or storage path of a field.
+    pub fn field(&self, name: &str, kind: &FieldKind) -> Objects {
+        match kind {
+            FieldKind::Score => Objects::ScoreboardPlayerPair(
+                self.definition.objective(name),
+                self.holder(),
+                Box::new(Objects::Number(0)),
+            ),
+            FieldKind::Storage(nbt_type) => Objects::StoragePath(
+                format!("{}:structs", self.namespace),
+                format!("{}.{}", self.instance, name),
+                nbt_type.clone(),
+            ),
+        }
+    }
+
+    /// Binds the instance to the entity given to `new`, if any. Without one it belongs to
+    /// a fake player.
+    pub fn instantiate(&self, params: &[Objects], associate: &CodeAssociate) -> StructObject {
+        if params.len() > 1 {
+            compile_error(CompileErrors::ArgumentCount(
+                argument_count(&self.definition.name, 0, 1, params.len()),
+                associate.clone(),
+            ));
+            exit(1);
+        }
+        let owner = match params.first() {
+            None => None,
+            Some(Objects::Entity(selector)) => Some(selector.clone()),
+            Some(_) => {
+                compile_error(CompileErrors::TypeMismatch(
+                    format!(
+                        "Struct {} can only be owned by an entity",
+                        self.definition.name
+                    ),
+                    associate.clone(),
+                ));
+                exit(1);
+            }
+        };
+        StructObject {
+            owner,
+            ..self.clone()
+        }
+    }
+
+    /// The instance as its methods see it: they run as the owner, so it is `@s` there.
+    pub fn as_self(&self) -> StructObject {
+        StructObject {
+            owner: self.owner.as_ref().map(|_| "@s".to_string()),
+            ..self.clone()
+        }
+    }
+}
+
+impl Object for StructObject {
+    fn get_type(&self) -> Objects {
+        Objects::Struct(self.clone())
+    }
+    fn get_variables(&self) -> HashMap<String, Rc<VariableObject>> {
+        let mut map = HashMap::new();
+        for (name, kind) in &self.definition.fields {
+            map.insert(
+                name.clone(),
+                mk_variable(self.field(name, kind), Objects::Unknown),
+            );
+        }
+        if let Some(owner) = &self.owner {
+            map.insert(
+                "owner".to_string(),
+                mk_variable(Objects::Entity(owner.clone()), Objects::Unknown),
+            );
+        }
+        map
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
+        // methods are user code, which the compiler calls itself
+        mk_function_map()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compile::compiler::tests::compiled;
+
+    #[test]
+    fn assigns_fields_inside_and_outside_of_methods() {
+        let outputs = compiled(
+            "struct Player {\n    health: int\n    fn heal(amount: int) {\n        self.health = self.health + amount;\n    }\n}\nlet p = new Player(@s);\nlet bonus = 2;\np.health = 20;\np.health = p.health - bonus;\np.heal(5);\n",
+        );
+        assert_eq!(
+            outputs["code"],
+            "scoreboard objectives add test.code.bonus dummy\nscoreboard players set $test test.code.bonus 2\nscoreboard players set @s test.Player.health 20\nscoreboard players operation @s test.Player.health -= $test test.code.bonus\nexecute as @s run function test:code.0"
+        );
+        assert_eq!(
+            outputs["code.0"],
+            "scoreboard objectives add test.code.0.amount dummy\nscoreboard players set $test test.code.0.amount 5\nscoreboard players operation @s test.Player.health += $test test.code.0.amount"
+        );
+    }
+}
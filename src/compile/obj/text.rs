@@ -0,0 +1,456 @@
+use std::{any::Any, collections::HashMap, process::exit, rc::Rc};
+
+use crate::compile::{
+    mcstatements::{MinecraftStatementObject, Statements},
+    objects::{check_params, mk_function_map, own, string_param, Object, ObjectFunction, Objects},
+    version::Feature,
+};
+
+use super::{
+    basic::{BooleanObject, DecimalObject, NumberObject, StringObject},
+    block::is_resource_location,
+    blockpos::{format_decimal, BlockPosObject},
+    entity::EntityObject,
+    std::VariableObject,
+    storage::StorageObject,
+};
+
+const COLORS: [&str; 16] = [
+    "black",
+    "dark_blue",
+    "dark_green",
+    "dark_aqua",
+    "dark_red",
+    "dark_purple",
+    "gold",
+    "gray",
+    "dark_gray",
+    "blue",
+    "green",
+    "aqua",
+    "red",
+    "light_purple",
+    "yellow",
+    "white",
+];
+
+/// What a text component displays.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TextContent {
+    Text(String),
+    Score(String, String),
+    Selector(String),
+    /// The NBT path, and the kind (`entity`, `block` or `storage`) and name of its source.
+    Nbt(String, String, String),
+}
+
+/// A JSON text component, built with `text("...")` and used by `tellraw`, `title` and
+/// bossbar names. Components are rendered when compiled, as the click and hover event
+/// format depends on the target pack format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextObject {
+    pub content: TextContent,
+    pub color: Option<String>,
+    pub formatting: Vec<(String, bool)>,
+    pub click: Option<(String, String)>,
+    pub hover: Option<Box<TextObject>>,
+    pub extra: Vec<TextObject>,
+}
+
+pub fn json_string(value: &str) -> String {
+    let mut escaped = String::new();
+    for char in value.chars() {
+        match char {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            char if (char as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", char as u32)),
+            char => escaped.push(char),
+        }
+    }
+    format!("\"{}\"", escaped)
+}
+
+impl TextObject {
+    pub fn new(content: TextContent) -> TextObject {
+        TextObject {
+            content,
+            color: None,
+            formatting: vec![],
+            click: None,
+            hover: None,
+            extra: vec![],
+        }
+    }
+
+    /// Builds a component out of a value: scores for scoreboard variables, selectors for
+    /// entities and plain text for everything else that can be printed.
+    pub fn from_object(object: &Rc<dyn Object>) -> Option<TextObject> {
+        if let Some(text) = object.as_any().downcast_ref::<TextObject>() {
+            return Some(text.clone());
+        }
+        if let Some(str) = object.as_any().downcast_ref::<StringObject>() {
+            return Some(TextObject::new(TextContent::Text(str.value.clone())));
+        }
+        if let Some(number) = object.as_any().downcast_ref::<NumberObject>() {
+            return Some(TextObject::new(TextContent::Text(number.value.to_string())));
+        }
+        if let Some(number) = object.as_any().downcast_ref::<DecimalObject>() {
+            return Some(TextObject::new(TextContent::Text(format_decimal(
+                number.value,
+            ))));
+        }
+        if let Some(bool) = object.as_any().downcast_ref::<BooleanObject>() {
+            return Some(TextObject::new(TextContent::Text(bool.value.to_string())));
+        }
+        match object.get_type() {
+            Objects::ScoreboardPlayerPair(objective, player, _) => {
+                Some(TextObject::new(TextContent::Score(player, objective)))
+            }
+            Objects::Entity(selector) => Some(TextObject::new(TextContent::Selector(selector))),
+            Objects::StoragePath(storage, path, _) => Some(TextObject::new(TextContent::Nbt(
+                path,
+                "storage".to_string(),
+                storage,
+            ))),
+            _ => None,
+        }
+    }
+
+    /// Renders the component as JSON for the given pack format.
+    pub fn render(&self, pack_format: u32) -> String {
+        let mut fields = vec![];
+        match &self.content {
+            TextContent::Text(text) => fields.push(format!("\"text\":{}", json_string(text))),
+            TextContent::Score(name, objective) => fields.push(format!(
+                "\"score\":{{\"name\":{},\"objective\":{}}}",
+                json_string(name),
+                json_string(objective)
+            )),
+            TextContent::Selector(selector) => {
+                fields.push(format!("\"selector\":{}", json_string(selector)))
+            }
+            TextContent::Nbt(path, kind, source) => {
+                fields.push(format!("\"nbt\":{}", json_string(path)));
+                fields.push(format!("\"{}\":{}", kind, json_string(source)));
+            }
+        }
+        if let Some(color) = &self.color {
+            fields.push(format!("\"color\":{}", json_string(color)));
+        }
+        for (format, value) in self.formatting.iter() {
+            fields.push(format!("\"{}\":{}", format, value));
+        }
+        let snake_case = Feature::SnakeCaseEvents.supported_by(pack_format);
+        if let Some((action, value)) = &self.click {
+            let (event, value_field) = match action.as_str() {
+                _ if !snake_case => ("clickEvent", "value"),
+                "run_command" | "suggest_command" => ("click_event", "command"),
+                "open_url" => ("click_event", "url"),
+                _ => ("click_event", "value"),
+            };
+            fields.push(format!(
+                "\"{}\":{{\"action\":\"{}\",\"{}\":{}}}",
+                event,
+                action,
+                value_field,
+                json_string(value)
+            ));
+        }
+        if let Some(hover) = &self.hover {
+            let (event, contents_field) = if snake_case {
+                ("hover_event", "value")
+            } else {
+                ("hoverEvent", "contents")
+            };
+            fields.push(format!(
+                "\"{}\":{{\"action\":\"show_text\",\"{}\":{}}}",
+                event,
+                contents_field,
+                hover.render(pack_format)
+            ));
+        }
+        if !self.extra.is_empty() {
+            let extra = self
+                .extra
+                .iter()
+                .map(|text| text.render(pack_format))
+                .collect::<Vec<String>>();
+            fields.push(format!("\"extra\":[{}]", extra.join(",")));
+        }
+        format!("{{{}}}", fields.join(","))
+    }
+}
+
+fn text_param(params: &[Rc<dyn Object>], index: usize, function: &str) -> TextObject {
+    let text = TextObject::from_object(&params[index]);
+    if text.is_none() {
+        eprintln!(
+            "Incorrect argument type for function {}, expected text",
+            function
+        );
+        exit(1);
+    }
+    text.unwrap()
+}
+
+fn selector_param(params: &[Rc<dyn Object>], index: usize, function: &str) -> String {
+    if let Some(entity) = params[index].as_any().downcast_ref::<EntityObject>() {
+        return entity.selector.clone();
+    }
+    if let Objects::Variable(value, _) = params[index].get_type() {
+        if let Objects::Entity(selector) = *value {
+            return selector;
+        }
+    }
+    eprintln!(
+        "Incorrect argument type for function {}, expected an entity",
+        function
+    );
+    exit(1);
+}
+
+fn text_statement(prefix: String, text: TextObject) -> Rc<dyn Object> {
+    Rc::new(MinecraftStatementObject {
+        value: Statements::Text(prefix, text, String::new()),
+    })
+}
+
+/// A formatting method such as `bold()`, which takes an optional boolean to turn the
+/// formatting off again.
+fn formatting_function(format: &'static str) -> ObjectFunction {
+    Box::new(move |params, variable| {
+        check_params(&params, 0, 1, format);
+        let value = if params.is_empty() {
+            true
+        } else {
+            match params[0].as_any().downcast_ref::<BooleanObject>() {
+                Some(value) => value.value,
+                None => {
+                    eprintln!(
+                        "Incorrect argument type for function {}, expected boolean",
+                        format
+                    );
+                    exit(1);
+                }
+            }
+        };
+        let mut text: TextObject = own(variable);
+        text.formatting.retain(|(name, _)| name != format);
+        text.formatting.push((format.to_string(), value));
+        Rc::new(text)
+    })
+}
+
+fn click_function(function: &'static str, action: &'static str) -> ObjectFunction {
+    Box::new(move |params, variable| {
+        check_params(&params, 1, 1, function);
+        let mut value = string_param(&params, 0, function);
+        if action == "run_command" && !value.starts_with('/') {
+            value.insert(0, '/');
+        }
+        let mut text: TextObject = own(variable);
+        text.click = Some((action.to_string(), value));
+        Rc::new(text)
+    })
+}
+
+/// The functions that can be called without an object: `text`, `nbt`, `tellraw`, `title`,
+/// `subtitle`, `actionbar`, `bossbar` and `bossbar_name`.
+pub fn builtin_functions() -> HashMap<String, ObjectFunction> {
+    let mut map = mk_function_map();
+
+    map.insert(
+        "text".to_string(),
+        Box::new(|params, _| {
+            check_params(&params, 1, 1, "text");
+            Rc::new(text_param(&params, 0, "text"))
+        }),
+    );
+
+    map.insert(
+        "nbt".to_string(),
+        Box::new(|params, _| {
+            check_params(&params, 2, 2, "nbt");
+            let path = string_param(&params, 1, "nbt");
+            let source = params[0].as_any();
+            let (kind, source) = if let Some(position) = source.downcast_ref::<BlockPosObject>() {
+                ("block", position.to_string())
+            } else if let Some(storage) = source.downcast_ref::<StringObject>() {
+                if !is_resource_location(&storage.value) {
+                    eprintln!("Invalid storage {} in function nbt", storage.value);
+                    exit(1);
+                }
+                ("storage", storage.value.clone())
+            } else if let Some(storage) = source.downcast_ref::<StorageObject>() {
+                ("storage", storage.name.clone())
+            } else {
+                ("entity", selector_param(&params, 0, "nbt"))
+            };
+            Rc::new(TextObject::new(TextContent::Nbt(
+                path,
+                kind.to_string(),
+                source,
+            )))
+        }),
+    );
+
+    map.insert(
+        "tellraw".to_string(),
+        Box::new(|params, _| {
+            check_params(&params, 2, 2, "tellraw");
+            let selector = selector_param(&params, 0, "tellraw");
+            let text = text_param(&params, 1, "tellraw");
+            text_statement(format!("tellraw {} ", selector), text)
+        }),
+    );
+
+    for display in ["title", "subtitle", "actionbar"] {
+        map.insert(
+            display.to_string(),
+            Box::new(move |params, _| {
+                check_params(&params, 2, 2, display);
+                let selector = selector_param(&params, 0, display);
+                let text = text_param(&params, 1, display);
+                text_statement(format!("title {} {} ", selector, display), text)
+            }),
+        );
+    }
+
+    map.insert(
+        "bossbar".to_string(),
+        Box::new(|params, _| {
+            check_params(&params, 2, 2, "bossbar");
+            let id = string_param(&params, 0, "bossbar");
+            if !is_resource_location(&id) {
+                eprintln!("Invalid bossbar id {}", id);
+                exit(1);
+            }
+            let name = text_param(&params, 1, "bossbar");
+            text_statement(format!("bossbar add {} ", id), name)
+        }),
+    );
+
+    map.insert(
+        "bossbar_name".to_string(),
+        Box::new(|params, _| {
+            check_params(&params, 2, 2, "bossbar_name");
+            let id = string_param(&params, 0, "bossbar_name");
+            if !is_resource_location(&id) {
+                eprintln!("Invalid bossbar id {}", id);
+                exit(1);
+            }
+            let name = text_param(&params, 1, "bossbar_name");
+            text_statement(format!("bossbar set {} name ", id), name)
+        }),
+    );
+
+    map
+}
+
+impl Object for TextObject {
+    fn get_type(&self) -> Objects {
+        Objects::Text(self.clone())
+    }
+    fn get_variables(&self) -> HashMap<String, Rc<VariableObject>> {
+        HashMap::new()
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
+        let mut map = mk_function_map();
+
+        map.insert(
+            "color".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 1, 1, "color");
+                let color = string_param(&params, 0, "color");
+                let hex = color.len() == 7
+                    && color.starts_with('#')
+                    && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+                if !hex && !COLORS.contains(&color.as_str()) {
+                    eprintln!("Invalid color {}", color);
+                    exit(1);
+                }
+                let mut text: TextObject = own(variable);
+                text.color = Some(color);
+                Rc::new(text)
+            }),
+        );
+
+        for format in [
+            "bold",
+            "italic",
+            "underlined",
+            "strikethrough",
+            "obfuscated",
+        ] {
+            map.insert(format.to_string(), formatting_function(format));
+        }
+
+        map.insert(
+            "click_run".to_string(),
+            click_function("click_run", "run_command"),
+        );
+        map.insert(
+            "click_suggest".to_string(),
+            click_function("click_suggest", "suggest_command"),
+        );
+        map.insert(
+            "click_url".to_string(),
+            click_function("click_url", "open_url"),
+        );
+        map.insert(
+            "click_copy".to_string(),
+            click_function("click_copy", "copy_to_clipboard"),
+        );
+
+        map.insert(
+            "hover".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 1, 1, "hover");
+                let hover = text_param(&params, 0, "hover");
+                let mut text: TextObject = own(variable);
+                text.hover = Some(Box::new(hover));
+                Rc::new(text)
+            }),
+        );
+
+        map.insert(
+            "append".to_string(),
+            Box::new(|params, variable| {
+                check_params(&params, 1, 1, "append");
+                let extra = text_param(&params, 0, "append");
+                let mut text: TextObject = own(variable);
+                text.extra.push(extra);
+                Rc::new(text)
+            }),
+        );
+
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compile::compiler::tests::compiled;
+
+    #[test]
+    fn prints_text_components() {
+        let outputs = compiled(
+            "let points = 3;\ntellraw(@a, \"Points: {points}\");\ntellraw(@a, text(\"Shop\").color(\"gold\").bold().click_run(\"/trigger shop\"));\ntitle(@s, text(\"Go\").append(nbt(@s, \"Health\")));\n",
+        );
+        let commands = outputs["code"].lines().skip(2).collect::<Vec<&str>>();
+        assert_eq!(
+            commands,
+            [
+                "tellraw @a {\"text\":\"\",\"extra\":[{\"text\":\"Points: \"},{\"score\":{\"name\":\"$test\",\"objective\":\"test.code.points\"}}]}",
+                "tellraw @a {\"text\":\"Shop\",\"color\":\"gold\",\"bold\":true,\"clickEvent\":{\"action\":\"run_command\",\"value\":\"/trigger shop\"}}",
+                "title @s title {\"text\":\"Go\",\"extra\":[{\"nbt\":\"Health\",\"entity\":\"@s\"}]}",
+            ]
+        );
+    }
+}
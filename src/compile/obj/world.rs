@@ -0,0 +1,252 @@
+use std::{any::Any, collections::HashMap, process::exit, rc::Rc};
+
+use crate::compile::{
+    mcstatements::{MinecraftStatementObject, Statements},
+    nbt::validate_snbt_compound,
+    objects::{
+        check_params, mk_function_map, statement, string_param, Object, ObjectFunction, Objects,
+    },
+};
+
+use super::{
+    block::{is_resource_location, parse_block},
+    blockpos::BlockPosObject,
+    item::{count_suffix, is_slot, ItemObject, ItemSyntax},
+    std::VariableObject,
+};
+
+/// The `world` builtin, which exposes the block level commands.
+#[derive(Clone, Debug)]
+pub struct WorldObject {}
+
+fn position_param(params: &[Rc<dyn Object>], index: usize, function: &str) -> BlockPosObject {
+    let position = params[index].as_any().downcast_ref::<BlockPosObject>();
+    if position.is_none() {
+        eprintln!(
+            "Incorrect argument type for function {}, expected BlockPos",
+            function
+        );
+        exit(1);
+    }
+    position.unwrap().clone()
+}
+
+fn block_param(params: &[Rc<dyn Object>], index: usize, function: &str) -> String {
+    let block = string_param(params, index, function);
+    match parse_block(&block, false) {
+        Ok(block) => block.to_string(),
+        Err(message) => {
+            eprintln!(
+                "Invalid block {} in function {}: {}",
+                block, function, message
+            );
+            exit(1);
+        }
+    }
+}
+
+fn mode_param(
+    params: &[Rc<dyn Object>],
+    index: usize,
+    function: &str,
+    modes: &[&str],
+) -> Option<String> {
+    if params.len() <= index {
+        return None;
+    }
+    let mode = string_param(params, index, function);
+    if !modes.contains(&mode.as_str()) {
+        eprintln!(
+            "Invalid mode {} for function {}, expected one of: {}",
+            mode,
+            function,
+            modes.join(", ")
+        );
+        exit(1);
+    }
+    Some(mode)
+}
+
+impl Object for WorldObject {
+    fn get_type(&self) -> Objects {
+        Objects::World
+    }
+    fn get_variables(&self) -> HashMap<String, Rc<VariableObject>> {
+        HashMap::new()
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_functions(&self) -> HashMap<String, ObjectFunction> {
+        let mut map = mk_function_map();
+
+        map.insert(
+            "setblock".to_string(),
+            Box::new(|params, _| {
+                check_params(&params, 2, 3, "setblock");
+                let position = position_param(&params, 0, "setblock");
+                let block = block_param(&params, 1, "setblock");
+                let mode = mode_param(&params, 2, "setblock", &["replace", "destroy", "keep"]);
+                let mut command = format!("setblock {} {}", position, block);
+                if let Some(mode) = mode {
+                    command.push_str(&format!(" {}", mode));
+                }
+                statement(command)
+            }),
+        );
+
+        map.insert(
+            "fill".to_string(),
+            Box::new(|params, _| {
+                check_params(&params, 3, 5, "fill");
+                let from = position_param(&params, 0, "fill");
+                let to = position_param(&params, 1, "fill");
+                let block = block_param(&params, 2, "fill");
+                let mode = mode_param(
+                    &params,
+                    3,
+                    "fill",
+                    &["replace", "destroy", "hollow", "keep", "outline"],
+                );
+                let mut command = format!("fill {} {} {}", from, to, block);
+                if let Some(mode) = mode {
+                    command.push_str(&format!(" {}", mode));
+                    // fill(from, to, block, "replace", filter) only replaces matching blocks
+                    if params.len() == 5 {
+                        if mode != "replace" {
+                            eprintln!("A block filter can only be used with the replace mode");
+                            exit(1);
+                        }
+                        let filter = string_param(&params, 4, "fill");
+                        match parse_block(&filter, true) {
+                            Ok(filter) => command.push_str(&format!(" {}", filter)),
+                            Err(message) => {
+                                eprintln!("Invalid block {} in function fill: {}", filter, message);
+                                exit(1);
+                            }
+                        }
+                    }
+                }
+                statement(command)
+            }),
+        );
+
+        map.insert(
+            "clone".to_string(),
+            Box::new(|params, _| {
+                check_params(&params, 3, 5, "clone");
+                let from = position_param(&params, 0, "clone");
+                let to = position_param(&params, 1, "clone");
+                let destination = position_param(&params, 2, "clone");
+                let modes = ["force", "move", "normal"];
+                let mut command = format!("clone {} {} {}", from, to, destination);
+                if params.len() > 3 {
+                    // the mask is `replace`, `masked` or a block predicate to clone only
+                    // matching blocks, and can be left out before a mode
+                    let mask = string_param(&params, 3, "clone");
+                    let mask = match mask.as_str() {
+                        "replace" | "masked" => mask,
+                        mode if modes.contains(&mode) && params.len() == 4 => {
+                            format!("replace {}", mode)
+                        }
+                        _ => match parse_block(&mask, true) {
+                            Ok(filter) => format!("filtered {}", filter),
+                            Err(message) => {
+                                eprintln!("Invalid block {} in function clone: {}", mask, message);
+                                exit(1);
+                            }
+                        },
+                    };
+                    command.push_str(&format!(" {}", mask));
+                }
+                if let Some(mode) = mode_param(&params, 4, "clone", &modes) {
+                    command.push_str(&format!(" {}", mode));
+                }
+                statement(command)
+            }),
+        );
+
+        map.insert(
+            "summon".to_string(),
+            Box::new(|params, _| {
+                check_params(&params, 2, 3, "summon");
+                // summon(item, pos) drops an item entity
+                if let Some(item) = params[0].as_any().downcast_ref::<ItemObject>() {
+                    check_params(&params, 2, 2, "summon");
+                    let position = position_param(&params, 1, "summon");
+                    return Rc::new(MinecraftStatementObject {
+                        value: Statements::Item(
+                            format!("summon minecraft:item {} ", position),
+                            item.clone(),
+                            ItemSyntax::Entity,
+                            String::new(),
+                        ),
+                    });
+                }
+                let entity = string_param(&params, 0, "summon");
+                if !is_resource_location(&entity) {
+                    eprintln!("Invalid entity type {} in function summon", entity);
+                    exit(1);
+                }
+                let position = position_param(&params, 1, "summon");
+                let mut command = format!("summon {} {}", entity, position);
+                if params.len() == 3 {
+                    let nbt = string_param(&params, 2, "summon");
+                    if let Err(message) = validate_snbt_compound(&nbt) {
+                        eprintln!("Invalid NBT in function summon: {}", message);
+                        exit(1);
+                    }
+                    command.push_str(&format!(" {}", nbt));
+                }
+                statement(command)
+            }),
+        );
+
+        map.insert(
+            "replace_item".to_string(),
+            Box::new(|params, _| {
+                check_params(&params, 3, 3, "replace_item");
+                let position = position_param(&params, 0, "replace_item");
+                let slot = string_param(&params, 1, "replace_item");
+                if !is_slot(&slot) {
+                    eprintln!("Invalid slot {} in function replace_item", slot);
+                    exit(1);
+                }
+                let item = params[2].as_any().downcast_ref::<ItemObject>();
+                if item.is_none() {
+                    eprintln!("Incorrect argument type for function replace_item, expected Item");
+                    exit(1);
+                }
+                let item = item.unwrap();
+                Rc::new(MinecraftStatementObject {
+                    value: Statements::Item(
+                        format!("item replace block {} {} with ", position, slot),
+                        item.clone(),
+                        ItemSyntax::Stack,
+                        count_suffix(item.count),
+                    ),
+                })
+            }),
+        );
+
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compile::compiler::tests::compiled;
+
+    #[test]
+    fn changes_and_tests_blocks() {
+        let outputs = compiled(
+            "let pos = new BlockPos(~, ~-1, ~);\nworld.setblock(pos, \"chest[facing=north]{Lock:\\\"key\\\"}\");\nworld.fill(new BlockPos(0, 0, 0), new BlockPos(5, 5, 5), \"stone\", \"replace\", \"#minecraft:logs\");\nworld.clone(new BlockPos(0, 0, 0), new BlockPos(5, 5, 5), pos, \"masked\", \"move\");\nif block (~ ~1 ~) != \"#minecraft:logs\" && block pos == \"air\" {\n    world.setblock(pos, \"dirt\", \"keep\");\n}\n",
+        );
+        assert_eq!(
+            outputs["code"],
+            "setblock ~ ~-1 ~ minecraft:chest[facing=north]{Lock:\"key\"}\nfill 0 0 0 5 5 5 minecraft:stone replace #minecraft:logs\nclone 0 0 0 5 5 5 ~ ~-1 ~ masked move\nexecute unless block ~ ~1 ~ #minecraft:logs if block ~ ~-1 ~ minecraft:air run function test:code.0"
+        );
+        assert_eq!(outputs["code.0"], "setblock ~ ~-1 ~ minecraft:dirt keep");
+    }
+}
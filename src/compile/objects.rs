@@ -1,18 +1,26 @@
-use std::{any::Any, collections::HashMap, fmt::Debug, rc::Rc};
+use std::{any::Any, collections::HashMap, fmt::Debug, process::exit, rc::Rc};
 
 use crate::ast::operations::{ASTOperation, Operator};
 
 use super::{
-    mcstatements::{MinecraftStatementObject, Statements},
+    checker::argument_count,
+    mcstatements::{ExecuteSteps, MinecraftStatementObject, Statements},
     obj::{
-        basic::{BooleanObject, NullObject, NumberObject, SetObject, StringObject},
-        blockpos::BlockPosObject,
+        basic::{BooleanObject, DecimalObject, NullObject, NumberObject, SetObject, StringObject},
+        blockpos::{BlockPosObject, Coordinate, CoordinateObject},
+        dimension::DimensionObject,
         entity::EntityObject,
+        item::ItemObject,
+        list::ListObject,
         scoreboard::{ScoreboardObject, ScoreboardPlayerPairObject},
         std::{
-            CreatedFunctionObject, IfStatementObject, MutationVariableObject, VariableObject,
-            WhileObject,
+            CreatedFunctionObject, ExecuteBlockObject, IfStatementObject, MutationVariableObject,
+            VariableObject, WhileObject,
         },
+        storage::{NbtType, StorageObject, StoragePathObject},
+        structs::StructObject,
+        text::{builtin_functions, TextObject},
+        world::WorldObject,
     },
 };
 
@@ -20,9 +28,17 @@ use super::{
 pub enum Objects {
     Entity(String),
     Dimension(String),
-    BlockPos(i64, i64, i64),
+    BlockPos(Coordinate, Coordinate, Coordinate),
+    Coordinate(Coordinate),
+    Item(ItemObject),
+    Text(TextObject),
+    Storage(String),
+    StoragePath(String, String, NbtType),
+    List(String, String, NbtType),
+    Struct(StructObject),
     String(String),
     Number(i64),
+    Decimal(f64),
     Boolean(bool),
     MCStatement(Statements),
     Scoreboard(String, String, Box<Objects>),
@@ -37,20 +53,21 @@ pub enum Objects {
         Box<Objects>,
     ),
     IfStatement(Vec<Rc<dyn Object>>, Box<ASTOperation>),
+    ExecuteBlock(Vec<ExecuteSteps>, Box<ASTOperation>),
     Array(Vec<Rc<dyn Object>>),
     While(String, Vec<Rc<dyn Object>>, Box<ASTOperation>),
+    World,
     Unknown,
 }
 
+/// A function of an object, called with its arguments and the variable it is called on.
+pub type ObjectFunction =
+    Box<dyn Fn(Vec<Rc<dyn Object>>, Option<Rc<VariableObject>>) -> Rc<dyn Object>>;
+
 pub trait Object: Debug {
     fn get_type(&self) -> Objects;
     fn get_variables(&self) -> HashMap<String, Rc<VariableObject>>;
-    fn get_functions(
-        &self,
-    ) -> HashMap<
-        String,
-        Box<dyn Fn(Vec<Rc<dyn Object>>, Option<Rc<VariableObject>>) -> Rc<dyn Object>>,
-    >;
+    fn get_functions(&self) -> HashMap<String, ObjectFunction>;
     fn as_any(&self) -> &dyn Any;
 }
 
@@ -58,7 +75,20 @@ pub fn name_into_object(str: &str) -> Rc<dyn Object> {
     match str {
         "Entity" => match_objects(Objects::Entity("".to_string())),
         "Dimension" => match_objects(Objects::Dimension("".to_string())),
-        "BlockPos" => match_objects(Objects::BlockPos(0, 0, 0)),
+        "Item" => match_objects(Objects::Item(ItemObject {
+            id: "".to_string(),
+            count: 1,
+            name: None,
+            lore: vec![],
+            enchantments: vec![],
+            unbreakable: false,
+        })),
+        "Storage" => match_objects(Objects::Storage("".to_string())),
+        "BlockPos" => match_objects(Objects::BlockPos(
+            Coordinate::Absolute(0.0),
+            Coordinate::Absolute(0.0),
+            Coordinate::Absolute(0.0),
+        )),
         // "string" => Objects::String("".to_string()),
         // "number" => Objects::Number(0),
         // "boolean" => Objects::Boolean(false),
@@ -74,9 +104,24 @@ pub fn name_into_object(str: &str) -> Rc<dyn Object> {
         _ => match_objects(Objects::Unknown),
     }
 }
+/// Objects that are always in scope without being declared, such as `world`.
+pub fn builtin_variable(str: &str) -> Option<Rc<VariableObject>> {
+    match str {
+        "world" => Some(mk_variable(Objects::World, Objects::Unknown)),
+        _ => None,
+    }
+}
+
+/// Functions that are always in scope without being declared, such as `tellraw`.
+pub fn builtin_function(str: &str) -> Option<ObjectFunction> {
+    builtin_functions().remove(str)
+}
+
 pub fn match_objects(obj: Objects) -> Rc<dyn Object> {
     match obj {
         Objects::Number(num) => Rc::new(NumberObject { value: num }),
+        Objects::Decimal(num) => Rc::new(DecimalObject { value: num }),
+        Objects::Coordinate(value) => Rc::new(CoordinateObject { value }),
         Objects::String(str) => Rc::new(StringObject { value: str }),
         Objects::Boolean(bool) => Rc::new(BooleanObject { value: bool }),
         Objects::Unknown => Rc::new(NullObject {}),
@@ -106,8 +151,26 @@ pub fn match_objects(obj: Objects) -> Rc<dyn Object> {
             code_block,
             operations: boolean_statements,
         }),
+        Objects::ExecuteBlock(steps, code_block) => {
+            Rc::new(ExecuteBlockObject { steps, code_block })
+        }
         Objects::Entity(selector) => Rc::new(EntityObject { selector }),
+        Objects::Dimension(name) => Rc::new(DimensionObject { name }),
         Objects::BlockPos(x, y, z) => Rc::new(BlockPosObject { x, y, z }),
+        Objects::Item(item) => Rc::new(item),
+        Objects::Text(text) => Rc::new(text),
+        Objects::Struct(instance) => Rc::new(instance),
+        Objects::Storage(name) => Rc::new(StorageObject { name }),
+        Objects::StoragePath(storage, path, nbt_type) => Rc::new(StoragePathObject {
+            storage,
+            path,
+            nbt_type,
+        }),
+        Objects::List(storage, path, element) => Rc::new(ListObject {
+            storage,
+            path,
+            element,
+        }),
         Objects::Array(values) => Rc::new(SetObject { values }),
         Objects::While(name, iterator, code_block) => Rc::new(WhileObject {
             name,
@@ -122,12 +185,12 @@ pub fn match_objects(obj: Objects) -> Rc<dyn Object> {
             })
         }
         Objects::CreatedFunction => Rc::new(CreatedFunctionObject {}),
+        Objects::World => Rc::new(WorldObject {}),
         Objects::Scoreboard(name, objective, objective_type) => Rc::new(ScoreboardObject {
             name,
             objective,
             objective_type: *objective_type,
         }),
-        _ => Rc::new(NullObject {}),
     }
 }
 
@@ -138,8 +201,40 @@ pub fn mk_variable(obj: Objects, scoreboard: Objects) -> Rc<VariableObject> {
     })
 }
 
-pub fn mk_function_map(
-) -> HashMap<String, Box<dyn Fn(Vec<Rc<dyn Object>>, Option<Rc<VariableObject>>) -> Rc<dyn Object>>>
-{
+pub fn mk_function_map() -> HashMap<String, ObjectFunction> {
     HashMap::new()
 }
+
+/// Exits when a function of an object is called with fewer than `min` or more than `max`
+/// arguments.
+pub fn check_params(params: &[Rc<dyn Object>], min: usize, max: usize, function: &str) {
+    if params.len() < min || params.len() > max {
+        eprintln!("{}", argument_count(function, min, max, params.len()));
+        exit(1);
+    }
+}
+
+pub fn string_param(params: &[Rc<dyn Object>], index: usize, function: &str) -> String {
+    let value = params[index].as_any().downcast_ref::<StringObject>();
+    if value.is_none() {
+        eprintln!(
+            "Incorrect argument type for function {}, expected string",
+            function
+        );
+        exit(1);
+    }
+    value.unwrap().value.clone()
+}
+
+/// The object a method is called on, such as the item of `sword.count(2)`.
+pub fn own<T: Object + Clone + 'static>(variable: Option<Rc<VariableObject>>) -> T {
+    let own = match_objects(*variable.unwrap().value.clone());
+    own.as_any().downcast_ref::<T>().unwrap().clone()
+}
+
+/// A command returned by a function of an object.
+pub fn statement(command: String) -> Rc<dyn Object> {
+    Rc::new(MinecraftStatementObject {
+        value: Statements::Raw(command),
+    })
+}
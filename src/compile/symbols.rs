@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use super::compiler::Variable;
+
+/// The variables in scope at some point of the code. Blocks nest inside of the scope they are
+/// written in: they see its variables, and a variable declared inside of a block shadows the
+/// one of the same name around it until the block ends.
+#[derive(Clone, Debug)]
+pub struct SymbolTable {
+    /// The variables declared in each block, from the outermost in, by the name of its scope.
+    blocks: Vec<(String, HashMap<String, Variable>)>,
+}
+
+impl SymbolTable {
+    /// The table of the outermost block of a scope, such as a module.
+    pub fn new(scope: &str) -> SymbolTable {
+        SymbolTable {
+            blocks: vec![(scope.to_string(), HashMap::new())],
+        }
+    }
+
+    /// The table of a block nested inside of this one, such as the body of an `if`.
+    pub fn block(&self, scope: &str) -> SymbolTable {
+        let mut table = self.clone();
+        table.blocks.push((scope.to_string(), HashMap::new()));
+        table
+    }
+
+    /// The variables seen by code written in `scope`, without those of the blocks this table
+    /// is nested in since. Functions see the variables of where they are declared, not of
+    /// where they are called.
+    pub fn lexical(&self, scope: &str) -> SymbolTable {
+        let end = self
+            .blocks
+            .iter()
+            .rposition(|(name, _)| name == scope)
+            .map_or(self.blocks.len(), |index| index + 1);
+        SymbolTable {
+            blocks: self.blocks[..end].to_vec(),
+        }
+    }
+
+    /// The innermost variable of a name.
+    pub fn get(&self, name: &str) -> Option<&Variable> {
+        self.blocks
+            .iter()
+            .rev()
+            .find_map(|(_, variables)| variables.get(name))
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Declares a variable in the innermost block.
+    pub fn insert(&mut self, name: String, variable: Variable) {
+        self.blocks.last_mut().unwrap().1.insert(name, variable);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile::objects::{mk_variable, Objects};
+
+    fn number(name: &str, value: i64) -> Variable {
+        Variable {
+            name: name.to_string(),
+            value: mk_variable(Objects::Number(value), Objects::Unknown),
+            static_variable: false,
+        }
+    }
+
+    fn value(table: &SymbolTable, name: &str) -> Option<i64> {
+        match table.get(name)?.value.get_type() {
+            Objects::Variable(value, _) => match *value {
+                Objects::Number(value) => Some(value),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn blocks_see_the_variables_around_them() {
+        let mut table = SymbolTable::new("code");
+        table.insert("a".to_string(), number("a", 1));
+        let block = table.block("code.0");
+        assert_eq!(value(&block, "a"), Some(1));
+        assert!(!block.contains_key("b"));
+    }
+
+    #[test]
+    fn shadows_variables_until_the_block_ends() {
+        let mut table = SymbolTable::new("code");
+        table.insert("a".to_string(), number("a", 1));
+        let mut block = table.block("code.0");
+        block.insert("a".to_string(), number("a", 2));
+        block.insert("b".to_string(), number("b", 3));
+        assert_eq!(value(&block, "a"), Some(2));
+        assert_eq!(value(&table, "a"), Some(1));
+        assert!(!table.contains_key("b"));
+    }
+
+    #[test]
+    fn sees_the_blocks_of_where_code_is_written() {
+        let mut table = SymbolTable::new("code");
+        table.insert("a".to_string(), number("a", 1));
+        let mut block = table.block("code.0");
+        block.insert("b".to_string(), number("b", 2));
+        let mut call = block.block("code.0.0");
+        call.insert("c".to_string(), number("c", 3));
+        let lexical = call.lexical("code");
+        assert!(lexical.contains_key("a"));
+        assert!(!lexical.contains_key("b"));
+        assert!(!lexical.contains_key("c"));
+        assert!(call.lexical("code.0").contains_key("b"));
+    }
+}
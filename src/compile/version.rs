@@ -0,0 +1,167 @@
+//! The Minecraft versions a datapack can target. The target decides the `pack_format` written
+//! to `pack.mcmeta`, the syntax of generated commands and which features can be used at all.
+
+/// The version targeted when none is given.
+pub const DEFAULT_TARGET: &str = "1.21";
+
+const VERSIONS: [(&str, u32); 43] = [
+    ("1.13", 4),
+    ("1.13.1", 4),
+    ("1.13.2", 4),
+    ("1.14", 4),
+    ("1.14.1", 4),
+    ("1.14.2", 4),
+    ("1.14.3", 4),
+    ("1.14.4", 4),
+    ("1.15", 5),
+    ("1.15.1", 5),
+    ("1.15.2", 5),
+    ("1.16", 5),
+    ("1.16.1", 5),
+    ("1.16.2", 6),
+    ("1.16.3", 6),
+    ("1.16.4", 6),
+    ("1.16.5", 6),
+    ("1.17", 7),
+    ("1.17.1", 7),
+    ("1.18", 8),
+    ("1.18.1", 8),
+    ("1.18.2", 9),
+    ("1.19", 10),
+    ("1.19.1", 10),
+    ("1.19.2", 10),
+    ("1.19.3", 10),
+    ("1.19.4", 12),
+    ("1.20", 15),
+    ("1.20.1", 15),
+    ("1.20.2", 18),
+    ("1.20.3", 26),
+    ("1.20.4", 26),
+    ("1.20.5", 41),
+    ("1.20.6", 41),
+    ("1.21", 48),
+    ("1.21.1", 48),
+    ("1.21.2", 57),
+    ("1.21.3", 57),
+    ("1.21.4", 61),
+    ("1.21.5", 71),
+    ("1.21.6", 80),
+    ("1.21.7", 81),
+    ("1.21.8", 81),
+];
+
+/// The pack format of a Minecraft version such as `1.20.4`.
+pub fn pack_format(version: &str) -> Option<u32> {
+    VERSIONS
+        .iter()
+        .find(|(name, _)| *name == version)
+        .map(|(_, pack_format)| *pack_format)
+}
+
+/// The first Minecraft version using the given pack format or a newer one.
+pub fn first_version(pack_format: u32) -> &'static str {
+    VERSIONS
+        .iter()
+        .find(|(_, format)| *format >= pack_format)
+        .map(|(name, _)| *name)
+        .unwrap_or(VERSIONS[VERSIONS.len() - 1].0)
+}
+
+/// Constructs and datapack features that only exist from a given version on.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Feature {
+    /// `data storage`, used by `Storage` and runtime lists.
+    DataStorage,
+    /// Function macros, used for runtime values in command arguments and list indexes.
+    Macros,
+    /// The singular `function` folders (1.21) instead of `functions`.
+    SingularFolders,
+    /// Objective names longer than 16 characters.
+    LongObjectiveNames,
+    /// Data components (1.20.5) instead of NBT tags on items.
+    ItemComponents,
+    /// Text components inside items written as SNBT (1.21.5) instead of JSON strings.
+    SnbtText,
+    /// Click and hover events in the snake case format (1.21.5).
+    SnakeCaseEvents,
+    /// `return run`, returning the result of another command (1.20.2).
+    ReturnRun,
+    /// `execute if function` and `execute unless function` (1.20.3).
+    ExecuteIfFunction,
+}
+
+impl Feature {
+    pub fn pack_format(&self) -> u32 {
+        match self {
+            Feature::DataStorage => 5,
+            Feature::Macros | Feature::ReturnRun => 18,
+            Feature::SingularFolders => 45,
+            Feature::LongObjectiveNames => 8,
+            Feature::ExecuteIfFunction => 26,
+            Feature::ItemComponents => 41,
+            Feature::SnbtText | Feature::SnakeCaseEvents => 71,
+        }
+    }
+
+    /// Whether datapacks of the given pack format have the feature.
+    pub fn supported_by(&self, pack_format: u32) -> bool {
+        pack_format >= self.pack_format()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Feature::DataStorage => "data storage",
+            Feature::Macros => "function macros",
+            Feature::SingularFolders => "singular datapack folders",
+            Feature::LongObjectiveNames => "long objective names",
+            Feature::ItemComponents => "item components",
+            Feature::SnbtText => "SNBT text components",
+            Feature::SnakeCaseEvents => "snake case text events",
+            Feature::ReturnRun => "return run",
+            Feature::ExecuteIfFunction => "execute if function",
+        }
+    }
+
+    /// The features the syntax of a command uses, such as `return run` inside an
+    /// `execute ... run`.
+    pub fn used_by(command: &str) -> Vec<Feature> {
+        let words: Vec<&str> = command.split_whitespace().collect();
+        let mut features = vec![];
+        for (index, pair) in words.windows(2).enumerate() {
+            // a subcommand starts the command or follows a `run`
+            let starts_command = index == 0 || words[index - 1] == "run";
+            match (pair[0], pair[1]) {
+                ("return", "run") if starts_command => features.push(Feature::ReturnRun),
+                ("if" | "unless", "function") if words[0] == "execute" => {
+                    features.push(Feature::ExecuteIfFunction)
+                }
+                _ => {}
+            }
+        }
+        features.dedup();
+        features
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Feature;
+
+    #[test]
+    fn finds_the_syntax_commands_use() {
+        assert_eq!(
+            Feature::used_by("return run say hi"),
+            vec![Feature::ReturnRun]
+        );
+        assert_eq!(
+            Feature::used_by("execute as @a run return run function a:b"),
+            vec![Feature::ReturnRun]
+        );
+        assert_eq!(
+            Feature::used_by("execute if function a:b run say hi"),
+            vec![Feature::ExecuteIfFunction]
+        );
+        assert_eq!(Feature::used_by("say return run"), vec![]);
+        assert_eq!(Feature::used_by("return 1"), vec![]);
+    }
+}
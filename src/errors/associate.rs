@@ -1,7 +1,9 @@
-#[derive(Debug, Clone, PartialEq, Eq)]
+use super::sources::FileId;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct CodeAssociate {
     pub lines: String,
-    pub file: String,
+    pub file: FileId,
     pub line: usize,
     pub start_column: usize,
     pub end_column: usize,
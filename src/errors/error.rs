@@ -1,46 +1,103 @@
-use super::associate::CodeAssociate;
+use super::{
+    associate::CodeAssociate,
+    sources::{path, source, FileId},
+};
 
 pub enum StdErrors {
-    IOError(&'static str),
-    SyntaxError(String, CodeAssociate),
+    Io(&'static str),
+    Argument(String),
+    Manifest(String),
+    Syntax(String, CodeAssociate),
 }
 
 pub enum CompileErrors {
     SyntaxError(CodeAssociate),
     TypeError(CodeAssociate),
-    NameError(CodeAssociate),
     ValueError(CodeAssociate),
-    ImportError(CodeAssociate),
-    ExportError(CodeAssociate),
-    FunctionError(CodeAssociate),
+    ImportError(String, CodeAssociate),
+    ExportError(String, CodeAssociate),
     UnknownIdentifier(CodeAssociate),
     InstantiationError(CodeAssociate),
     MissingParams(CodeAssociate),
-    UnknownError(CodeAssociate),
+    VersionError(String, CodeAssociate),
+    CommandError(String, CodeAssociate),
+    InternalError(String, CodeAssociate),
+    TypeMismatch(String, CodeAssociate),
+    ArgumentCount(String, CodeAssociate),
+    UndefinedName(String, CodeAssociate),
 }
 
 pub fn std_error(error: StdErrors) {
     match error {
-        StdErrors::IOError(message) => eprintln!("io: {}", message),
-        StdErrors::SyntaxError(message, associate) => eprintln!(
-            "syntax: {}\n{}\n\tFile: {}\n\tAt Line: {}:{}-{}",
-            message,
+        StdErrors::Io(message) => eprintln!("io: {}", message),
+        StdErrors::Argument(message) => eprintln!("argument: {}", message),
+        StdErrors::Manifest(message) => eprintln!("manifest: {}", message),
+        StdErrors::Syntax(message, associate) => {
+            eprintln!(
+                "syntax: {}\n{}\n\tFile: {}\n\tAt Line: {}:{}-{}",
+                message,
+                associate.lines,
+                path(associate.file),
+                associate.line,
+                associate.start_column,
+                associate.end_column
+            );
+            imported_here(associate.file);
+        }
+    }
+}
+
+impl CompileErrors {
+    /// The code an error was found in.
+    pub fn associate(&self) -> &CodeAssociate {
+        match self {
+            CompileErrors::SyntaxError(associate)
+            | CompileErrors::TypeError(associate)
+            | CompileErrors::ValueError(associate)
+            | CompileErrors::ImportError(_, associate)
+            | CompileErrors::ExportError(_, associate)
+            | CompileErrors::UnknownIdentifier(associate)
+            | CompileErrors::InstantiationError(associate)
+            | CompileErrors::MissingParams(associate)
+            | CompileErrors::VersionError(_, associate)
+            | CompileErrors::CommandError(_, associate)
+            | CompileErrors::InternalError(_, associate)
+            | CompileErrors::TypeMismatch(_, associate)
+            | CompileErrors::ArgumentCount(_, associate)
+            | CompileErrors::UndefinedName(_, associate) => associate,
+        }
+    }
+}
+
+/// Points to where the file of an error was imported, and so on up to the file being compiled.
+fn imported_here(file: FileId) {
+    let mut file = file;
+    let mut seen = vec![file];
+    while let Some(associate) = source(file).and_then(|source| source.imported_at) {
+        eprintln!(
+            "note: imported here\n{}\n\tFile: {}\n\tAt Line: {}:{}-{}",
             associate.lines,
-            associate.file,
+            path(associate.file),
             associate.line,
             associate.start_column,
             associate.end_column
-        ),
+        );
+        if seen.contains(&associate.file) {
+            break;
+        }
+        file = associate.file;
+        seen.push(file);
     }
 }
 
 pub fn compile_error(error: CompileErrors) {
+    let file = error.associate().file;
     match error {
         CompileErrors::SyntaxError(associate) => eprintln!(
             "syntax: {}\n{}\n\tFile: {}\n\tAt Line: {}:{}-{}",
             "Syntax Error",
             associate.lines,
-            associate.file,
+            path(associate.file),
             associate.line,
             associate.start_column,
             associate.end_column
@@ -49,16 +106,7 @@ pub fn compile_error(error: CompileErrors) {
             "type: {}\n{}\n\tFile: {}\n\tAt Line: {}:{}-{}",
             "Type Error",
             associate.lines,
-            associate.file,
-            associate.line,
-            associate.start_column,
-            associate.end_column
-        ),
-        CompileErrors::NameError(associate) => eprintln!(
-            "name: {}\n{}\n\tFile: {}\n\tAt Line: {}:{}-{}",
-            "Name Error",
-            associate.lines,
-            associate.file,
+            path(associate.file),
             associate.line,
             associate.start_column,
             associate.end_column
@@ -67,34 +115,25 @@ pub fn compile_error(error: CompileErrors) {
             "value: {}\n{}\n\tFile: {}\n\tAt Line: {}:{}-{}",
             "Value Error",
             associate.lines,
-            associate.file,
+            path(associate.file),
             associate.line,
             associate.start_column,
             associate.end_column
         ),
-        CompileErrors::ImportError(associate) => eprintln!(
+        CompileErrors::ImportError(message, associate) => eprintln!(
             "import: {}\n{}\n\tFile: {}\n\tAt Line: {}:{}-{}",
-            "Import Error",
+            message,
             associate.lines,
-            associate.file,
+            path(associate.file),
             associate.line,
             associate.start_column,
             associate.end_column
         ),
-        CompileErrors::ExportError(associate) => eprintln!(
+        CompileErrors::ExportError(message, associate) => eprintln!(
             "export: {}\n{}\n\tFile: {}\n\tAt Line: {}:{}-{}",
-            "Export Error",
-            associate.lines,
-            associate.file,
-            associate.line,
-            associate.start_column,
-            associate.end_column
-        ),
-        CompileErrors::FunctionError(associate) => eprintln!(
-            "function: {}\n{}\n\tFile: {}\n\tAt Line: {}:{}-{}",
-            "Function Error",
+            message,
             associate.lines,
-            associate.file,
+            path(associate.file),
             associate.line,
             associate.start_column,
             associate.end_column
@@ -103,7 +142,7 @@ pub fn compile_error(error: CompileErrors) {
             "instantiation: {}\n{}\n\tFile: {}\n\tAt Line: {}:{}-{}",
             "No instantiation function found in object.",
             associate.lines,
-            associate.file,
+            path(associate.file),
             associate.line,
             associate.start_column,
             associate.end_column
@@ -112,7 +151,7 @@ pub fn compile_error(error: CompileErrors) {
             "missing: {}\n{}\n\tFile: {}\n\tAt Line: {}:{}-{}",
             "Missing Parameters in function call.",
             associate.lines,
-            associate.file,
+            path(associate.file),
             associate.line,
             associate.start_column,
             associate.end_column
@@ -121,19 +160,79 @@ pub fn compile_error(error: CompileErrors) {
             "unknown: {}\n{}\n\tFile: {}\n\tAt Line: {}:{}-{}",
             "Unknown identifier found in call.",
             associate.lines,
-            associate.file,
+            path(associate.file),
             associate.line,
             associate.start_column,
             associate.end_column
         ),
-        CompileErrors::UnknownError(associate) => eprintln!(
-            "unknown: {}\n{}\n\tFile: {}\n\tAt Line: {}:{}-{}",
-            "Unknown Error",
+        CompileErrors::VersionError(message, associate) => eprintln!(
+            "version: {}\n{}\n\tFile: {}\n\tAt Line: {}:{}-{}",
+            message,
+            associate.lines,
+            path(associate.file),
+            associate.line,
+            associate.start_column,
+            associate.end_column
+        ),
+        CompileErrors::CommandError(message, associate) => eprintln!(
+            "command: {}\n{}\n\tFile: {}\n\tAt Line: {}:{}-{}",
+            message,
+            associate.lines,
+            path(associate.file),
+            associate.line,
+            associate.start_column,
+            associate.end_column
+        ),
+        CompileErrors::InternalError(message, associate) => eprintln!(
+            "internal: {}\n{}\n\tFile: {}\n\tAt Line: {}:{}-{}",
+            message,
             associate.lines,
-            associate.file,
+            path(associate.file),
+            associate.line,
+            associate.start_column,
+            associate.end_column
+        ),
+        CompileErrors::TypeMismatch(message, associate) => eprintln!(
+            "type: {}\n{}\n\tFile: {}\n\tAt Line: {}:{}-{}",
+            message,
+            associate.lines,
+            path(associate.file),
+            associate.line,
+            associate.start_column,
+            associate.end_column
+        ),
+        CompileErrors::ArgumentCount(message, associate) => eprintln!(
+            "arguments: {}\n{}\n\tFile: {}\n\tAt Line: {}:{}-{}",
+            message,
+            associate.lines,
+            path(associate.file),
+            associate.line,
+            associate.start_column,
+            associate.end_column
+        ),
+        CompileErrors::UndefinedName(message, associate) => eprintln!(
+            "name: {}\n{}\n\tFile: {}\n\tAt Line: {}:{}-{}",
+            message,
+            associate.lines,
+            path(associate.file),
             associate.line,
             associate.start_column,
             associate.end_column
         ),
     }
+    imported_here(file);
+}
+
+/// Reports code that is likely a mistake but still compiles, such as an unused variable.
+pub fn compile_warning(message: &str, associate: &CodeAssociate) {
+    eprintln!(
+        "warning: {}\n{}\n\tFile: {}\n\tAt Line: {}:{}-{}",
+        message,
+        associate.lines,
+        path(associate.file),
+        associate.line,
+        associate.start_column,
+        associate.end_column
+    );
+    imported_here(associate.file);
 }
@@ -0,0 +1,63 @@
+use std::cell::RefCell;
+
+use super::associate::CodeAssociate;
+
+/// A file of the program, by its position in the source map.
+pub type FileId = usize;
+
+/// The file of code that was not read from any file, such as generated code.
+pub const NO_FILE: FileId = usize::MAX;
+
+/// A file of the program and where it was first imported.
+#[derive(Clone, Debug)]
+pub struct SourceFile {
+    /// The module name used to import it, such as `utils/math`.
+    pub module: String,
+    /// The path it was read from, such as `inputs/utils/math.mc`.
+    pub path: String,
+    pub code: String,
+    pub imported_at: Option<CodeAssociate>,
+}
+
+thread_local! {
+    /// The files being compiled, shared by the lexer, the parser, the compiler and the errors
+    /// they report.
+    static SOURCES: RefCell<Vec<SourceFile>> = const { RefCell::new(vec![]) };
+}
+
+/// Adds a file to the source map and returns its id.
+pub fn add_source(module: &str, path: &str, code: String) -> FileId {
+    SOURCES.with_borrow_mut(|sources| {
+        sources.push(SourceFile {
+            module: module.to_string(),
+            path: path.to_string(),
+            code,
+            imported_at: None,
+        });
+        sources.len() - 1
+    })
+}
+
+/// The id of the file of a module.
+pub fn module_id(module: &str) -> Option<FileId> {
+    SOURCES.with_borrow(|sources| sources.iter().position(|source| source.module == module))
+}
+
+/// The file with an id.
+pub fn source(file: FileId) -> Option<SourceFile> {
+    SOURCES.with_borrow(|sources| sources.get(file).cloned())
+}
+
+/// The path of a file, or nothing for code that was not read from a file.
+pub fn path(file: FileId) -> String {
+    source(file).map(|source| source.path).unwrap_or_default()
+}
+
+/// Remembers the first import of a file, to point to it from errors inside of the file.
+pub fn record_import(file: FileId, associate: &CodeAssociate) {
+    SOURCES.with_borrow_mut(|sources| {
+        if let Some(source) = sources.get_mut(file) {
+            source.imported_at.get_or_insert_with(|| associate.clone());
+        }
+    })
+}
@@ -1,17 +1,135 @@
-use std::{collections::HashMap, fs, process::exit};
+use std::{
+    collections::HashMap,
+    fs::{self, ReadDir},
+    path::Path,
+    process::exit,
+};
 
 use crate::{
     ast::constructor::AST,
-    compile::compiler::{Compiler, Scope},
-    errors::error::{std_error, StdErrors},
+    compile::{
+        checker::TypeChecker,
+        compiler::{Compiler, Scope},
+        modules::ModuleGraph,
+    },
+    errors::{
+        error::{compile_error, compile_warning, std_error, StdErrors},
+        sources::{add_source, module_id, source},
+    },
     lexer::lexer::Lexer,
+    manifest::{archive_inputs, DependencySource, Manifest},
 };
 
+/// Reads the `.mc` files of a directory and its subdirectories into the source map, as modules
+/// named by their path such as `utils/math`.
+fn read_inputs(files: ReadDir, prefix: &str) {
+    for file in files.into_iter() {
+        let file = file.unwrap();
+        let path = file.path();
+        let name = prefix.to_string() + &file.file_name().into_string().unwrap();
+        if path.is_dir() {
+            let files = fs::read_dir(&path).unwrap_or_else(|_| {
+                std_error(StdErrors::Io(
+                    "Failed to access directory. Does it exist or lacking permissions?",
+                ));
+                exit(1);
+            });
+            read_inputs(files, &format!("{}/", name));
+            continue;
+        }
+        let code = fs::read_to_string(&path);
+        if code.is_err() {
+            std_error(StdErrors::Io(
+                "Failed to access file. Does it exist or lacking permissions?",
+            ));
+            exit(1);
+        }
+        let code: String = code.unwrap();
+        let module = name.strip_suffix(".mc").unwrap();
+        add_source(module, &path.display().to_string(), code);
+    }
+}
+
+/// Reads the modules of the dependencies into the source map, as modules named by the
+/// dependency and their path such as `math:trig`.
+fn read_dependencies(manifest: &Manifest) {
+    for dependency in &manifest.dependencies {
+        let prefix = format!("{}:", dependency.name);
+        match &dependency.source {
+            DependencySource::Path(path) => {
+                let files = fs::read_dir(Path::new(path).join("inputs")).unwrap_or_else(|_| {
+                    std_error(StdErrors::Manifest(format!(
+                        "Failed to read the inputs of dependency {} at {}",
+                        dependency.name, path
+                    )));
+                    exit(1);
+                });
+                read_inputs(files, &prefix);
+            }
+            DependencySource::Archive(path) => {
+                let files = fs::read(path)
+                    .map_err(|_| format!("Failed to read archive {}", path))
+                    .and_then(|archive| archive_inputs(&archive));
+                let files = files.unwrap_or_else(|message| {
+                    std_error(StdErrors::Manifest(message));
+                    exit(1);
+                });
+                for (file, code) in files {
+                    let module = prefix.clone() + file.strip_suffix(".mc").unwrap();
+                    add_source(&module, &format!("{}:inputs/{}", path, file), code);
+                }
+            }
+        }
+    }
+}
+
+/// The modules reachable from a root module, stopping at a missing module or circular import.
+fn module_graph(root: &str) -> ModuleGraph {
+    match ModuleGraph::build(root) {
+        Ok(modules) => modules,
+        Err(error) => {
+            compile_error(error);
+            exit(1);
+        }
+    }
+}
+
+/// Prints what a module exports, after checking it.
+fn print_interface(module: &str) {
+    let Some(file) = module_id(module) else {
+        std_error(StdErrors::Argument(format!(
+            "Module {} does not exist",
+            module
+        )));
+        exit(1);
+    };
+    module_graph(module);
+    let mut lexer = Lexer::new(source(file).unwrap().code, file).quiet();
+    lexer.tokenizer();
+    let mut ast = AST::new(lexer.flush().to_vec());
+    ast.generate();
+
+    let mut checker = TypeChecker::new();
+    checker.check(module, ast.flush());
+    if !checker.errors.is_empty() {
+        for error in checker.errors {
+            compile_error(error);
+        }
+        exit(1);
+    }
+    println!("{}", checker.interface(module).unwrap());
+}
+
 pub fn run() {
     let files = fs::read_dir("inputs");
-    let namespace = "test";
+    let current_path = std::env::current_dir().unwrap();
+    let manifest = Manifest::read(&current_path).unwrap_or_else(|message| {
+        std_error(StdErrors::Manifest(message));
+        exit(1);
+    });
+    let namespace = manifest.namespace.as_str();
     if files.is_err() {
-        std_error(StdErrors::IOError(
+        std_error(StdErrors::Io(
             "Failed to access directory. Does it exist or lacking permissions?",
         ));
         exit(1);
@@ -20,68 +138,178 @@ pub fn run() {
     let files = files.unwrap();
 
     let mut compiler = Compiler::new(namespace);
-    for file in files.into_iter() {
-        let file = file.unwrap();
-        let path = file.path();
-        let code = fs::read_to_string(path);
-        if code.is_err() {
-            std_error(StdErrors::IOError(
-                "Failed to access file. Does it exist or lacking permissions?",
-            ));
-            exit(1);
+    compiler.names.allocation = manifest.allocation;
+    compiler.external = manifest
+        .dependencies
+        .iter()
+        .filter(|dependency| !dependency.bundle)
+        .map(|dependency| dependency.name.clone())
+        .collect();
+    // mclang [exports <module> | graph] [--target <minecraft version>]
+    let mut exports = None;
+    let mut graph = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "exports" => match args.next() {
+                Some(module) => exports = Some(module),
+                None => {
+                    std_error(StdErrors::Argument(
+                        "Expected a module after exports".to_string(),
+                    ));
+                    exit(1);
+                }
+            },
+            "graph" => graph = true,
+            "--target" => {
+                let version = args.next().unwrap_or_default();
+                if let Err(message) = compiler.set_target(&version) {
+                    std_error(StdErrors::Argument(message));
+                    exit(1);
+                }
+            }
+            _ => {
+                std_error(StdErrors::Argument(format!("Unknown argument {}", arg)));
+                exit(1);
+            }
         }
-        let code: String = code.unwrap();
-        compiler.prepared_files.insert(
-            file.file_name()
-                .into_string()
-                .unwrap()
-                .strip_suffix(".mc")
-                .unwrap()
-                .to_string(),
-            code,
-        );
+    }
+    read_inputs(files, "");
+    read_dependencies(&manifest);
+
+    if let Some(module) = exports {
+        print_interface(&module);
+        return;
+    }
+    if module_id("code").is_none() {
+        std_error(StdErrors::Argument(
+            "Module code does not exist".to_string(),
+        ));
+        exit(1);
+    }
+    let modules = module_graph("code");
+    if graph {
+        println!("{}", modules.dot());
+        return;
     }
 
     // compile the code scope
-    let code = compiler.prepared_files.get("code").unwrap();
+    let code = module_id("code").unwrap();
 
-    let mut lexer = Lexer::new(code.to_string(), "code".to_string());
+    let mut lexer = Lexer::new(source(code).unwrap().code, code);
     lexer.tokenizer();
     let mut ast = AST::new(lexer.flush().to_vec());
     ast.generate();
+
+    // report every type error before anything is generated
+    let mut checker = TypeChecker::new();
+    checker.check("code", ast.flush());
+    for (message, associate) in &checker.warnings {
+        compile_warning(message, associate);
+    }
+    if !checker.errors.is_empty() {
+        for error in checker.errors {
+            compile_error(error);
+        }
+        exit(1);
+    }
+
+    // imported modules are compiled before the modules that import them
+    for module in &modules.order {
+        if module != "code" {
+            compiler.compile_module(module);
+        }
+    }
     let mut scope = Scope::new(
         format!("{}", "code"),
-        "test".to_string(),
+        namespace.to_string(),
         ast.flush().to_vec(),
         HashMap::new(),
     );
     compiler.scopes.push(scope.clone());
     compiler.compile(&mut scope);
-
-    let current_path = std::env::current_dir().unwrap();
+    compiler.finish();
 
     if current_path.join("outputs").exists() {
         fs::remove_dir_all(current_path.join("outputs")).unwrap_or_else(|_| {
-            std_error(StdErrors::IOError(
-                "Failed to remove directory. Does it exist?",
-            ));
+            std_error(StdErrors::Io("Failed to remove directory. Does it exist?"));
             exit(1);
         });
     }
     fs::create_dir(current_path.join("outputs")).unwrap_or_else(|_| {
-        std_error(StdErrors::IOError(
+        std_error(StdErrors::Io(
             "Failed to create directory. Does it already exist?",
         ));
         exit(1);
     });
+    fs::write(
+        current_path.join("outputs").join("pack.mcmeta"),
+        format!(
+            "{{\n  \"pack\": {{\n    \"pack_format\": {},\n    \"description\": \"Compiled with mclang for Minecraft {}\"\n  }}\n}}\n",
+            compiler.pack_format, compiler.target
+        ),
+    )
+    .unwrap_or_else(|_| {
+        std_error(StdErrors::Io("Failed to write pack.mcmeta."));
+        exit(1);
+    });
+    // which declaration each objective and tag belongs to, for debugging in the game
+    fs::write(
+        current_path.join("outputs").join("objectives.txt"),
+        compiler.names.mapping(),
+    )
+    .unwrap_or_else(|_| {
+        std_error(StdErrors::Io("Failed to write objectives.txt."));
+        exit(1);
+    });
+    // 1.21 renamed the `functions` folders to `function`
+    let data = current_path.join("outputs").join("data");
+    let folder = compiler.function_folder();
+    for (tag, functions) in &compiler.function_tags {
+        let (namespace, path) = tag.split_once(':').unwrap();
+        let directory = data.join(namespace).join("tags").join(folder);
+        let values = functions
+            .iter()
+            .map(|function| format!("\"{}\"", function))
+            .collect::<Vec<String>>();
+        fs::create_dir_all(&directory)
+            .and_then(|_| {
+                fs::write(
+                    directory.join(format!("{}.json", path)),
+                    format!("{{\"values\":[{}]}}", values.join(",")),
+                )
+            })
+            .unwrap_or_else(|_| {
+                std_error(StdErrors::Io(
+                    "Failed to write function tag. Does it already exist?",
+                ));
+                exit(1);
+            });
+    }
     for (name, item) in compiler.flush() {
+        // functions of dependencies are named with their namespace
+        let (namespace, name) = name.split_once(':').unwrap_or((namespace, name));
+        let bundled = manifest
+            .dependencies
+            .iter()
+            .all(|dependency| dependency.name != namespace || dependency.bundle);
+        if !bundled {
+            continue;
+        }
+        let functions = data.join(namespace).join(folder);
+        fs::create_dir_all(&functions).unwrap_or_else(|_| {
+            std_error(StdErrors::Io(
+                "Failed to create directory. Does it already exist?",
+            ));
+            exit(1);
+        });
         // clean all unnecessary new lines
         let item = item.replace("\n\n", "\n");
         // remove the first new line
         let item = item.trim_start_matches("\n");
         let name = name.replace(" ", "_") + ".mcfunction";
-        fs::write(current_path.join("outputs").join(name), item).unwrap_or_else(|_| {
-            std_error(StdErrors::IOError(
+        fs::write(functions.join(name), item).unwrap_or_else(|_| {
+            std_error(StdErrors::Io(
                 "Failed to write to file. Does it already exist?",
             ));
             exit(1);
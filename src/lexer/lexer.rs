@@ -3,6 +3,7 @@ use std::process::exit;
 use crate::errors::{
     associate::CodeAssociate,
     error::{std_error, StdErrors},
+    sources::{FileId, NO_FILE},
 };
 
 use super::tokens::Tokens;
@@ -12,11 +13,14 @@ pub struct Lexer {
     line: usize,
     column: usize,
     tokens: Vec<Tokens>,
-    file_name: String,
+    file_id: FileId,
+    /// Whether the tokens are printed once lexed, which tools such as `mclang exports` turn
+    /// off to keep their output clean.
+    verbose: bool,
 }
 
 impl Lexer {
-    pub fn new(file: String, file_name: String) -> Lexer {
+    pub fn new(file: String, file_id: FileId) -> Lexer {
         let mut raw_tokens: Vec<Vec<char>> = vec![];
         for line in file.split("\n").collect::<Vec<&str>>() {
             raw_tokens.push(line.chars().collect());
@@ -25,8 +29,23 @@ impl Lexer {
             raw_tokens,
             line: 0usize,
             column: 0usize,
-            file_name,
+            file_id,
             tokens: vec![],
+            verbose: true,
+        }
+    }
+
+    /// Stops the lexer from printing what it reads.
+    pub fn quiet(mut self) -> Lexer {
+        self.verbose = false;
+        self
+    }
+
+    /// A lexer for a part of the current line, such as the arguments of a call.
+    fn nested(&self, code: String) -> Lexer {
+        Lexer {
+            verbose: self.verbose,
+            ..Lexer::new(code, self.file_id)
         }
     }
     pub fn flush(&self) -> &Vec<Tokens> {
@@ -53,15 +72,38 @@ impl Lexer {
         }
 
         if !built_str.contains(token) {
-            std_error(StdErrors::SyntaxError(
-                format!("Expected: {}", token),
-                associate,
-            ));
+            std_error(StdErrors::Syntax(format!("Expected: {}", token), associate));
             exit(1);
         }
         return (built_str.replace(token, ""), tracked_col - 1);
     }
 
+    /// Reads a string literal up to its closing quote, resolving `\"` and `\\` escapes.
+    pub fn read_string(&self, associate: CodeAssociate) -> (String, usize) {
+        let mut tracked_col = 0;
+        let mut built_str = String::new();
+        loop {
+            tracked_col += 1;
+            let token = self.peek(tracked_col);
+            if token == '\0' {
+                std_error(StdErrors::Syntax("Expected: \"".to_string(), associate));
+                exit(1);
+            }
+            if token == '"' {
+                break;
+            }
+            if token == '\\'
+                && (self.peek(tracked_col + 1) == '"' || self.peek(tracked_col + 1) == '\\')
+            {
+                tracked_col += 1;
+                built_str.push(self.peek(tracked_col));
+                continue;
+            }
+            built_str.push(token);
+        }
+        (built_str, tracked_col - 1)
+    }
+
     pub fn read_until_end(&self) -> (String, usize) {
         let mut tracked_col = 0;
         let mut built_str = String::new();
@@ -76,6 +118,14 @@ impl Lexer {
         return (built_str, tracked_col);
     }
 
+    /// Whether the next token starts a new statement.
+    fn at_statement_start(&self) -> bool {
+        matches!(
+            self.tokens.last(),
+            None | Some(Tokens::EOL | Tokens::SemiColon(_) | Tokens::LBrace(_) | Tokens::RBrace(_))
+        )
+    }
+
     pub fn read_until_last(
         &self,
         open_token: char,
@@ -101,12 +151,12 @@ impl Lexer {
         }
 
         // if !built_str.contains(open_token) {
-        //     std_error(StdErrors::SyntaxError(format!("Expected: {}", open_token), self.raw_tokens[self.line - 1].iter().collect(), self.line, self.column));
+        //     std_error(StdErrors::Syntax(format!("Expected: {}", open_token), self.raw_tokens[self.line - 1].iter().collect(), self.line, self.column));
         //     exit(1);
         // }
         //
         if counted_opens > 0 {
-            std_error(StdErrors::SyntaxError(
+            std_error(StdErrors::Syntax(
                 format!(
                     "Opened token {} but did not close with {}",
                     open_token, opposite_token
@@ -116,7 +166,7 @@ impl Lexer {
             exit(1);
         }
         if counted_opens < 0 {
-            std_error(StdErrors::SyntaxError(
+            std_error(StdErrors::Syntax(
                 format!(
                     "Closed token {} but did not open with {}",
                     opposite_token, open_token
@@ -137,6 +187,81 @@ impl Lexer {
         return (new_str.trim().to_string(), tracked_col);
     }
 
+    /// Splits an execute chain such as `as (@a) at (@s) positioned (~ ~1 ~)` into its
+    /// subcommands and the tokens of their parenthesized argument.
+    pub fn execute_chain(
+        &self,
+        chain: &str,
+        associate: CodeAssociate,
+    ) -> Vec<(String, Vec<Tokens>)> {
+        let mut subcommands = vec![];
+        let chars: Vec<char> = chain.chars().collect();
+        let mut index = 0;
+        while index < chars.len() {
+            if chars[index].is_whitespace() {
+                index += 1;
+                continue;
+            }
+            let mut subcommand = String::new();
+            while index < chars.len() && (chars[index].is_alphabetic() || chars[index] == '_') {
+                subcommand.push(chars[index]);
+                index += 1;
+            }
+            while index < chars.len() && chars[index].is_whitespace() {
+                index += 1;
+            }
+            if subcommand.is_empty() || index >= chars.len() || chars[index] != '(' {
+                std_error(StdErrors::Syntax(
+                    "Expected: (subcommand argument) in execute block".to_string(),
+                    associate,
+                ));
+                exit(1);
+            }
+
+            let mut argument = String::new();
+            let mut counted_opens = 0;
+            while index < chars.len() {
+                let char = chars[index];
+                index += 1;
+                if char == '(' {
+                    counted_opens += 1;
+                    if counted_opens == 1 {
+                        continue;
+                    }
+                } else if char == ')' {
+                    counted_opens -= 1;
+                    if counted_opens == 0 {
+                        break;
+                    }
+                }
+                argument.push(char);
+            }
+            if counted_opens != 0 {
+                std_error(StdErrors::Syntax(
+                    "Opened token ( but did not close with )".to_string(),
+                    associate,
+                ));
+                exit(1);
+            }
+
+            let argument = argument.trim().to_string();
+            // anchors are passed through as they are written
+            let tokens = if subcommand == "anchored" {
+                vec![Tokens::DblQuote(argument, associate.clone())]
+            } else {
+                let mut lexer = self.nested(argument);
+                lexer.tokenizer();
+                let mut tokens = lexer.flush().to_vec();
+                // remove the last 2 tokens as those are just EOL EOF
+                tokens.remove(tokens.len() - 1);
+                tokens.remove(tokens.len() - 1);
+                tokens
+            };
+            subcommands.push((subcommand, tokens));
+        }
+        subcommands
+    }
+
     pub fn mk_association(
         &self,
         line: &String,
@@ -146,7 +271,7 @@ impl Lexer {
     ) -> CodeAssociate {
         CodeAssociate {
             lines: line.to_string(),
-            file: self.file_name.clone(),
+            file: self.file_id,
             line: line_num,
             start_column: starting_column,
             end_column: ending_column,
@@ -228,12 +353,38 @@ impl Lexer {
                     }
                     "export " => {
                         built_str.clear();
-                        self.tokens.push(Tokens::Export(self.mk_association(
-                            &original_line,
-                            starting_column,
-                            self.column,
-                            self.line,
-                        )));
+                        let mut forward = 1;
+                        while self.peek(forward) == ' ' {
+                            forward += 1;
+                        }
+                        if self.peek(forward) == '{' || self.peek(forward) == '*' {
+                            let (export_list, forwardness) = self.read_until(
+                                ";",
+                                self.mk_association(
+                                    &original_line,
+                                    starting_column,
+                                    self.column,
+                                    self.line,
+                                ),
+                            );
+                            self.column += forwardness;
+                            self.tokens.push(Tokens::ExportList(
+                                export_list.trim().to_string(),
+                                self.mk_association(
+                                    &original_line,
+                                    starting_column,
+                                    self.column,
+                                    self.line,
+                                ),
+                            ));
+                        } else {
+                            self.tokens.push(Tokens::Export(self.mk_association(
+                                &original_line,
+                                starting_column,
+                                self.column,
+                                self.line,
+                            )));
+                        }
                     }
                     "=" => {
                         // this means that this is an equivalence operator
@@ -318,8 +469,7 @@ impl Lexer {
                             ),
                         );
                         self.column += forwardness;
-                        let mut lexer =
-                            Lexer::new(boolean.trim().to_string(), self.file_name.clone());
+                        let mut lexer = self.nested(boolean.trim().to_string());
                         lexer.tokenizer();
                         let mut tokens = lexer.flush().to_vec();
                         // remove the last 2 tokens as those are just EOL EOF
@@ -335,6 +485,155 @@ impl Lexer {
                             ),
                         ));
                     }
+                    "as " | "at " | "positioned " | "facing " | "rotated " | "anchored "
+                    | "in " => {
+                        let subcommand = built_str.trim().to_string();
+                        built_str.clear();
+                        let associate = self.mk_association(
+                            &original_line,
+                            starting_column,
+                            self.column,
+                            self.line,
+                        );
+                        let (chain, forwardness) = self.read_until("{", associate.clone());
+                        self.column += forwardness;
+                        let subcommands =
+                            self.execute_chain(&format!("{} {}", subcommand, chain), associate);
+                        self.tokens.push(Tokens::Execute(
+                            subcommands,
+                            self.mk_association(
+                                &original_line,
+                                starting_column,
+                                self.column,
+                                self.line,
+                            ),
+                        ));
+                    }
+                    "on " => {
+                        built_str.clear();
+                        let associate = self.mk_association(
+                            &original_line,
+                            starting_column,
+                            self.column,
+                            self.line,
+                        );
+                        let (handler, forwardness) = self.read_until("{", associate.clone());
+                        self.column += forwardness;
+                        // the first word names the event, e.g. `on trigger "menu" (player, value)`
+                        let handler = handler.trim();
+                        let (event, arguments) = handler.split_once(' ').unwrap_or((handler, ""));
+                        let mut lexer = self.nested(arguments.trim().to_string());
+                        lexer.tokenizer();
+                        let mut tokens = lexer.flush().to_vec();
+                        // remove the last 2 tokens as those are just EOL EOF
+                        tokens.remove(tokens.len() - 1);
+                        tokens.remove(tokens.len() - 1);
+                        self.tokens.push(Tokens::On(
+                            event.to_string(),
+                            tokens,
+                            self.mk_association(
+                                &original_line,
+                                starting_column,
+                                self.column,
+                                self.line,
+                            ),
+                        ));
+                    }
+                    "block " => {
+                        built_str.clear();
+                        let associate = self.mk_association(
+                            &original_line,
+                            starting_column,
+                            self.column,
+                            self.line,
+                        );
+                        let (rest, _) = self.read_until_end();
+                        // the position of a block predicate ends at its comparison
+                        let end = [rest.find("=="), rest.find("!=")]
+                            .into_iter()
+                            .flatten()
+                            .min();
+                        if end.is_none() {
+                            std_error(StdErrors::Syntax(
+                                "Expected: == or != after block position".to_string(),
+                                associate,
+                            ));
+                            exit(1);
+                        }
+                        let position = rest[..end.unwrap()].to_string();
+                        self.column += position.chars().count();
+                        let mut lexer = self.nested(position.trim().to_string());
+                        lexer.tokenizer();
+                        let mut tokens = lexer.flush().to_vec();
+                        // remove the last 2 tokens as those are just EOL EOF
+                        tokens.remove(tokens.len() - 1);
+                        tokens.remove(tokens.len() - 1);
+                        self.tokens.push(Tokens::Block(
+                            tokens,
+                            self.mk_association(
+                                &original_line,
+                                starting_column,
+                                self.column,
+                                self.line,
+                            ),
+                        ));
+                    }
+                    "@" => {
+                        built_str.clear();
+                        let mut selector = String::from("@");
+                        let mut tracked_col = 0;
+                        while self.peek(tracked_col + 1).is_ascii_alphabetic() {
+                            tracked_col += 1;
+                            selector.push(self.peek(tracked_col));
+                        }
+                        self.column += tracked_col;
+                        if self.peek(1) == '[' {
+                            self.column += 1;
+                            let (arguments, forwardness) = self.read_until_last(
+                                '[',
+                                ']',
+                                self.mk_association(
+                                    &original_line,
+                                    starting_column,
+                                    self.column,
+                                    self.line,
+                                ),
+                            );
+                            self.column += forwardness;
+                            selector.push_str(&format!("[{}]", arguments));
+                        }
+                        self.tokens.push(Tokens::Selector(
+                            selector,
+                            self.mk_association(
+                                &original_line,
+                                starting_column,
+                                self.column,
+                                self.line,
+                            ),
+                        ));
+                    }
+                    "~" | "^" => {
+                        let mut coordinate = built_str.trim().to_string();
+                        built_str.clear();
+                        let mut tracked_col = 0;
+                        while self.peek(tracked_col + 1).is_ascii_digit()
+                            || self.peek(tracked_col + 1) == '.'
+                            || self.peek(tracked_col + 1) == '-'
+                        {
+                            tracked_col += 1;
+                            coordinate.push(self.peek(tracked_col));
+                        }
+                        self.column += tracked_col;
+                        self.tokens.push(Tokens::Coordinate(
+                            coordinate,
+                            self.mk_association(
+                                &original_line,
+                                starting_column,
+                                self.column,
+                                self.line,
+                            ),
+                        ));
+                    }
                     "fn " => {
                         built_str.clear();
                         let (function_name, forwardness) = self.read_until(
@@ -358,8 +657,7 @@ impl Lexer {
                             ),
                         );
                         self.column += forwardness;
-                        let mut lexer =
-                            Lexer::new(function_args.trim().to_string(), self.file_name.clone());
+                        let mut lexer = self.nested(function_args.trim().to_string());
                         lexer.tokenizer();
                         let mut tokens = lexer.flush().to_vec();
                         // remove the last 2 tokens as those are just EOL EOF
@@ -376,6 +674,28 @@ impl Lexer {
                             ),
                         ));
                     }
+                    "struct " => {
+                        built_str.clear();
+                        let (struct_name, forwardness) = self.read_until(
+                            "{",
+                            self.mk_association(
+                                &original_line,
+                                starting_column,
+                                self.column,
+                                self.line,
+                            ),
+                        );
+                        self.column += forwardness;
+                        self.tokens.push(Tokens::Struct(
+                            struct_name.trim().to_string(),
+                            self.mk_association(
+                                &original_line,
+                                starting_column,
+                                self.column,
+                                self.line,
+                            ),
+                        ));
+                    }
                     "while " => {
                         built_str.clear();
                         let (statements, forwardness) = self.read_until(
@@ -393,8 +713,7 @@ impl Lexer {
                         // make parser just get the name
                         let name_statement = format!("{} =", statements[0]);
                         // name parser
-                        let mut lexer =
-                            Lexer::new(name_statement.trim().to_string(), self.file_name.clone());
+                        let mut lexer = self.nested(name_statement.trim().to_string());
                         lexer.tokenizer();
                         let mut tokens = lexer.flush().to_vec();
                         tokens.remove(tokens.len() - 1);
@@ -412,8 +731,7 @@ impl Lexer {
                             eprintln!("Missing let token name.");
                             exit(1);
                         }
-                        let mut lexer =
-                            Lexer::new(statements[1].trim().to_string(), self.file_name.clone());
+                        let mut lexer = self.nested(statements[1].trim().to_string());
                         lexer.tokenizer();
                         let mut tokens = lexer.flush().to_vec();
                         tokens.remove(tokens.len() - 1);
@@ -429,6 +747,46 @@ impl Lexer {
                             ),
                         ));
                     }
+                    "for " => {
+                        built_str.clear();
+                        let associate = self.mk_association(
+                            &original_line,
+                            starting_column,
+                            self.column,
+                            self.line,
+                        );
+                        let (statements, forwardness) = self.read_until("{", associate.clone());
+                        self.column += forwardness;
+                        // for item in list { ... }
+                        let statements = statements.splitn(2, " in ").collect::<Vec<&str>>();
+                        let name = statements[0].trim();
+                        if statements.len() != 2
+                            || name.is_empty()
+                            || !name.chars().all(|c| c.is_alphanumeric() || c == '_')
+                        {
+                            std_error(StdErrors::Syntax(
+                                "Expected: for <name> in <list>".to_string(),
+                                associate,
+                            ));
+                            exit(1);
+                        }
+                        let mut lexer = self.nested(statements[1].trim().to_string());
+                        lexer.tokenizer();
+                        let mut tokens = lexer.flush().to_vec();
+                        // remove the last 2 tokens as those are just EOL EOF
+                        tokens.remove(tokens.len() - 1);
+                        tokens.remove(tokens.len() - 1);
+                        self.tokens.push(Tokens::For(
+                            name.to_string(),
+                            tokens,
+                            self.mk_association(
+                                &original_line,
+                                starting_column,
+                                self.column,
+                                self.line,
+                            ),
+                        ));
+                    }
                     "{" => {
                         built_str.clear();
                         self.tokens.push(Tokens::LBrace(self.mk_association(
@@ -460,8 +818,7 @@ impl Lexer {
                             ),
                         );
                         self.column += forwardness;
-                        let mut lexer =
-                            Lexer::new(boolean.trim().to_string(), self.file_name.clone());
+                        let mut lexer = self.nested(boolean.trim().to_string());
                         lexer.tokenizer();
                         let mut tokens = lexer.flush().to_vec();
                         // remove the last 2 tokens as those are just EOL EOF
@@ -479,15 +836,12 @@ impl Lexer {
                     }
                     "\"" => {
                         built_str.clear();
-                        let (string, forwardness) = self.read_until(
-                            "\"",
-                            self.mk_association(
-                                &original_line,
-                                starting_column,
-                                self.column,
-                                self.line,
-                            ),
-                        );
+                        let (string, forwardness) = self.read_string(self.mk_association(
+                            &original_line,
+                            starting_column,
+                            self.column,
+                            self.line,
+                        ));
                         self.column += forwardness + 1;
                         self.tokens.push(Tokens::DblQuote(
                             string,
@@ -513,8 +867,7 @@ impl Lexer {
                         );
 
                         self.column += forwardness;
-                        let mut lexer =
-                            Lexer::new(inside_parens.trim().to_string(), self.file_name.clone());
+                        let mut lexer = self.nested(inside_parens.trim().to_string());
                         lexer.tokenizer();
                         let mut tokens = lexer.flush().to_vec();
                         // remove the last 2 tokens as those are just EOL EOF
@@ -556,8 +909,7 @@ impl Lexer {
                             ),
                         );
                         self.column += forwardness;
-                        let mut lexer =
-                            Lexer::new(inside_parens.trim().to_string(), self.file_name.clone());
+                        let mut lexer = self.nested(inside_parens.trim().to_string());
                         lexer.tokenizer();
                         let mut tokens = lexer.flush().to_vec();
                         // remove the last 2 tokens as those are just EOL EOF
@@ -579,29 +931,26 @@ impl Lexer {
                         built_str.clear();
                         let (mut statements, forwardness) = self.read_until_end();
                         // exclude the semicolon
-                        // remove operands starting at && and to the end
+                        // remove operands and argument separators starting at && and to the end
                         let mut point_to_grab = forwardness;
-                        for state in [
-                            "&&", "||", "==", ">", "<", ">=", "<=", "+", "-", "*", "/", "%", "^",
-                        ] {
-                            if statements.contains(state) {
-                                point_to_grab = statements.find(state).unwrap();
-                                statements = statements.split_at(point_to_grab).0.to_string();
-                            }
+                        if let Some(end) = expression_end(&statements) {
+                            point_to_grab = statements[..end].chars().count();
+                            statements = statements[..end].to_string();
                         }
 
                         // remove point to grab from forwardness
                         let forwardness = point_to_grab;
                         // let statements = statements.split_at(point_to_grab).0;
-                        println!("Statements: {}", statements);
+                        if self.verbose {
+                            println!("Statements: {}", statements);
+                        }
                         let rev_string = statements.chars().rev().collect::<String>();
                         let has_semicolon = rev_string.trim().chars().next() == Some(';');
                         let statements: String =
                             rev_string.replacen(';', "", 1).chars().rev().collect();
 
                         self.column += forwardness;
-                        let mut lexer =
-                            Lexer::new(statements.trim().to_string(), self.file_name.clone());
+                        let mut lexer = self.nested(statements.trim().to_string());
                         lexer.tokenizer();
                         let mut tokens = lexer.flush().to_vec();
                         // remove the last 2 tokens as those are just EOL EOF
@@ -663,12 +1012,50 @@ impl Lexer {
                     }
                     "-" => {
                         built_str.clear();
-                        self.tokens.push(Tokens::Subtract(self.mk_association(
-                            &original_line,
-                            starting_column,
-                            self.column,
-                            self.line,
-                        )));
+                        // a minus before a number that does not follow a value is a negative literal
+                        let follows_value = matches!(
+                            self.tokens.last(),
+                            Some(
+                                Tokens::Number(..)
+                                    | Tokens::Coordinate(..)
+                                    | Tokens::Symbol(..)
+                                    | Tokens::Selector(..)
+                                    | Tokens::DblQuote(..)
+                                    | Tokens::Bool(..)
+                                    | Tokens::Parens(..)
+                                    | Tokens::Bracket(..)
+                                    | Tokens::Period(..)
+                            )
+                        );
+                        if self.peek(1).is_ascii_digit() && !follows_value {
+                            let mut number = String::from("-");
+                            let mut tracked_col = 0;
+                            while self.peek(tracked_col + 1).is_ascii_digit()
+                                || (self.peek(tracked_col + 1) == '.'
+                                    && self.peek(tracked_col + 2).is_ascii_digit()
+                                    && !number.contains('.'))
+                            {
+                                tracked_col += 1;
+                                number.push(self.peek(tracked_col));
+                            }
+                            self.column += tracked_col;
+                            self.tokens.push(Tokens::Number(
+                                number,
+                                self.mk_association(
+                                    &original_line,
+                                    starting_column,
+                                    self.column,
+                                    self.line,
+                                ),
+                            ));
+                        } else {
+                            self.tokens.push(Tokens::Subtract(self.mk_association(
+                                &original_line,
+                                starting_column,
+                                self.column,
+                                self.line,
+                            )));
+                        }
                     }
                     "*" => {
                         built_str.clear();
@@ -679,6 +1066,26 @@ impl Lexer {
                             self.line,
                         )));
                     }
+                    "/" if self.at_statement_start() => {
+                        // a raw command, such as `/say hi`, runs to the end of the line
+                        built_str.clear();
+                        let (command, forwardness) = self.read_until_end();
+                        self.column += forwardness;
+                        let command = command.trim();
+                        self.tokens.push(Tokens::Command(
+                            command
+                                .strip_suffix(';')
+                                .unwrap_or(command)
+                                .trim()
+                                .to_string(),
+                            self.mk_association(
+                                &original_line,
+                                starting_column,
+                                self.column,
+                                self.line,
+                            ),
+                        ));
+                    }
                     "/" => {
                         built_str.clear();
                         self.tokens.push(Tokens::Divide(self.mk_association(
@@ -737,7 +1144,18 @@ impl Lexer {
                     // continue peeking until it is not a number
                     let mut okay_number = String::new();
                     let mut tracked_col = 0usize;
-                    while built_str.trim().parse::<i32>().is_ok() {
+                    while built_str.trim().parse::<i32>().is_ok()
+                        || is_decimal(built_str.trim())
+                        // keep reading a decimal point when a digit follows it
+                        || (built_str.ends_with('.')
+                            && !built_str.trim().trim_end_matches('.').contains('.')
+                            && self.peek(tracked_col + 1).is_ascii_digit())
+                    {
+                        if built_str.ends_with('.') {
+                            tracked_col += 1;
+                            built_str.push(self.peek(tracked_col));
+                            continue;
+                        }
                         okay_number = String::from(&built_str);
                         tracked_col += 1;
                         let token = self.peek(tracked_col);
@@ -762,6 +1180,7 @@ impl Lexer {
                 // now peek to see if this is just a big symbol
                 let char = self.peek(1);
                 if built_str.trim().len() > 0
+                    && !(built_str.trim() == "!" && char == '=')
                     && (self.column == self.raw_tokens[self.line - 1].len()
                         || char == '.'
                         || char == '('
@@ -799,8 +1218,10 @@ impl Lexer {
             }
             self.tokens.push(Tokens::EOL);
             if built_str.trim().len() > 0 {
-                println!("{:?}", self.tokens);
-                std_error(StdErrors::SyntaxError(
+                if self.verbose {
+                    println!("{:?}", self.tokens);
+                }
+                std_error(StdErrors::Syntax(
                     "Unknown token".to_string(),
                     empty_associate(),
                 ));
@@ -810,6 +1231,9 @@ impl Lexer {
         }
 
         self.tokens.push(Tokens::EOF);
+        if !self.verbose {
+            return;
+        }
         // pretty print
         println!("\n--\n");
         for token in &self.tokens {
@@ -827,9 +1251,62 @@ impl Lexer {
 pub fn empty_associate() -> CodeAssociate {
     CodeAssociate {
         lines: "".to_string(),
-        file: "".to_string(),
+        file: NO_FILE,
         line: 0,
         start_column: 0,
         end_column: 0,
     }
 }
+
+fn is_decimal(str: &str) -> bool {
+    match str.split_once('.') {
+        Some((whole, fraction)) => {
+            !whole.is_empty()
+                && !fraction.is_empty()
+                && whole.chars().all(|c| c.is_ascii_digit())
+                && fraction.chars().all(|c| c.is_ascii_digit())
+        }
+        None => false,
+    }
+}
+
+/// Finds where an access such as `pos.offset(0, 1, 0)` ends: the first operator or argument
+/// separator that is not nested inside parentheses, brackets, braces or a string. An
+/// assignment such as `p.health = p.health + 1` takes the whole statement.
+fn expression_end(str: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, char) in str.char_indices() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if char == '\\' {
+                escaped = true;
+            } else if char == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match char {
+            '"' => in_string = true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            _ if depth == 0 => {
+                let rest = &str[index..];
+                if rest.starts_with('=') && !rest.starts_with("==") {
+                    return None;
+                }
+                for state in [
+                    "&&", "||", "==", "!=", ">", "<", "+", "-", "*", "/", "%", "^", ",",
+                ] {
+                    if rest.starts_with(state) {
+                        return Some(index);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
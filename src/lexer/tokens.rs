@@ -9,6 +9,7 @@ pub enum Tokens {
     SemiColon(CodeAssociate),
 
     Number(String, CodeAssociate),
+    Coordinate(String, CodeAssociate),
     DblQuote(String, CodeAssociate),
     Bool(bool, CodeAssociate),
 
@@ -24,6 +25,7 @@ pub enum Tokens {
 
     If(Vec<Tokens>, CodeAssociate),
     While(String, Vec<Tokens>, CodeAssociate),
+    For(String, Vec<Tokens>, CodeAssociate),
     And(CodeAssociate),
     Or(CodeAssociate),
     Comma(CodeAssociate),
@@ -37,7 +39,15 @@ pub enum Tokens {
     NotEqual(CodeAssociate),
     New(String, Vec<Tokens>, CodeAssociate),
     Export(CodeAssociate),
+    /// The rest of `export { a, b }` or `export * from "x"`.
+    ExportList(String, CodeAssociate),
     Import(String, CodeAssociate),
+    Selector(String, CodeAssociate),
+    Block(Vec<Tokens>, CodeAssociate),
+    Execute(Vec<(String, Vec<Tokens>)>, CodeAssociate),
+    On(String, Vec<Tokens>, CodeAssociate),
+    Command(String, CodeAssociate),
+    Struct(String, CodeAssociate),
 
     EOL,
     EOF,
@@ -1,4 +1,5 @@
 mod executor;
+mod manifest;
 
 mod lexer {
     pub mod lexer;
@@ -12,19 +13,36 @@ mod ast {
 mod compile {
     mod obj {
         pub mod basic;
+        pub mod block;
         pub mod blockpos;
+        pub mod dimension;
         pub mod entity;
+        pub mod item;
+        pub mod list;
         pub mod scoreboard;
         pub mod std;
+        pub mod storage;
+        pub mod structs;
+        pub mod text;
+        pub mod world;
     }
+    pub mod checker;
+    pub mod commands;
     pub mod compiler;
+    pub mod macros;
     pub mod mcstatements;
+    pub mod modules;
+    pub mod names;
+    pub mod nbt;
     pub mod objects;
+    pub mod symbols;
+    pub mod version;
 }
 
 mod errors {
     pub mod associate;
     pub mod error;
+    pub mod sources;
 }
 
 fn main() {
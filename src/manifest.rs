@@ -0,0 +1,326 @@
+use std::{fs, path::Path};
+
+use crate::compile::names::Allocation;
+
+/// The project manifest, `mclang.toml`, next to the `inputs` directory:
+///
+/// ```toml
+/// [package]
+/// namespace = "game"
+/// allocation = "registers"
+///
+/// [dependencies]
+/// math = { path = "../math" }
+/// ui = { archive = "vendor/ui.tar", bundle = false }
+/// ```
+pub struct Manifest {
+    /// The namespace of the datapack's functions.
+    pub namespace: String,
+    /// How variables are laid out in scores.
+    pub allocation: Allocation,
+    pub dependencies: Vec<Dependency>,
+}
+
+/// Another mclang project whose modules are imported as `"<name>:<module>"`.
+pub struct Dependency {
+    /// The name it is imported by, which is also the namespace of its functions.
+    pub name: String,
+    pub source: DependencySource,
+    /// Whether its functions are written into this datapack. Otherwise they are left to its own
+    /// datapack, which has to be built with the same namespace and installed alongside, and only
+    /// its exported functions taking numbers and booleans can be called.
+    pub bundle: bool,
+}
+
+pub enum DependencySource {
+    /// The directory of the project.
+    Path(String),
+    /// An uncompressed tar archive of the project.
+    Archive(String),
+}
+
+impl Manifest {
+    /// Reads the manifest in a directory. A project without one uses the `test` namespace, an
+    /// objective per variable and has no dependencies.
+    pub fn read(directory: &Path) -> Result<Manifest, String> {
+        let path = directory.join("mclang.toml");
+        if !path.exists() {
+            return Manifest::parse("");
+        }
+        let contents =
+            fs::read_to_string(&path).map_err(|_| "Failed to read mclang.toml".to_string())?;
+        Manifest::parse(&contents)
+    }
+
+    /// Reads the contents of a manifest.
+    fn parse(contents: &str) -> Result<Manifest, String> {
+        let mut manifest = Manifest {
+            namespace: "test".to_string(),
+            allocation: Allocation::Objectives,
+            dependencies: vec![],
+        };
+        let mut section = String::new();
+        for (number, line) in contents.lines().enumerate() {
+            let line = split_unquoted(line, '#')[0].trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                section = name.trim().to_string();
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("Expected `key = value` on line {}", number + 1));
+            };
+            let (key, value) = (key.trim(), value.trim());
+            match (section.as_str(), key) {
+                ("package", "namespace") => {
+                    manifest.namespace = namespace(&string(value, number)?, number)?
+                }
+                ("package", "allocation") => {
+                    let allocation = string(value, number)?;
+                    manifest.allocation = Allocation::parse(&allocation).ok_or(format!(
+                        "Unknown allocation {}, expected objectives or registers",
+                        allocation
+                    ))?;
+                }
+                ("package", _) => return Err(format!("Unknown package setting {}", key)),
+                ("dependencies", _) => {
+                    let name = namespace(key, number)?;
+                    manifest
+                        .dependencies
+                        .push(dependency(&name, value, number)?)
+                }
+                _ => return Err(format!("Unknown section [{}]", section)),
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+/// Splits a line at every separator that is not inside of a quoted string.
+fn split_unquoted(line: &str, separator: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (index, char) in line.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if quoted && char == '\\' {
+            escaped = true;
+        } else if char == '"' {
+            quoted = !quoted;
+        } else if char == separator && !quoted {
+            parts.push(&line[start..index]);
+            start = index + char.len_utf8();
+        }
+    }
+    parts.push(&line[start..]);
+    parts
+}
+
+/// A quoted string value.
+fn string(value: &str, number: usize) -> Result<String, String> {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .map(|value| value.to_string())
+        .ok_or(format!("Expected a quoted string on line {}", number + 1))
+}
+
+/// A namespace, which datapacks only allow to have lowercase letters, digits, `_`, `-` and `.`.
+fn namespace(name: &str, number: usize) -> Result<String, String> {
+    let valid = |char: char| matches!(char, 'a'..='z' | '0'..='9' | '_' | '-' | '.');
+    if name.is_empty() || !name.chars().all(valid) {
+        return Err(format!("Invalid namespace {} on line {}", name, number + 1));
+    }
+    Ok(name.to_string())
+}
+
+/// A dependency such as `math = { path = "../math", bundle = false }`.
+fn dependency(name: &str, value: &str, number: usize) -> Result<Dependency, String> {
+    let Some(table) = value
+        .strip_prefix('{')
+        .and_then(|value| value.strip_suffix('}'))
+    else {
+        return Err(format!(
+            "Expected `{{ path = \"...\" }}` on line {}",
+            number + 1
+        ));
+    };
+    let mut source = None;
+    let mut bundle = true;
+    for entry in split_unquoted(table, ',')
+        .into_iter()
+        .filter(|entry| !entry.trim().is_empty())
+    {
+        let Some((key, value)) = entry.split_once('=') else {
+            return Err(format!("Expected `key = value` on line {}", number + 1));
+        };
+        match key.trim() {
+            "path" => source = Some(DependencySource::Path(string(value.trim(), number)?)),
+            "archive" => source = Some(DependencySource::Archive(string(value.trim(), number)?)),
+            "bundle" => {
+                bundle = match value.trim() {
+                    "true" => true,
+                    "false" => false,
+                    _ => {
+                        return Err(format!(
+                            "Expected true or false for bundle on line {}",
+                            number + 1
+                        ))
+                    }
+                }
+            }
+            key => return Err(format!("Unknown dependency setting {}", key)),
+        }
+    }
+    let Some(source) = source else {
+        return Err(format!("Dependency {} needs a path or an archive", name));
+    };
+    Ok(Dependency {
+        name: name.to_string(),
+        source,
+        bundle,
+    })
+}
+
+/// The `.mc` files of the `inputs` directory inside of an uncompressed tar archive, by their
+/// path inside of that directory.
+pub fn archive_inputs(archive: &[u8]) -> Result<Vec<(String, String)>, String> {
+    let mut files = vec![];
+    let mut offset = 0;
+    while offset + 512 <= archive.len() {
+        let header = &archive[offset..offset + 512];
+        if header.iter().all(|byte| *byte == 0) {
+            break;
+        }
+        let field = |start: usize, end: usize| {
+            String::from_utf8_lossy(&header[start..end])
+                .trim_end_matches('\0')
+                .to_string()
+        };
+        // ustar splits long names into a prefix and a name
+        let name = match field(345, 500) {
+            prefix if prefix.is_empty() => field(0, 100),
+            prefix => format!("{}/{}", prefix, field(0, 100)),
+        };
+        let size = usize::from_str_radix(field(124, 136).trim(), 8)
+            .map_err(|_| format!("Invalid size of {} in the archive", name))?;
+        let contents = archive
+            .get(offset + 512..offset + 512 + size)
+            .ok_or(format!("{} is cut off in the archive", name))?;
+        // the first directory called `inputs`, not one merely ending in it
+        let parts: Vec<&str> = name.split('/').collect();
+        let inputs = parts[..parts.len() - 1]
+            .iter()
+            .position(|part| *part == "inputs");
+        let file = inputs.map(|inputs| parts[inputs + 1..].join("/"));
+        if let Some(file) = file.filter(|file| file.ends_with(".mc")) {
+            files.push((file, String::from_utf8_lossy(contents).to_string()));
+        }
+        offset += 512 + size.div_ceil(512) * 512;
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_without_a_manifest() {
+        let manifest = Manifest::parse("").unwrap();
+        assert_eq!(manifest.namespace, "test");
+        assert_eq!(manifest.allocation, Allocation::Objectives);
+        assert!(manifest.dependencies.is_empty());
+    }
+
+    #[test]
+    fn reads_packages_and_dependencies() {
+        let manifest = Manifest::parse(
+            "[package]\nnamespace = \"game\" # the datapack\nallocation = \"registers\"\n\n[dependencies]\nmath = { path = \"../math\" }\nui = { archive = \"vendor/ui.tar\", bundle = false }\n",
+        )
+        .unwrap();
+        assert_eq!(manifest.namespace, "game");
+        assert_eq!(manifest.allocation, Allocation::Registers);
+        let [math, ui] = manifest.dependencies.as_slice() else {
+            panic!("Expected 2 dependencies");
+        };
+        assert_eq!(math.name, "math");
+        assert!(matches!(&math.source, DependencySource::Path(path) if path == "../math"));
+        assert!(math.bundle);
+        assert_eq!(ui.name, "ui");
+        assert!(matches!(&ui.source, DependencySource::Archive(path) if path == "vendor/ui.tar"));
+        assert!(!ui.bundle);
+    }
+
+    #[test]
+    fn keeps_separators_inside_of_strings() {
+        assert_eq!(
+            Manifest::parse("[package]\nnamespace = \"a#b\"\n")
+                .err()
+                .unwrap(),
+            "Invalid namespace a#b on line 2"
+        );
+        let manifest = Manifest::parse(
+            "[package]\nnamespace = \"a.b\"\n[dependencies]\nlib = { path = \"libs/#1, v2\", bundle = true }\n",
+        )
+        .unwrap();
+        assert_eq!(manifest.namespace, "a.b");
+        assert!(matches!(
+            &manifest.dependencies[0].source,
+            DependencySource::Path(path) if path == "libs/#1, v2"
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_settings() {
+        let error = |contents: &str| Manifest::parse(contents).err().unwrap();
+        assert_eq!(
+            error("[dependencies]\nmath = { path = \"../math\", bundle = yes }\n"),
+            "Expected true or false for bundle on line 2"
+        );
+        assert_eq!(
+            error("[dependencies]\nmath = { bundle = false }\n"),
+            "Dependency math needs a path or an archive"
+        );
+        assert_eq!(
+            error("[package]\nnamespace = game\n"),
+            "Expected a quoted string on line 2"
+        );
+        assert_eq!(error("[server]\nport = 1\n"), "Unknown section [server]");
+        assert_eq!(
+            error("[dependencies]\nMath = { path = \"../math\" }\n"),
+            "Invalid namespace Math on line 2"
+        );
+    }
+
+    #[test]
+    fn reads_the_inputs_directory_of_archives() {
+        let mut archive = vec![];
+        for (name, contents) in [
+            ("ui/inputs/widgets/button.mc", "let a = 1;"),
+            ("ui/myinputs/code.mc", "let b = 2;"),
+            ("ui/inputs/notes.txt", "notes"),
+        ] {
+            let mut header = [0u8; 512];
+            header[..name.len()].copy_from_slice(name.as_bytes());
+            let size = format!("{:011o}", contents.len());
+            header[124..135].copy_from_slice(size.as_bytes());
+            archive.extend(header);
+            let mut data = contents.as_bytes().to_vec();
+            data.resize(512, 0);
+            archive.extend(data);
+        }
+        archive.extend([0u8; 1024]);
+        assert_eq!(
+            archive_inputs(&archive).unwrap(),
+            vec![("widgets/button.mc".to_string(), "let a = 1;".to_string())]
+        );
+    }
+}